@@ -0,0 +1,231 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use jatsi_shared::errors::InvalidLogin;
+use jatsi_shared::protocol::{Account, ClientMessage, ServerMessage};
+use log::{info, warn};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::database::Database;
+use crate::lobby::Session;
+use crate::room::send;
+
+/// How many times an address can try to log in or register per `ATTEMPT_WINDOW`.
+const ATTEMPTS_PER_ADDRESS: usize = 20;
+/// How many times anyone can try to log in to an account per `ATTEMPT_WINDOW`.
+const ATTEMPTS_PER_USERNAME: usize = 5;
+const ATTEMPT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Counts attempts by key over a sliding window.
+struct RateLimit {
+  limit: usize,
+  attempts: HashMap<String, VecDeque<Instant>>,
+}
+
+impl RateLimit {
+  fn new(limit: usize) -> Self {
+    Self {
+      limit,
+      attempts: HashMap::new(),
+    }
+  }
+
+  /// Records an attempt by `key`, returning whether it is within the limit.
+  fn allow(&mut self, key: &str, now: Instant) -> bool {
+    // Forgetting old attempts of every key keeps the map from growing
+    self.attempts.retain(|_, times| {
+      while times
+        .front()
+        .is_some_and(|&time| now.duration_since(time) >= ATTEMPT_WINDOW)
+      {
+        times.pop_front();
+      }
+      !times.is_empty()
+    });
+
+    let times = self.attempts.entry(key.to_string()).or_default();
+    if times.len() >= self.limit {
+      return false;
+    }
+    times.push_back(now);
+    true
+  }
+}
+
+/// Registering, logging in and out. Hashing passwords is slow on purpose, so accounts have a database connection of
+/// their own and do their work on the blocking threads instead of under the lock of the lobby.
+pub struct Accounts {
+  database: Mutex<Database>,
+  by_address: Mutex<RateLimit>,
+  by_username: Mutex<RateLimit>,
+}
+
+impl Accounts {
+  pub fn new(database: Database) -> Self {
+    Self {
+      database: Mutex::new(database),
+      by_address: Mutex::new(RateLimit::new(ATTEMPTS_PER_ADDRESS)),
+      by_username: Mutex::new(RateLimit::new(ATTEMPTS_PER_USERNAME)),
+    }
+  }
+
+  /// Whether the message is about accounts, to be handled here rather than by the lobby.
+  pub fn handles(msg: &ClientMessage) -> bool {
+    matches!(
+      msg,
+      ClientMessage::Register { .. }
+        | ClientMessage::LogIn { .. }
+        | ClientMessage::Authenticate(..)
+        | ClientMessage::LogOut
+    )
+  }
+
+  /// Processes an account message from a connection at `address`.
+  pub async fn handle(
+    self: &Arc<Self>,
+    session: &mut Session,
+    sender: &UnboundedSender<Message>,
+    address: IpAddr,
+    msg: ClientMessage,
+  ) {
+    let now = Instant::now();
+    let result = match msg {
+      ClientMessage::Register { username, password } => {
+        if !self
+          .by_address
+          .lock()
+          .unwrap()
+          .allow(&address.to_string(), now)
+        {
+          Err(InvalidLogin::TooManyAttempts)
+        } else {
+          self
+            .blocking(move |database| database.register(&username, &password))
+            .await
+        }
+      }
+      ClientMessage::LogIn { username, password } => {
+        let username_key = username.trim().to_lowercase();
+        if !self
+          .by_address
+          .lock()
+          .unwrap()
+          .allow(&address.to_string(), now)
+          || !self.by_username.lock().unwrap().allow(&username_key, now)
+        {
+          Err(InvalidLogin::TooManyAttempts)
+        } else {
+          self
+            .blocking(move |database| database.log_in(&username, &password))
+            .await
+        }
+      }
+      ClientMessage::Authenticate(token) => {
+        self
+          .blocking(move |database| {
+            database
+              .authenticate(&token)
+              .map(|account| (account, token))
+          })
+          .await
+      }
+      ClientMessage::LogOut => {
+        if let Some(token) = session.log_out() {
+          let revoked = self
+            .blocking(move |database| database.revoke_token(&token))
+            .await;
+          if let Err(err) = revoked {
+            return send(sender, &ServerMessage::LoginRejected(err));
+          }
+        }
+        return send(sender, &ServerMessage::LoggedOut);
+      }
+      other => {
+        warn!("Not an account message: {:?}", other);
+        return;
+      }
+    };
+    log_in(session, sender, result);
+  }
+
+  /// Runs database work on the blocking threads.
+  async fn blocking<T, F>(self: &Arc<Self>, work: F) -> Result<T, InvalidLogin>
+  where
+    T: Send + 'static,
+    F: FnOnce(&Database) -> Result<T, InvalidLogin> + Send + 'static,
+  {
+    let accounts = self.clone();
+    tokio::task::spawn_blocking(move || work(&accounts.database.lock().unwrap()))
+      .await
+      .unwrap_or_else(|err| {
+        warn!("An account task failed: {}", err);
+        Err(InvalidLogin::Unavailable)
+      })
+  }
+}
+
+fn log_in(
+  session: &mut Session,
+  sender: &UnboundedSender<Message>,
+  result: Result<(Account, String), InvalidLogin>,
+) {
+  match result {
+    Ok((account, token)) => {
+      info!("{} logged in", account.username);
+      session.log_in(account.clone(), token.clone());
+      send(sender, &ServerMessage::LoggedIn { account, token });
+    }
+    Err(err) => send(sender, &ServerMessage::LoginRejected(err)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::sync::mpsc::unbounded_channel;
+
+  #[test]
+  fn test_rate_limit() {
+    let mut limit = RateLimit::new(2);
+    let start = Instant::now();
+    assert!(limit.allow("henry", start));
+    assert!(limit.allow("henry", start));
+    assert!(!limit.allow("henry", start));
+    assert!(limit.allow("bobby", start), "keys are limited separately");
+    assert!(
+      limit.allow("henry", start + ATTEMPT_WINDOW),
+      "old attempts are forgotten"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_too_many_attempts() {
+    let accounts = Arc::new(Accounts::new(Database::in_memory().unwrap()));
+    let (sender, mut receiver) = unbounded_channel::<Message>();
+    let mut session = Session::default();
+    for _ in 0..ATTEMPTS_PER_USERNAME {
+      accounts
+        .by_username
+        .lock()
+        .unwrap()
+        .allow("henry", Instant::now());
+    }
+
+    let log_in = ClientMessage::LogIn {
+      username: " Henry".into(),
+      password: "hunter22".into(),
+    };
+    accounts
+      .handle(&mut session, &sender, IpAddr::from([127, 0, 0, 1]), log_in)
+      .await;
+    let reply: ServerMessage =
+      serde_json::from_str(receiver.try_recv().unwrap().to_text().unwrap()).unwrap();
+    assert_eq!(
+      reply,
+      ServerMessage::LoginRejected(InvalidLogin::TooManyAttempts)
+    );
+  }
+}
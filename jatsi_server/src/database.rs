@@ -0,0 +1,622 @@
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use jatsi_shared::errors::InvalidLogin;
+use jatsi_shared::game::SavedGame;
+use jatsi_shared::protocol::Account;
+use jatsi_shared::ratings::{rating_changes, Leaderboard, LeaderboardEntry, INITIAL_RATING};
+use jatsi_shared::rules::Ruleset;
+use log::warn;
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+const TOKEN_BYTES: usize = 32;
+/// How long a login lasts, in milliseconds.
+const TOKEN_LIFETIME: i64 = 30 * 24 * 60 * 60 * 1000;
+/// How long to wait for another connection writing to the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+  CREATE TABLE IF NOT EXISTS accounts (
+    id INTEGER PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    password_hash TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+  );
+  CREATE TABLE IF NOT EXISTS tokens (
+    token_hash TEXT PRIMARY KEY,
+    account_id INTEGER NOT NULL REFERENCES accounts (id),
+    created_at INTEGER NOT NULL
+  );
+  CREATE TABLE IF NOT EXISTS games (
+    id INTEGER PRIMARY KEY,
+    finished_at INTEGER NOT NULL,
+    saved_game TEXT NOT NULL
+  );
+  CREATE TABLE IF NOT EXISTS game_players (
+    game_id INTEGER NOT NULL REFERENCES games (id),
+    player INTEGER NOT NULL,
+    account_id INTEGER REFERENCES accounts (id),
+    PRIMARY KEY (game_id, player)
+  );
+  CREATE TABLE IF NOT EXISTS rulesets (
+    key TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    ruleset TEXT NOT NULL
+  );
+  CREATE TABLE IF NOT EXISTS ratings (
+    ruleset_key TEXT NOT NULL REFERENCES rulesets (key),
+    account_id INTEGER NOT NULL REFERENCES accounts (id),
+    rating REAL NOT NULL,
+    games INTEGER NOT NULL,
+    PRIMARY KEY (ruleset_key, account_id)
+  );
+";
+
+/// Milliseconds since the Unix epoch.
+pub fn now() -> i64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|since_epoch| since_epoch.as_millis() as i64)
+    .unwrap_or(0)
+}
+
+/// Only the hash of a token is stored, so that a leaked database does not let anyone log in.
+fn hash_token(token: &str) -> String {
+  hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Rulesets have no identity besides their contents, so they are told apart by a hash of the parts that decide how
+/// they play. Renaming a ruleset keeps its ratings.
+pub fn ruleset_key(ruleset: &Ruleset) -> String {
+  let json = serde_json::to_string(&ruleset.gameplay()).expect("rulesets should serialize");
+  hex::encode(Sha256::digest(json.as_bytes()))[..12].to_string()
+}
+
+fn validate_username(username: &str) -> Result<(), InvalidLogin> {
+  let length = username.chars().count();
+  let valid_chars = username
+    .chars()
+    .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+  if (3..=20).contains(&length) && valid_chars {
+    Ok(())
+  } else {
+    Err(InvalidLogin::InvalidUsername)
+  }
+}
+
+/// A password hash to check passwords of unknown usernames against, so that logging in takes as long whether or not
+/// the username exists.
+fn dummy_hash() -> &'static str {
+  static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+  DUMMY_HASH.get_or_init(|| {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    Argon2::default()
+      .hash_password(b"not anyone's password", &salt)
+      .expect("hashing a fixed password should work")
+      .to_string()
+  })
+}
+
+/// Database errors are logged here and reported to the client without details.
+fn unavailable(err: rusqlite::Error) -> InvalidLogin {
+  warn!("Database error: {}", err);
+  InvalidLogin::Unavailable
+}
+
+/// Accounts and the games played by them, stored in SQLite.
+pub struct Database {
+  conn: Connection,
+}
+
+impl Database {
+  pub fn open(path: &str) -> rusqlite::Result<Self> {
+    Self::with_connection(Connection::open(path)?)
+  }
+
+  #[cfg(test)]
+  pub fn in_memory() -> rusqlite::Result<Self> {
+    Self::with_connection(Connection::open_in_memory()?)
+  }
+
+  fn with_connection(conn: Connection) -> rusqlite::Result<Self> {
+    // The lobby, the accounts and the HTTP API each have a connection of their own
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(Self { conn })
+  }
+
+  /// Creates an account. Returns it along with a token for logging in to it.
+  pub fn register(
+    &self,
+    username: &str,
+    password: &str,
+  ) -> Result<(Account, String), InvalidLogin> {
+    let username = username.trim();
+    validate_username(username)?;
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+      return Err(InvalidLogin::PasswordTooShort(MIN_PASSWORD_LENGTH));
+    }
+
+    let taken = self
+      .conn
+      .query_row(
+        "SELECT 1 FROM accounts WHERE username = ?1",
+        params![username],
+        |_| Ok(()),
+      )
+      .optional()
+      .map_err(unavailable)?
+      .is_some();
+    if taken {
+      return Err(InvalidLogin::UsernameTaken);
+    }
+
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let password_hash = Argon2::default()
+      .hash_password(password.as_bytes(), &salt)
+      .map_err(|err| {
+        warn!("Failed to hash a password: {}", err);
+        InvalidLogin::Unavailable
+      })?
+      .to_string();
+
+    self
+      .conn
+      .execute(
+        "INSERT INTO accounts (username, password_hash, created_at) VALUES (?1, ?2, ?3)",
+        params![username, password_hash, now()],
+      )
+      .map_err(unavailable)?;
+    let account = Account {
+      id: self.conn.last_insert_rowid(),
+      username: username.to_string(),
+    };
+
+    let token = self.issue_token(&account)?;
+    Ok((account, token))
+  }
+
+  pub fn log_in(&self, username: &str, password: &str) -> Result<(Account, String), InvalidLogin> {
+    let found = self
+      .conn
+      .query_row(
+        "SELECT id, username, password_hash FROM accounts WHERE username = ?1",
+        params![username.trim()],
+        |row| {
+          Ok((
+            Account {
+              id: row.get(0)?,
+              username: row.get(1)?,
+            },
+            row.get::<_, String>(2)?,
+          ))
+        },
+      )
+      .optional()
+      .map_err(unavailable)?;
+
+    let (account, password_hash) = match found {
+      Some((account, password_hash)) => (Some(account), password_hash),
+      None => (None, dummy_hash().to_string()),
+    };
+    let password_hash = PasswordHash::new(&password_hash).map_err(|_| InvalidLogin::Unavailable)?;
+    Argon2::default()
+      .verify_password(password.as_bytes(), &password_hash)
+      .map_err(|_| InvalidLogin::WrongPassword)?;
+    let account = account.ok_or(InvalidLogin::WrongPassword)?;
+
+    let token = self.issue_token(&account)?;
+    Ok((account, token))
+  }
+
+  /// The account a token was issued for, unless the token has expired.
+  pub fn authenticate(&self, token: &str) -> Result<Account, InvalidLogin> {
+    self
+      .conn
+      .query_row(
+        "SELECT accounts.id, accounts.username FROM tokens
+        JOIN accounts ON accounts.id = tokens.account_id
+        WHERE tokens.token_hash = ?1 AND tokens.created_at > ?2",
+        params![hash_token(token), now() - TOKEN_LIFETIME],
+        |row| {
+          Ok(Account {
+            id: row.get(0)?,
+            username: row.get(1)?,
+          })
+        },
+      )
+      .optional()
+      .map_err(unavailable)?
+      .ok_or(InvalidLogin::UnknownToken)
+  }
+
+  pub fn revoke_token(&self, token: &str) -> Result<(), InvalidLogin> {
+    self
+      .conn
+      .execute(
+        "DELETE FROM tokens WHERE token_hash = ?1",
+        params![hash_token(token)],
+      )
+      .map_err(unavailable)?;
+    Ok(())
+  }
+
+  fn issue_token(&self, account: &Account) -> Result<String, InvalidLogin> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+
+    self
+      .conn
+      .execute(
+        "DELETE FROM tokens WHERE created_at <= ?1",
+        params![now() - TOKEN_LIFETIME],
+      )
+      .map_err(unavailable)?;
+    self
+      .conn
+      .execute(
+        "INSERT INTO tokens (token_hash, account_id, created_at) VALUES (?1, ?2, ?3)",
+        params![hash_token(&token), account.id, now()],
+      )
+      .map_err(unavailable)?;
+    Ok(token)
+  }
+
+  /// Stores a finished game and updates the ratings of its players. `accounts` has the account of each player,
+  /// if they were logged in.
+  pub fn record_game(
+    &mut self,
+    saved_game: &SavedGame,
+    accounts: &[Option<i64>],
+  ) -> rusqlite::Result<i64> {
+    let json = serde_json::to_string(saved_game).expect("games should serialize");
+
+    let tx = self.conn.transaction()?;
+    tx.execute(
+      "INSERT INTO games (finished_at, saved_game) VALUES (?1, ?2)",
+      params![now(), json],
+    )?;
+    let game_id = tx.last_insert_rowid();
+    for (player, account_id) in accounts.iter().enumerate() {
+      tx.execute(
+        "INSERT INTO game_players (game_id, player, account_id) VALUES (?1, ?2, ?3)",
+        params![game_id, player as i64, account_id],
+      )?;
+    }
+    update_ratings(&tx, saved_game, accounts)?;
+    tx.commit()?;
+
+    Ok(game_id)
+  }
+
+  pub fn leaderboards(&self) -> rusqlite::Result<Vec<Leaderboard>> {
+    let mut statement = self
+      .conn
+      .prepare("SELECT key FROM rulesets ORDER BY name, key")?;
+    let keys = statement
+      .query_map([], |row| row.get::<_, String>(0))?
+      .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    let mut leaderboards = Vec::new();
+    for key in keys {
+      if let Some(leaderboard) = self.leaderboard(&key)? {
+        leaderboards.push(leaderboard);
+      }
+    }
+    Ok(leaderboards)
+  }
+
+  pub fn leaderboard(&self, ruleset_key: &str) -> rusqlite::Result<Option<Leaderboard>> {
+    let ruleset_name = self
+      .conn
+      .query_row(
+        "SELECT name FROM rulesets WHERE key = ?1",
+        params![ruleset_key],
+        |row| row.get::<_, String>(0),
+      )
+      .optional()?;
+    let ruleset_name = match ruleset_name {
+      Some(name) => name,
+      None => return Ok(None),
+    };
+
+    let mut statement = self.conn.prepare(
+      "SELECT accounts.username, ratings.rating, ratings.games FROM ratings
+      JOIN accounts ON accounts.id = ratings.account_id
+      WHERE ratings.ruleset_key = ?1
+      ORDER BY ratings.rating DESC, accounts.username",
+    )?;
+    let entries = statement
+      .query_map(params![ruleset_key], |row| {
+        Ok(LeaderboardEntry {
+          username: row.get(0)?,
+          rating: row.get(1)?,
+          games: row.get::<_, i64>(2)? as usize,
+        })
+      })?
+      .collect::<rusqlite::Result<Vec<LeaderboardEntry>>>()?;
+
+    Ok(Some(Leaderboard {
+      ruleset_key: ruleset_key.to_string(),
+      ruleset_name,
+      entries,
+    }))
+  }
+
+  pub fn account_by_username(&self, username: &str) -> rusqlite::Result<Option<Account>> {
+    self
+      .conn
+      .query_row(
+        "SELECT id, username FROM accounts WHERE username = ?1",
+        params![username],
+        |row| {
+          Ok(Account {
+            id: row.get(0)?,
+            username: row.get(1)?,
+          })
+        },
+      )
+      .optional()
+  }
+
+  /// The games an account has played in, oldest first, each with the index of the player of the account.
+  pub fn games_of(&self, account_id: i64) -> rusqlite::Result<Vec<(SavedGame, usize)>> {
+    let mut statement = self.conn.prepare(
+      "SELECT games.saved_game, game_players.player FROM games
+      JOIN game_players ON game_players.game_id = games.id
+      WHERE game_players.account_id = ?1
+      ORDER BY games.finished_at, games.id",
+    )?;
+    let rows = statement.query_map(params![account_id], |row| {
+      Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    let mut games = Vec::new();
+    for row in rows {
+      let (json, player) = row?;
+      match serde_json::from_str(&json) {
+        Ok(saved_game) => games.push((saved_game, player as usize)),
+        Err(err) => warn!("Skipping an unreadable game: {}", err),
+      }
+    }
+    Ok(games)
+  }
+}
+
+/// Rates the players who were logged in against each other by their final points.
+fn update_ratings(
+  conn: &Connection,
+  saved_game: &SavedGame,
+  accounts: &[Option<i64>],
+) -> rusqlite::Result<()> {
+  let game = match saved_game.load() {
+    Ok(game) => game,
+    Err(err) => {
+      warn!("Not rating a game that does not load: {}", err);
+      return Ok(());
+    }
+  };
+
+  // Guests are not rated, and an account playing against itself is only rated once.
+  // Ratings measure skill, so the starting points of handicaps are left out.
+  let mut rated: Vec<(i64, u64)> = Vec::new();
+  for (player, account_id) in accounts.iter().enumerate() {
+    if let (Some(account_id), Some(player)) = (account_id, game.players.get(player)) {
+      if rated.iter().all(|(rated_id, _)| rated_id != account_id) {
+        let points = game.ruleset.total(&player.score_sheet);
+        rated.push((*account_id, points));
+      }
+    }
+  }
+  if rated.len() < 2 {
+    return Ok(());
+  }
+
+  let key = ruleset_key(&saved_game.ruleset);
+  let name = saved_game
+    .ruleset
+    .builtin_name()
+    .map(|name| name.to_string())
+    .unwrap_or_else(|| format!("Custom ruleset {}", key));
+  let ruleset_json = serde_json::to_string(&saved_game.ruleset).expect("rulesets should serialize");
+  conn.execute(
+    "INSERT OR IGNORE INTO rulesets (key, name, ruleset) VALUES (?1, ?2, ?3)",
+    params![key, name, ruleset_json],
+  )?;
+
+  let mut ratings = Vec::new();
+  for (account_id, _) in rated.iter() {
+    let rating = conn
+      .query_row(
+        "SELECT rating FROM ratings WHERE ruleset_key = ?1 AND account_id = ?2",
+        params![key, account_id],
+        |row| row.get::<_, f64>(0),
+      )
+      .optional()?;
+    ratings.push(rating.unwrap_or(INITIAL_RATING));
+  }
+
+  let points: Vec<u64> = rated.iter().map(|&(_, points)| points).collect();
+  let changes = rating_changes(&ratings, &points);
+  for (((account_id, _), rating), change) in rated.iter().zip(ratings).zip(changes) {
+    conn.execute(
+      "INSERT INTO ratings (ruleset_key, account_id, rating, games) VALUES (?1, ?2, ?3, 1)
+      ON CONFLICT (ruleset_key, account_id) DO UPDATE SET rating = ?3, games = games + 1",
+      params![key, account_id, rating + change],
+    )?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use jatsi_shared::game::{Game, GameMessage, PlayerMessage};
+  use jatsi_shared::rules::{mini_rules, Metadata};
+
+  #[test]
+  fn test_accounts() {
+    let db = Database::in_memory().unwrap();
+
+    assert_eq!(
+      db.register("H", "hunter22").unwrap_err(),
+      InvalidLogin::InvalidUsername
+    );
+    assert_eq!(
+      db.register("Henry", "short").unwrap_err(),
+      InvalidLogin::PasswordTooShort(MIN_PASSWORD_LENGTH)
+    );
+
+    let (henry, token) = db.register("Henry", "hunter22").unwrap();
+    assert_eq!(henry.username, "Henry");
+    assert_eq!(
+      db.register("henry", "something else").unwrap_err(),
+      InvalidLogin::UsernameTaken,
+      "usernames are case insensitive"
+    );
+
+    assert_eq!(
+      db.log_in("Henry", "hunter23").unwrap_err(),
+      InvalidLogin::WrongPassword
+    );
+    assert_eq!(
+      db.log_in("Bobby", "hunter22").unwrap_err(),
+      InvalidLogin::WrongPassword
+    );
+    let (logged_in, other_token) = db.log_in("henry", "hunter22").unwrap();
+    assert_eq!(logged_in, henry);
+    assert_ne!(token, other_token);
+
+    assert_eq!(db.authenticate(&token).unwrap(), henry);
+    db.revoke_token(&token).unwrap();
+    assert_eq!(
+      db.authenticate(&token).unwrap_err(),
+      InvalidLogin::UnknownToken
+    );
+    assert_eq!(
+      db.authenticate(&other_token).unwrap(),
+      henry,
+      "other logins stay valid"
+    );
+
+    db.conn
+      .execute(
+        "UPDATE tokens SET created_at = ?1",
+        params![now() - TOKEN_LIFETIME],
+      )
+      .unwrap();
+    assert_eq!(
+      db.authenticate(&other_token).unwrap_err(),
+      InvalidLogin::UnknownToken,
+      "logins expire"
+    );
+  }
+
+  #[test]
+  fn test_record_game() {
+    let mut db = Database::in_memory().unwrap();
+    let (henry, _) = db.register("Henry", "hunter22").unwrap();
+
+    let saved_game = Game::dummy().save();
+    let game_id = db
+      .record_game(&saved_game, &[Some(henry.id), None])
+      .unwrap();
+
+    let json: String = db
+      .conn
+      .query_row(
+        "SELECT saved_game FROM games WHERE id = ?1",
+        params![game_id],
+        |row| row.get(0),
+      )
+      .unwrap();
+    assert_eq!(
+      serde_json::from_str::<SavedGame>(&json).unwrap(),
+      saved_game
+    );
+
+    let accounts: Vec<Option<i64>> = db
+      .conn
+      .prepare("SELECT account_id FROM game_players WHERE game_id = ?1 ORDER BY player")
+      .unwrap()
+      .query_map(params![game_id], |row| row.get(0))
+      .unwrap()
+      .collect::<rusqlite::Result<_>>()
+      .unwrap();
+    assert_eq!(accounts, [Some(henry.id), None]);
+
+    assert_eq!(
+      db.account_by_username("henry").unwrap(),
+      Some(henry.clone())
+    );
+    assert_eq!(db.games_of(henry.id).unwrap(), [(saved_game, 0)]);
+    assert!(db.games_of(henry.id + 1).unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_ratings() {
+    let mut db = Database::in_memory().unwrap();
+    let (henry, _) = db.register("Henry", "hunter22").unwrap();
+    let (bobby, _) = db.register("Bobby", "hunter22").unwrap();
+
+    let mut game = Game::new(mini_rules());
+    for (player, name) in ["Henry", "Bobby", "Guest"].into_iter().enumerate() {
+      for msg in game
+        .prepare(player, &PlayerMessage::JoinGame(name.into()))
+        .unwrap()
+      {
+        game.commit(&msg).unwrap();
+      }
+    }
+    // Henry places a straight, the others place nothing
+    for msg in [
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+      GameMessage::PlayerTurn(0),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![1, 2, 3, 4, 5]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Place(3)),
+    ] {
+      game.commit(&msg).unwrap();
+    }
+
+    let accounts = [Some(henry.id), Some(bobby.id), None];
+    db.record_game(&game.save(), &accounts).unwrap();
+    db.record_game(&game.save(), &[Some(henry.id), None, None])
+      .unwrap();
+
+    let leaderboards = db.leaderboards().unwrap();
+    assert_eq!(leaderboards.len(), 1);
+    let leaderboard = &leaderboards[0];
+    assert_eq!(leaderboard.ruleset_name, "Mini");
+    assert_eq!(leaderboard.ruleset_key, ruleset_key(&mini_rules()));
+    let renamed = Ruleset {
+      metadata: Metadata {
+        name: "Tiny".into(),
+        ..Metadata::default()
+      },
+      row_names: vec![Some("Sixes!".into())],
+      ..mini_rules()
+    };
+    assert_eq!(ruleset_key(&renamed), ruleset_key(&mini_rules()));
+
+    let entries: Vec<(&str, usize)> = leaderboard
+      .entries
+      .iter()
+      .map(|entry| (entry.username.as_str(), entry.games))
+      .collect();
+    assert_eq!(
+      entries,
+      [("Henry", 1), ("Bobby", 1)],
+      "games without other rated players do not count"
+    );
+    assert!(leaderboard.entries[0].rating > INITIAL_RATING);
+    assert!(db.leaderboard("nope").unwrap().is_none());
+  }
+}
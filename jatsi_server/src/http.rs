@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use jatsi_shared::ratings::Leaderboard;
+use jatsi_shared::stats::{player_stats, PlayerStats};
+use log::warn;
+use serde::Serialize;
+
+use crate::database::Database;
+
+/// The HTTP API of the server, for data that is not tied to a room.
+pub fn router(database: Arc<Mutex<Database>>) -> Router {
+  Router::new()
+    .route("/players/{username}/stats", get(get_player_stats))
+    .route("/leaderboards", get(get_leaderboards))
+    .route("/leaderboards/{ruleset}", get(get_leaderboard))
+    .with_state(database)
+}
+
+/// Loads the games of a player and replays them. The database is only locked for loading.
+fn stats_of(database: &Mutex<Database>, username: &str) -> Result<PlayerStats, StatusCode> {
+  let internal_error = |err: &dyn std::fmt::Display| {
+    warn!("Failed to compute the stats of {}: {}", username, err);
+    StatusCode::INTERNAL_SERVER_ERROR
+  };
+
+  let (account, games) = {
+    let database = database.lock().unwrap();
+    let account = database
+      .account_by_username(username)
+      .map_err(|err| internal_error(&err))?
+      .ok_or(StatusCode::NOT_FOUND)?;
+    let games = database
+      .games_of(account.id)
+      .map_err(|err| internal_error(&err))?;
+    (account, games)
+  };
+  let stats = player_stats(&account.username, &games);
+  if stats.unreadable_games > 0 {
+    warn!(
+      "Left {} games of {} that do not replay out of their stats",
+      stats.unreadable_games, account.username
+    );
+  }
+  Ok(stats)
+}
+
+fn leaderboard_of(database: &Database, ruleset_key: &str) -> Result<Leaderboard, StatusCode> {
+  database
+    .leaderboard(ruleset_key)
+    .map_err(|err| {
+      warn!("Failed to load the leaderboard of {}: {}", ruleset_key, err);
+      StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Runs database queries and game replays on the blocking threads, so they do not hold up the async workers.
+async fn blocking<T, F>(work: F) -> Result<T, StatusCode>
+where
+  T: Send + 'static,
+  F: FnOnce() -> Result<T, StatusCode> + Send + 'static,
+{
+  tokio::task::spawn_blocking(work)
+    .await
+    .unwrap_or_else(|err| {
+      warn!("A blocking task of the HTTP API failed: {}", err);
+      Err(StatusCode::INTERNAL_SERVER_ERROR)
+    })
+}
+
+fn respond<T: Serialize>(result: Result<T, StatusCode>) -> Response {
+  // The web client is served from another origin
+  let cors = [(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")];
+  match result {
+    Ok(body) => (cors, Json(body)).into_response(),
+    Err(status) => (status, cors).into_response(),
+  }
+}
+
+async fn get_player_stats(
+  State(database): State<Arc<Mutex<Database>>>,
+  Path(username): Path<String>,
+) -> Response {
+  respond(blocking(move || stats_of(&database, &username)).await)
+}
+
+async fn get_leaderboards(State(database): State<Arc<Mutex<Database>>>) -> Response {
+  let result = blocking(move || {
+    database.lock().unwrap().leaderboards().map_err(|err| {
+      warn!("Failed to load the leaderboards: {}", err);
+      StatusCode::INTERNAL_SERVER_ERROR
+    })
+  });
+  respond(result.await)
+}
+
+async fn get_leaderboard(
+  State(database): State<Arc<Mutex<Database>>>,
+  Path(ruleset_key): Path<String>,
+) -> Response {
+  respond(blocking(move || leaderboard_of(&database.lock().unwrap(), &ruleset_key)).await)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use jatsi_shared::game::Game;
+
+  #[test]
+  fn test_stats_of() {
+    let database = Mutex::new(Database::in_memory().unwrap());
+    let (henry, _) = database
+      .lock()
+      .unwrap()
+      .register("Henry", "hunter22")
+      .unwrap();
+    assert_eq!(stats_of(&database, "Bobby"), Err(StatusCode::NOT_FOUND));
+
+    let stats = stats_of(&database, "henry").unwrap();
+    assert_eq!(stats.username, "Henry");
+    assert_eq!(stats.games_played, 0);
+
+    database
+      .lock()
+      .unwrap()
+      .record_game(&Game::dummy().save(), &[Some(henry.id)])
+      .unwrap();
+    assert_eq!(stats_of(&database, "Henry").unwrap().games_played, 1);
+  }
+
+  #[test]
+  fn test_leaderboard_of() {
+    let database = Database::in_memory().unwrap();
+    assert_eq!(
+      leaderboard_of(&database, "nope"),
+      Err(StatusCode::NOT_FOUND)
+    );
+  }
+}
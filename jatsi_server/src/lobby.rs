@@ -0,0 +1,938 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use jatsi_shared::chat::ChatBody;
+use jatsi_shared::errors::{InvalidAction, InvalidChat, InvalidTournament};
+use jatsi_shared::game::{PlayerMessage, SavedGame};
+use jatsi_shared::protocol::{Account, ClientMessage, ServerMessage};
+use jatsi_shared::rules::Ruleset;
+use jatsi_shared::tournament::Tournament;
+use log::{info, warn};
+use rand::Rng;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::database::Database;
+use crate::room::{send, Room};
+use crate::tournament::TournamentRoom;
+
+// No 0/O or 1/I so that codes can be read out loud
+const CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 5;
+
+/// Where a connection is: the join code of its room or tournament and its client id in it.
+pub struct Seat {
+  code: String,
+  client_id: usize,
+}
+
+/// The state of a connection: where it is and who is logged in on it.
+#[derive(Default)]
+pub struct Session {
+  seat: Option<Seat>,
+  tournament: Option<Seat>,
+  account: Option<Account>,
+  token: Option<String>,
+}
+
+impl Session {
+  /// Remembers the account logged in on the connection.
+  pub fn log_in(&mut self, account: Account, token: String) {
+    self.account = Some(account);
+    self.token = Some(token);
+  }
+
+  /// Forgets the account logged in on the connection, returning its token.
+  pub fn log_out(&mut self) -> Option<String> {
+    self.account = None;
+    self.token.take()
+  }
+}
+
+/// Where the game of a room belongs in a tournament: the code of the tournament, the round and the table.
+struct TableSeat {
+  tournament: String,
+  round: usize,
+  table: usize,
+}
+
+/// A game that has finished and is waiting to be recorded, with the accounts of its players.
+pub struct FinishedGame {
+  code: String,
+  saved_game: SavedGame,
+  accounts: Vec<Option<i64>>,
+}
+
+impl FinishedGame {
+  /// Records the game on the blocking threads, so that the database does not hold up the async workers.
+  pub fn record(self, database: Arc<Mutex<Database>>) {
+    tokio::task::spawn_blocking(move || {
+      if let Err(err) = database
+        .lock()
+        .unwrap()
+        .record_game(&self.saved_game, &self.accounts)
+      {
+        warn!("Failed to record the game of room {}: {}", self.code, err);
+      }
+    });
+  }
+}
+
+/// All the rooms and tournaments of the server, by join code.
+#[derive(Default)]
+pub struct Lobby {
+  rooms: HashMap<String, Room>,
+  tournaments: HashMap<String, TournamentRoom>,
+  /// The rooms of tournament tables whose games have not finished yet.
+  tables: HashMap<String, TableSeat>,
+  /// Games that have finished since they were last taken for recording.
+  finished: Vec<FinishedGame>,
+}
+
+impl Lobby {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Takes the games that have finished, to be recorded outside the lock of the lobby.
+  pub fn take_finished(&mut self) -> Vec<FinishedGame> {
+    std::mem::take(&mut self.finished)
+  }
+
+  /// Processes a message from a connection.
+  pub fn handle(
+    &mut self,
+    session: &mut Session,
+    sender: &UnboundedSender<Message>,
+    msg: ClientMessage,
+  ) {
+    let seat = &mut session.seat;
+    let account = &session.account;
+
+    match msg {
+      ClientMessage::CreateRoom {
+        name,
+        ruleset,
+        team,
+      } => {
+        if let Err(err) = ruleset.validate() {
+          return send(sender, &ServerMessage::RulesetRejected(err));
+        }
+
+        self.leave(seat);
+        let code = self.create_room(ruleset);
+        info!("Created room {}", code);
+        *seat = Some(self.enter(
+          &code,
+          name,
+          team,
+          account,
+          session.tournament.as_ref(),
+          sender,
+        ));
+      }
+      ClientMessage::JoinRoom { name, code, team } => {
+        let code = code.trim().to_uppercase();
+        if !self.rooms.contains_key(&code) {
+          return send(sender, &ServerMessage::NoSuchRoom(code));
+        }
+
+        self.leave(seat);
+        *seat = Some(self.enter(
+          &code,
+          name,
+          team,
+          account,
+          session.tournament.as_ref(),
+          sender,
+        ));
+      }
+      ClientMessage::Player(player_msg) => {
+        let player_msg = named_by_account(player_msg, account);
+        if let (Some(seat), PlayerMessage::JoinGame(name) | PlayerMessage::JoinTeam(_, name)) =
+          (seat.as_ref(), &player_msg)
+        {
+          if !self.may_sit(&seat.code, name, account, session.tournament.as_ref()) {
+            return send(
+              sender,
+              &ServerMessage::TournamentRejected(InvalidTournament::NotYourSeat),
+            );
+          }
+        }
+        match seat
+          .as_ref()
+          .and_then(|seat| Some((seat, self.rooms.get_mut(&seat.code)?)))
+        {
+          Some((seat, room)) => {
+            if room.handle(seat.client_id, &player_msg) {
+              let code = seat.code.clone();
+              self.finished.push(FinishedGame {
+                code: code.clone(),
+                saved_game: room.saved_game(),
+                accounts: room.player_accounts().to_vec(),
+              });
+              self.table_finished(&code);
+            }
+          }
+          None => send(sender, &ServerMessage::Rejected(InvalidAction::WrongState)),
+        }
+      }
+      ClientMessage::Chat(text) => self.chat(seat, sender, ChatBody::Text(text)),
+      ClientMessage::React(reaction) => self.chat(seat, sender, ChatBody::Reaction(reaction)),
+      // Accounts handle these outside the lock of the lobby
+      ClientMessage::Register { .. }
+      | ClientMessage::LogIn { .. }
+      | ClientMessage::Authenticate(..)
+      | ClientMessage::LogOut => warn!("Account message sent to the lobby"),
+      ClientMessage::CreateTournament {
+        name,
+        ruleset,
+        settings,
+      } => {
+        if let Err(err) = ruleset.validate() {
+          return send(sender, &ServerMessage::RulesetRejected(err));
+        }
+        let mut tournament = match Tournament::new(ruleset, settings) {
+          Ok(tournament) => tournament,
+          Err(err) => return send(sender, &ServerMessage::TournamentRejected(err)),
+        };
+        let player = match tournament.register(&player_name(name, &session.account)) {
+          Ok(player) => player,
+          Err(err) => return send(sender, &ServerMessage::TournamentRejected(err)),
+        };
+
+        self.leave_tournament(&mut session.tournament);
+        let code = self.new_code();
+        info!("Created tournament {}", code);
+        let mut tournament_room = TournamentRoom::new(code.clone(), tournament);
+        let client_id = tournament_room.connect(sender.clone());
+        tournament_room.claim(player, &session.account, client_id);
+        self.tournaments.insert(code.clone(), tournament_room);
+        session.tournament = Some(Seat { code, client_id });
+      }
+      ClientMessage::JoinTournament { name, code } => {
+        let code = code.trim().to_uppercase();
+        let tournament_room = match self.tournaments.get_mut(&code) {
+          Some(tournament_room) => tournament_room,
+          None => {
+            return send(
+              sender,
+              &ServerMessage::TournamentRejected(InvalidTournament::NoSuchTournament(code)),
+            )
+          }
+        };
+
+        // Players coming back are already registered, and anyone can follow a tournament that has started
+        let name = player_name(name, &session.account);
+        let tournament = tournament_room.tournament_mut();
+        let register = !tournament.has_started() && tournament.player_index(&name).is_none();
+        if register {
+          if let Err(err) = tournament.register(&name) {
+            return send(sender, &ServerMessage::TournamentRejected(err));
+          }
+          tournament_room.broadcast();
+        }
+
+        self.leave_tournament(&mut session.tournament);
+        if let Some(tournament_room) = self.tournaments.get_mut(&code) {
+          let client_id = tournament_room.connect(sender.clone());
+          if let Some(player) = tournament_room.tournament().player_index(&name) {
+            tournament_room.claim(player, &session.account, client_id);
+          }
+          session.tournament = Some(Seat { code, client_id });
+        }
+      }
+      ClientMessage::StartTournament => {
+        let result = match session.tournament.as_ref().and_then(|seat| {
+          let tournament_room = self.tournaments.get(&seat.code)?;
+          Some((
+            seat.code.clone(),
+            tournament_room.is_organizer(seat.client_id),
+          ))
+        }) {
+          Some((code, true)) => self.start_round(&code),
+          _ => Err(InvalidTournament::NotOrganizer),
+        };
+        if let Err(err) = result {
+          send(sender, &ServerMessage::TournamentRejected(err));
+        }
+      }
+      ClientMessage::Forfeit(name) => {
+        if let Err(err) = self.forfeit(&name, &session.account, session.tournament.as_ref()) {
+          send(sender, &ServerMessage::TournamentRejected(err));
+        }
+      }
+    }
+  }
+
+  /// Whether a connection may play as `name` in a room. Tournament tables only seat players through the account or
+  /// the tournament connection that registered them.
+  fn may_sit(
+    &self,
+    room_code: &str,
+    name: &str,
+    account: &Option<Account>,
+    tournament: Option<&Seat>,
+  ) -> bool {
+    let table = match self.tables.get(room_code) {
+      Some(table) => table,
+      None => return true,
+    };
+    let tournament_room = match self.tournaments.get(&table.tournament) {
+      Some(tournament_room) => tournament_room,
+      None => return false,
+    };
+    let client_id = tournament
+      .filter(|seat| seat.code == table.tournament)
+      .map(|seat| seat.client_id);
+    tournament_room
+      .tournament()
+      .player_index(name)
+      .is_some_and(|player| tournament_room.owns(player, account, client_id))
+  }
+
+  /// Ends the unfinished game of a tournament player who gives up, or who the organizer gives up for.
+  fn forfeit(
+    &mut self,
+    name: &str,
+    account: &Option<Account>,
+    tournament: Option<&Seat>,
+  ) -> Result<(), InvalidTournament> {
+    let seat = tournament.ok_or(InvalidTournament::NotPlaying)?;
+    let tournament_room = self
+      .tournaments
+      .get(&seat.code)
+      .ok_or_else(|| InvalidTournament::NoSuchTournament(seat.code.clone()))?;
+    let tournament = tournament_room.tournament();
+    let player = tournament
+      .player_index(name)
+      .ok_or(InvalidTournament::NotPlaying)?;
+    if !tournament_room.is_organizer(seat.client_id)
+      && !tournament_room.owns(player, account, Some(seat.client_id))
+    {
+      return Err(InvalidTournament::NotOrganizer);
+    }
+    let (round, table) = tournament
+      .current_table(player)
+      .ok_or(InvalidTournament::NotPlaying)?;
+    let table = &tournament.rounds[round][table];
+
+    // Players who have not joined the game yet have no points
+    let game_scoreboard = table
+      .room
+      .as_ref()
+      .and_then(|room_code| self.rooms.get(room_code))
+      .map(|room| room.game().scoreboard())
+      .unwrap_or_default();
+    let names: Vec<&str> = table
+      .players
+      .iter()
+      .map(|&player| tournament.players[player].as_str())
+      .collect();
+    let scoreboard: Vec<(u64, String)> = names
+      .iter()
+      .map(|name| {
+        let points = game_scoreboard
+          .iter()
+          .find(|(_, player)| player.eq_ignore_ascii_case(name))
+          .map_or(0, |&(points, _)| points);
+        (points, name.to_string())
+      })
+      .collect();
+    let scoreboard: Vec<(u64, &str)> = scoreboard
+      .iter()
+      .map(|(points, name)| (*points, name.as_str()))
+      .collect();
+    let room_code = table.room.clone();
+
+    let code = seat.code.clone();
+    let tournament_room = self
+      .tournaments
+      .get_mut(&code)
+      .expect("the tournament was just found");
+    tournament_room
+      .tournament_mut()
+      .forfeit(player, &scoreboard)?;
+    info!("{} forfeited in tournament {}", name, code);
+    tournament_room.broadcast();
+
+    if let Some(room_code) = room_code {
+      self.tables.remove(&room_code);
+      self.close_if_unused(&room_code);
+    }
+    self.next_round(&code);
+    Ok(())
+  }
+
+  /// Seats the next round of a tournament in new rooms.
+  fn start_round(&mut self, code: &str) -> Result<(), InvalidTournament> {
+    let tournament_room = self
+      .tournaments
+      .get_mut(code)
+      .ok_or_else(|| InvalidTournament::NoSuchTournament(code.into()))?;
+    let tournament = tournament_room.tournament_mut();
+    let round = tournament.rounds.len();
+    let tables: Vec<Vec<usize>> = tournament
+      .start_round()?
+      .iter()
+      .map(|table| table.players.clone())
+      .collect();
+    let names: Vec<Vec<String>> = tables
+      .iter()
+      .map(|players| {
+        players
+          .iter()
+          .map(|&player| tournament.players[player].clone())
+          .collect()
+      })
+      .collect();
+    let ruleset = tournament.ruleset.clone();
+
+    let mut room_codes = Vec::with_capacity(names.len());
+    for (table, names) in names.into_iter().enumerate() {
+      let room_code = self.new_code();
+      self.rooms.insert(
+        room_code.clone(),
+        Room::reserved(room_code.clone(), ruleset.clone(), names),
+      );
+      self.tables.insert(
+        room_code.clone(),
+        TableSeat {
+          tournament: code.into(),
+          round,
+          table,
+        },
+      );
+      room_codes.push(room_code);
+    }
+    info!("Started round {} of tournament {}", round + 1, code);
+
+    if let Some(tournament_room) = self.tournaments.get_mut(code) {
+      for (table, room_code) in tournament_room.tournament_mut().rounds[round]
+        .iter_mut()
+        .zip(room_codes)
+      {
+        table.room = Some(room_code);
+      }
+      tournament_room.broadcast();
+    }
+    Ok(())
+  }
+
+  /// Records the result of a finished tournament table, starting the next round when the whole round is done.
+  fn table_finished(&mut self, room_code: &str) {
+    let seat = match self.tables.remove(room_code) {
+      Some(seat) => seat,
+      None => return,
+    };
+    let (room, tournament_room) = match (
+      self.rooms.get(room_code),
+      self.tournaments.get_mut(&seat.tournament),
+    ) {
+      (Some(room), Some(tournament_room)) => (room, tournament_room),
+      _ => return,
+    };
+
+    let scoreboard = room.game().scoreboard();
+    if let Err(err) =
+      tournament_room
+        .tournament_mut()
+        .record_result(seat.round, seat.table, &scoreboard)
+    {
+      warn!("Failed to record the result of room {}: {}", room_code, err);
+      return;
+    }
+    tournament_room.broadcast();
+    self.next_round(&seat.tournament);
+  }
+
+  /// Starts the next round of a tournament if the current one has finished.
+  fn next_round(&mut self, code: &str) {
+    match self.start_round(code) {
+      Ok(()) | Err(InvalidTournament::RoundInProgress) => {}
+      Err(InvalidTournament::Finished) => info!("Tournament {} finished", code),
+      Err(err) => warn!("Failed to start the next round of {}: {}", code, err),
+    }
+  }
+
+  fn chat(&mut self, seat: &Option<Seat>, sender: &UnboundedSender<Message>, body: ChatBody) {
+    match seat
+      .as_ref()
+      .and_then(|seat| Some((seat, self.rooms.get_mut(&seat.code)?)))
+    {
+      Some((seat, room)) => room.chat(seat.client_id, body),
+      None => send(sender, &ServerMessage::ChatRejected(InvalidChat::NotJoined)),
+    }
+  }
+
+  /// Cleans up after a connection that has closed.
+  pub fn disconnect(&mut self, session: &mut Session) {
+    self.leave(&mut session.seat);
+    self.leave_tournament(&mut session.tournament);
+  }
+
+  /// Removes the connection from its room.
+  fn leave(&mut self, seat: &mut Option<Seat>) {
+    if let Some(Seat { code, client_id }) = seat.take() {
+      if let Some(room) = self.rooms.get_mut(&code) {
+        room.disconnect(client_id);
+      }
+      self.close_if_unused(&code);
+    }
+  }
+
+  /// Closes a room nobody is in. The rooms of tournament tables in progress stay open for the players to come back.
+  fn close_if_unused(&mut self, code: &str) {
+    let unused =
+      self.rooms.get(code).is_some_and(Room::is_empty) && !self.tables.contains_key(code);
+    if unused {
+      info!("Closed room {}", code);
+      self.rooms.remove(code);
+    }
+  }
+
+  /// Stops following a tournament. Tournaments are closed when the last client leaves, and so are the rooms of their
+  /// tables that nobody is in.
+  fn leave_tournament(&mut self, seat: &mut Option<Seat>) {
+    if let Some(Seat { code, client_id }) = seat.take() {
+      if let Some(tournament_room) = self.tournaments.get_mut(&code) {
+        tournament_room.disconnect(client_id);
+        if tournament_room.is_empty() {
+          info!("Closed tournament {}", code);
+          self.tournaments.remove(&code);
+          let room_codes: Vec<String> = self
+            .tables
+            .iter()
+            .filter(|(_, table)| table.tournament == code)
+            .map(|(room_code, _)| room_code.clone())
+            .collect();
+          for room_code in room_codes {
+            self.tables.remove(&room_code);
+            self.close_if_unused(&room_code);
+          }
+        }
+      }
+    }
+  }
+
+  /// A join code not used by any room or tournament.
+  fn new_code(&self) -> String {
+    let mut rng = rand::thread_rng();
+    loop {
+      let code: String = (0..CODE_LENGTH)
+        .map(|_| CODE_CHARS[rng.gen_range(0..CODE_CHARS.len())] as char)
+        .collect();
+      if !self.rooms.contains_key(&code) && !self.tournaments.contains_key(&code) {
+        break code;
+      }
+    }
+  }
+
+  fn create_room(&mut self, ruleset: Ruleset) -> String {
+    let code = self.new_code();
+    self
+      .rooms
+      .insert(code.clone(), Room::new(code.clone(), ruleset));
+    code
+  }
+
+  fn enter(
+    &mut self,
+    code: &str,
+    name: String,
+    team: Option<String>,
+    account: &Option<Account>,
+    tournament: Option<&Seat>,
+    sender: &UnboundedSender<Message>,
+  ) -> Seat {
+    let name = player_name(name, account);
+    let may_sit = self.may_sit(code, &name, account, tournament);
+    let room = self.rooms.get_mut(code).expect("the room should exist");
+    let client_id = room.connect(sender.clone(), account.clone());
+    let seated = room.accepts(&name) || room.vacant_seat(&name).is_some();
+    if seated && !may_sit {
+      send(
+        sender,
+        &ServerMessage::TournamentRejected(InvalidTournament::NotYourSeat),
+      );
+    } else if seated {
+      let team = team
+        .map(|team| team.trim().to_string())
+        .filter(|team| !team.is_empty());
+      let msg = match team {
+        Some(team) => PlayerMessage::JoinTeam(team, name),
+        None => PlayerMessage::JoinGame(name),
+      };
+      room.handle(client_id, &msg);
+    }
+
+    Seat {
+      code: code.into(),
+      client_id,
+    }
+  }
+}
+
+/// Logged in players are known by their username.
+fn player_name(name: String, account: &Option<Account>) -> String {
+  match account {
+    Some(account) => account.username.clone(),
+    None => name,
+  }
+}
+
+/// The message with the name of a player joining replaced by the username of the account logged in, if any.
+fn named_by_account(msg: PlayerMessage, account: &Option<Account>) -> PlayerMessage {
+  match msg {
+    PlayerMessage::JoinGame(name) => PlayerMessage::JoinGame(player_name(name, account)),
+    PlayerMessage::JoinTeam(team, name) => {
+      PlayerMessage::JoinTeam(team, player_name(name, account))
+    }
+    msg => msg,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::IpAddr;
+  use std::sync::Arc;
+
+  use super::*;
+  use crate::accounts::Accounts;
+  use jatsi_shared::errors::InvalidLogin;
+  use jatsi_shared::game::GameMessage;
+  use jatsi_shared::rules::mini_rules;
+  use jatsi_shared::tournament::{Format, TournamentSettings};
+  use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+  fn received(receiver: &mut UnboundedReceiver<Message>) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+    while let Ok(msg) = receiver.try_recv() {
+      messages.push(serde_json::from_str(msg.to_text().unwrap()).unwrap());
+    }
+    messages
+  }
+
+  #[test]
+  fn test_lobby() {
+    let mut lobby = Lobby::new();
+    let (sender, mut receiver) = unbounded_channel();
+    let mut session = Session::default();
+
+    lobby.handle(
+      &mut session,
+      &sender,
+      ClientMessage::JoinRoom {
+        name: "Henry".into(),
+        code: "nope".into(),
+        team: None,
+      },
+    );
+    let reply: ServerMessage =
+      serde_json::from_str(receiver.try_recv().unwrap().to_text().unwrap()).unwrap();
+    assert_eq!(reply, ServerMessage::NoSuchRoom("NOPE".into()));
+    assert!(session.seat.is_none());
+
+    lobby.handle(
+      &mut session,
+      &sender,
+      ClientMessage::CreateRoom {
+        name: "Henry".into(),
+        ruleset: mini_rules(),
+        team: None,
+      },
+    );
+    let code = session.seat.as_ref().unwrap().code.clone();
+    assert_eq!(code.len(), CODE_LENGTH);
+    assert!(lobby.rooms.contains_key(&code));
+
+    lobby.disconnect(&mut session);
+    assert!(session.seat.is_none());
+    assert!(lobby.rooms.is_empty(), "empty rooms are closed");
+  }
+
+  #[tokio::test]
+  async fn test_logged_in() {
+    let mut lobby = Lobby::new();
+    let accounts = Arc::new(Accounts::new(Database::in_memory().unwrap()));
+    let address = IpAddr::from([127, 0, 0, 1]);
+    let (sender, mut receiver) = unbounded_channel::<Message>();
+    let mut session = Session::default();
+    let mut received = || -> Vec<ServerMessage> {
+      let mut messages = Vec::new();
+      while let Ok(msg) = receiver.try_recv() {
+        messages.push(serde_json::from_str(msg.to_text().unwrap()).unwrap());
+      }
+      messages
+    };
+
+    accounts
+      .handle(
+        &mut session,
+        &sender,
+        address,
+        ClientMessage::Register {
+          username: "Henry".into(),
+          password: "hunter22".into(),
+        },
+      )
+      .await;
+    let token = match received().as_slice() {
+      [ServerMessage::LoggedIn { account, token }] => {
+        assert_eq!(account.username, "Henry");
+        token.clone()
+      }
+      other => panic!("expected to be logged in, got {:?}", other),
+    };
+
+    lobby.handle(
+      &mut session,
+      &sender,
+      ClientMessage::CreateRoom {
+        name: "Hank".into(),
+        ruleset: mini_rules(),
+        team: None,
+      },
+    );
+    let code = session.seat.as_ref().unwrap().code.clone();
+    assert_eq!(
+      lobby.rooms[&code].player_accounts(),
+      [session.account.as_ref().map(|account| account.id)]
+    );
+    assert!(
+      received().contains(&ServerMessage::Game(GameMessage::PlayerMessage(
+        0,
+        PlayerMessage::JoinGame("Henry".into())
+      ))),
+      "logged in players join with their username"
+    );
+
+    let account = session.account.clone();
+    assert_eq!(
+      named_by_account(
+        PlayerMessage::JoinTeam("Red".into(), "Hank".into()),
+        &account
+      ),
+      PlayerMessage::JoinTeam("Red".into(), "Henry".into())
+    );
+    assert_eq!(
+      named_by_account(PlayerMessage::JoinGame("Hank".into()), &None),
+      PlayerMessage::JoinGame("Hank".into())
+    );
+
+    accounts
+      .handle(&mut session, &sender, address, ClientMessage::LogOut)
+      .await;
+    assert_eq!(received(), [ServerMessage::LoggedOut]);
+    accounts
+      .handle(
+        &mut session,
+        &sender,
+        address,
+        ClientMessage::Authenticate(token),
+      )
+      .await;
+    assert_eq!(
+      received(),
+      [ServerMessage::LoginRejected(InvalidLogin::UnknownToken)],
+      "logging out revokes the token"
+    );
+  }
+
+  #[test]
+  fn test_tournament() {
+    let mut lobby = Lobby::new();
+    let (henry_sender, mut henry) = unbounded_channel::<Message>();
+    let (bobby_sender, mut bobby) = unbounded_channel::<Message>();
+    let mut henry_session = Session::default();
+    let mut bobby_session = Session::default();
+
+    lobby.handle(
+      &mut henry_session,
+      &henry_sender,
+      ClientMessage::CreateTournament {
+        name: "Henry".into(),
+        ruleset: mini_rules(),
+        settings: TournamentSettings {
+          table_size: 2,
+          format: Format::Bracket { advancing: 1 },
+          tie_breaks: Vec::new(),
+        },
+      },
+    );
+    let code = henry_session.tournament.as_ref().unwrap().code.clone();
+    lobby.handle(
+      &mut bobby_session,
+      &bobby_sender,
+      ClientMessage::JoinTournament {
+        name: "Bobby".into(),
+        code: code.to_lowercase(),
+      },
+    );
+    lobby.handle(
+      &mut bobby_session,
+      &bobby_sender,
+      ClientMessage::StartTournament,
+    );
+    assert_eq!(
+      received(&mut bobby).last(),
+      Some(&ServerMessage::TournamentRejected(
+        InvalidTournament::NotOrganizer
+      ))
+    );
+
+    lobby.handle(
+      &mut henry_session,
+      &henry_sender,
+      ClientMessage::StartTournament,
+    );
+    let tournament = match received(&mut henry).last() {
+      Some(ServerMessage::Tournament { tournament, .. }) => tournament.clone(),
+      other => panic!("expected the tournament, got {:?}", other),
+    };
+    assert_eq!(tournament.players, ["Henry", "Bobby"]);
+    let room_code = tournament.rounds[0][0].room.clone().unwrap();
+
+    let mut players = [
+      (&mut henry_session, &henry_sender, "Henry"),
+      (&mut bobby_session, &bobby_sender, "Bobby"),
+    ];
+    for (session, sender, name) in players.iter_mut() {
+      lobby.handle(
+        session,
+        sender,
+        ClientMessage::JoinRoom {
+          name: name.to_string(),
+          code: room_code.clone(),
+          team: None,
+        },
+      );
+    }
+    // The mini rules have three rows to place in besides the bonus
+    for row in [0, 2, 3] {
+      for (session, sender, _) in players.iter_mut() {
+        lobby.handle(session, sender, ClientMessage::Player(PlayerMessage::Roll));
+        lobby.handle(
+          session,
+          sender,
+          ClientMessage::Player(PlayerMessage::Place(row)),
+        );
+      }
+    }
+
+    let finished = lobby.take_finished();
+    assert_eq!(finished.len(), 1, "finished games are kept for recording");
+    assert_eq!(finished[0].accounts, [None, None]);
+    assert!(lobby.take_finished().is_empty());
+
+    let tournament = lobby.tournaments.get_mut(&code).unwrap().tournament_mut();
+    assert!(tournament.is_finished());
+    assert_eq!(tournament.standings()[0].games, 1);
+    assert!(lobby.tables.is_empty(), "finished tables are forgotten");
+  }
+
+  #[test]
+  fn test_tournament_seats() {
+    let mut lobby = Lobby::new();
+    let (henry_sender, mut henry) = unbounded_channel::<Message>();
+    let (bobby_sender, mut bobby) = unbounded_channel::<Message>();
+    let (other_sender, mut other) = unbounded_channel::<Message>();
+    let mut henry_session = Session::default();
+    let mut bobby_session = Session::default();
+    let mut other_session = Session::default();
+
+    lobby.handle(
+      &mut henry_session,
+      &henry_sender,
+      ClientMessage::CreateTournament {
+        name: "Henry".into(),
+        ruleset: mini_rules(),
+        settings: TournamentSettings {
+          table_size: 2,
+          format: Format::Bracket { advancing: 1 },
+          tie_breaks: Vec::new(),
+        },
+      },
+    );
+    let code = henry_session.tournament.as_ref().unwrap().code.clone();
+    lobby.handle(
+      &mut bobby_session,
+      &bobby_sender,
+      ClientMessage::JoinTournament {
+        name: "Bobby".into(),
+        code,
+      },
+    );
+    lobby.handle(
+      &mut henry_session,
+      &henry_sender,
+      ClientMessage::StartTournament,
+    );
+    let room_code = match received(&mut henry).last() {
+      Some(ServerMessage::Tournament { tournament, .. }) => tournament.rounds[0][0].room.clone(),
+      other => panic!("expected the tournament, got {:?}", other),
+    }
+    .unwrap();
+    let join = |name: &str| ClientMessage::JoinRoom {
+      name: name.into(),
+      code: room_code.clone(),
+      team: None,
+    };
+
+    lobby.handle(&mut henry_session, &henry_sender, join("Henry"));
+    lobby.handle(&mut bobby_session, &bobby_sender, join("Bobby"));
+    assert!(received(&mut bobby).contains(&ServerMessage::Joined(1)));
+    lobby.leave(&mut bobby_session.seat);
+    assert!(
+      lobby.rooms.contains_key(&room_code),
+      "tables stay open while the game is in progress"
+    );
+
+    lobby.handle(&mut other_session, &other_sender, join("Bobby"));
+    assert_eq!(
+      received(&mut other).last(),
+      Some(&ServerMessage::TournamentRejected(
+        InvalidTournament::NotYourSeat
+      ))
+    );
+    lobby.handle(
+      &mut other_session,
+      &other_sender,
+      ClientMessage::Forfeit("Bobby".into()),
+    );
+    assert_eq!(
+      received(&mut other),
+      [ServerMessage::TournamentRejected(
+        InvalidTournament::NotPlaying
+      )]
+    );
+
+    lobby.handle(&mut bobby_session, &bobby_sender, join("Bobby"));
+    assert!(
+      received(&mut bobby).contains(&ServerMessage::Joined(1)),
+      "players can come back to their seat"
+    );
+
+    lobby.handle(
+      &mut bobby_session,
+      &bobby_sender,
+      ClientMessage::Forfeit("Henry".into()),
+    );
+    assert_eq!(
+      received(&mut bobby).last(),
+      Some(&ServerMessage::TournamentRejected(
+        InvalidTournament::NotOrganizer
+      ))
+    );
+    lobby.handle(
+      &mut bobby_session,
+      &bobby_sender,
+      ClientMessage::Forfeit("Bobby".into()),
+    );
+    let tournament =
+      lobby.tournaments[&henry_session.tournament.as_ref().unwrap().code].tournament();
+    assert!(tournament.is_finished());
+    assert_eq!(tournament.standings()[0].name, "Henry");
+    assert!(lobby.tables.is_empty());
+  }
+}
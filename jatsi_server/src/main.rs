@@ -1,8 +1,25 @@
-use std::{env, io::Error};
+mod accounts;
+mod database;
+mod http;
+mod lobby;
+mod room;
+mod tournament;
 
-use futures_util::{future, StreamExt, TryStreamExt};
-use log::info;
+use std::{
+  env,
+  io::Error,
+  sync::{Arc, Mutex},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use jatsi_shared::protocol::ClientMessage;
+use log::{info, warn};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::accounts::Accounts;
+use crate::database::Database;
+use crate::lobby::{Lobby, Session};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -10,36 +27,114 @@ async fn main() -> Result<(), Error> {
   let addr = env::args()
     .nth(1)
     .unwrap_or_else(|| "127.0.0.1:8088".to_string());
+  let http_addr = env::args()
+    .nth(2)
+    .unwrap_or_else(|| "127.0.0.1:8089".to_string());
 
   // Create the event loop and TCP listener we'll accept connections on.
   let try_socket = TcpListener::bind(&addr).await;
   let listener = try_socket.expect("Failed to bind");
   info!("Listening on: {}", addr);
 
+  let database_path = env::var("JATSI_DATABASE").unwrap_or_else(|_| "jatsi.sqlite3".to_string());
+  // Finished games are recorded through a connection of their own, outside the lock of the lobby
+  let games_database = Database::open(&database_path).expect("Failed to open the database");
+  let games_database = Arc::new(Mutex::new(games_database));
+  info!("Using the database at {}", database_path);
+
+  let lobby = Arc::new(Mutex::new(Lobby::new()));
+  let accounts_database = Database::open(&database_path).expect("Failed to open the database");
+  let accounts = Arc::new(Accounts::new(accounts_database));
+
+  // The HTTP API reads the database through a connection of its own
+  let http_database = Database::open(&database_path).expect("Failed to open the database");
+  let http_listener = TcpListener::bind(&http_addr)
+    .await
+    .expect("Failed to bind the HTTP API");
+  info!("HTTP API listening on: {}", http_addr);
+  tokio::spawn(async move {
+    let router = http::router(Arc::new(Mutex::new(http_database)));
+    if let Err(err) = axum::serve(http_listener, router).await {
+      warn!("The HTTP API stopped: {}", err);
+    }
+  });
+
   while let Ok((stream, _)) = listener.accept().await {
-    tokio::spawn(accept_connection(stream));
+    tokio::spawn(accept_connection(
+      stream,
+      lobby.clone(),
+      accounts.clone(),
+      games_database.clone(),
+    ));
   }
 
   Ok(())
 }
 
-async fn accept_connection(stream: TcpStream) {
+async fn accept_connection(
+  stream: TcpStream,
+  lobby: Arc<Mutex<Lobby>>,
+  accounts: Arc<Accounts>,
+  games_database: Arc<Mutex<Database>>,
+) {
   let addr = stream
     .peer_addr()
     .expect("connected streams should have a peer address");
   info!("Peer address: {}", addr);
 
-  let ws_stream = tokio_tungstenite::accept_async(stream)
-    .await
-    .expect("Error during the websocket handshake occurred");
+  let ws_stream = match tokio_tungstenite::accept_async(stream).await {
+    Ok(ws_stream) => ws_stream,
+    Err(err) => {
+      warn!(
+        "Error during the websocket handshake with {}: {}",
+        addr, err
+      );
+      return;
+    }
+  };
 
   info!("New WebSocket connection: {}", addr);
 
-  let (write, read) = ws_stream.split();
-  // We should not forward messages other than text or binary.
-  read
-    .try_filter(|msg| future::ready(msg.is_text() || msg.is_binary()))
-    .forward(write)
-    .await
-    .expect("Failed to forward messages")
+  let (mut write, mut read) = ws_stream.split();
+
+  // Rooms send messages to the client through a channel, as they may do so on behalf of other connections.
+  let (sender, mut receiver) = unbounded_channel();
+  let forward = tokio::spawn(async move {
+    while let Some(msg) = receiver.recv().await {
+      if write.send(msg).await.is_err() {
+        break;
+      }
+    }
+  });
+
+  let mut session = Session::default();
+
+  while let Some(Ok(msg)) = read.next().await {
+    if !msg.is_text() {
+      continue;
+    }
+
+    match serde_json::from_str::<ClientMessage>(msg.to_text().unwrap_or_default()) {
+      Ok(client_msg) if Accounts::handles(&client_msg) => {
+        accounts
+          .handle(&mut session, &sender, addr.ip(), client_msg)
+          .await
+      }
+      Ok(client_msg) => {
+        let finished = {
+          let mut lobby = lobby.lock().unwrap();
+          lobby.handle(&mut session, &sender, client_msg);
+          lobby.take_finished()
+        };
+        for game in finished {
+          game.record(games_database.clone());
+        }
+      }
+      Err(err) => warn!("Invalid message from {}: {}", addr, err),
+    }
+  }
+
+  info!("Disconnected: {}", addr);
+  lobby.lock().unwrap().disconnect(&mut session);
+  forward.abort();
 }
@@ -0,0 +1,597 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use jatsi_shared::chat::{validate_chat, ChatBody, ChatMessage, CHAT_BURST, CHAT_WINDOW_SECS};
+use jatsi_shared::errors::{InvalidAction, InvalidChat};
+use jatsi_shared::game::{Game, GameMessage, PlayerMessage, SavedGame, State};
+use jatsi_shared::protocol::{Account, ServerMessage};
+use jatsi_shared::rules::Ruleset;
+use log::{info, warn};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::tungstenite::Message;
+
+/// The most chat messages a room keeps to catch up new clients with. Older ones are forgotten.
+const MAX_CHAT_HISTORY: usize = 200;
+
+struct Client {
+  id: usize,
+  sender: UnboundedSender<Message>,
+  player: Option<usize>,
+  /// The name the client plays under, if it joined a team.
+  member: Option<String>,
+  account: Option<Account>,
+  /// When the client last chatted, at most `CHAT_BURST` times.
+  chat_times: VecDeque<Instant>,
+}
+
+/// A game and the clients connected to it. The room is the leader of the game: it prepares the actions of the
+/// players, commits them and broadcasts the results to all clients.
+pub struct Room {
+  code: String,
+  game: Game,
+  clients: Vec<Client>,
+  next_client_id: usize,
+  chat: Vec<ChatMessage>,
+  /// The account of each player, if they were logged in when joining. Teams have none.
+  player_accounts: Vec<Option<i64>>,
+  /// The names of the only players allowed to join, for tournament tables. The game starts once all have joined.
+  reserved: Option<Vec<String>>,
+}
+
+impl Room {
+  pub fn new(code: String, ruleset: Ruleset) -> Self {
+    Self {
+      code,
+      game: Game::new(ruleset),
+      clients: Vec::new(),
+      next_client_id: 0,
+      chat: Vec::new(),
+      player_accounts: Vec::new(),
+      reserved: None,
+    }
+  }
+
+  /// A room for a tournament table, only accepting the players seated at it.
+  pub fn reserved(code: String, ruleset: Ruleset, names: Vec<String>) -> Self {
+    Self {
+      reserved: Some(names),
+      ..Self::new(code, ruleset)
+    }
+  }
+
+  /// Adds a client to the room and brings it up to date with the message history and the chat.
+  /// Returns the id of the client used with the other methods.
+  pub fn connect(&mut self, sender: UnboundedSender<Message>, account: Option<Account>) -> usize {
+    let id = self.next_client_id;
+    self.next_client_id += 1;
+
+    let client = Client {
+      id,
+      sender,
+      player: None,
+      member: None,
+      account,
+      chat_times: VecDeque::new(),
+    };
+    send(
+      &client.sender,
+      &ServerMessage::RoomEntered {
+        code: self.code.clone(),
+        ruleset: self.game.ruleset.clone(),
+      },
+    );
+    for msg in self.game.message_history.iter() {
+      send(&client.sender, &ServerMessage::Game(msg.clone()));
+    }
+    for msg in self.chat.iter() {
+      send(&client.sender, &ServerMessage::Chat(msg.clone()));
+    }
+    self.clients.push(client);
+
+    id
+  }
+
+  pub fn disconnect(&mut self, client_id: usize) {
+    self.clients.retain(|client| client.id != client_id);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.clients.is_empty()
+  }
+
+  /// Players can only join before the game has started, and only with a reserved name if the room has them.
+  pub fn accepts(&self, name: &str) -> bool {
+    let reserved = match &self.reserved {
+      Some(names) => names
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name)),
+      None => true,
+    };
+    self.game.state == State::Start && reserved
+  }
+
+  /// The player of a tournament table going by `name` that no client plays, once the game has started. Players who
+  /// lost their connection take their seat back with it.
+  pub fn vacant_seat(&self, name: &str) -> Option<usize> {
+    if self.reserved.is_none() || self.game.state == State::Start {
+      return None;
+    }
+    let player = self
+      .game
+      .players
+      .iter()
+      .position(|player| player.name.eq_ignore_ascii_case(name))?;
+    let taken = self
+      .clients
+      .iter()
+      .any(|client| client.player == Some(player));
+    (!taken).then_some(player)
+  }
+
+  pub fn game(&self) -> &Game {
+    &self.game
+  }
+
+  /// The game along with the chat, as stored when it has finished.
+  pub fn saved_game(&self) -> SavedGame {
+    SavedGame {
+      chat: self.chat.clone(),
+      ..self.game.save()
+    }
+  }
+
+  pub fn player_accounts(&self) -> &[Option<i64>] {
+    &self.player_accounts
+  }
+
+  /// Processes an action by a client. Accepted actions are committed and broadcast to all clients,
+  /// rejected ones are reported back to the sender only. Returns whether the action finished the game.
+  pub fn handle(&mut self, client_id: usize, msg: &PlayerMessage) -> bool {
+    let client = match self.clients.iter().find(|client| client.id == client_id) {
+      Some(client) => client,
+      None => return false,
+    };
+
+    if let (PlayerMessage::JoinGame(name), None) = (msg, client.player) {
+      if let Some(player) = self.vacant_seat(name) {
+        info!("{} rejoined as player {}", name, player);
+        if let Some(client) = self
+          .clients
+          .iter_mut()
+          .find(|client| client.id == client_id)
+        {
+          client.player = Some(player);
+          send(&client.sender, &ServerMessage::Joined(player));
+        }
+        return false;
+      }
+    }
+
+    let from_player = match (msg, client.player) {
+      (PlayerMessage::JoinGame(name), None) if !self.accepts(name) => {
+        reject(client, InvalidAction::WrongState);
+        return false;
+      }
+      // Tournament tables are played alone
+      (PlayerMessage::JoinTeam(_, name), None)
+        if !self.accepts(name) || self.reserved.is_some() =>
+      {
+        reject(client, InvalidAction::WrongState);
+        return false;
+      }
+      (PlayerMessage::JoinGame(..), None) => self.game.players.len(),
+      (PlayerMessage::JoinTeam(team, _), None) => self
+        .game
+        .player_index(team)
+        .unwrap_or(self.game.players.len()),
+      (PlayerMessage::JoinGame(..) | PlayerMessage::JoinTeam(..), Some(_)) => {
+        reject(client, InvalidAction::WrongState);
+        return false;
+      }
+      // The team in turn only accepts actions from the member taking the turn
+      (_, Some(player))
+        if player == self.game.player_in_turn
+          && self.game.member_in_turn().is_some()
+          && self.game.member_in_turn() != client.member.as_deref() =>
+      {
+        reject(client, InvalidAction::NotYourTurn);
+        return false;
+      }
+      (_, Some(player)) => player,
+      (_, None) => {
+        reject(client, InvalidAction::NotYourTurn);
+        return false;
+      }
+    };
+
+    let messages = match self.game.prepare(from_player, msg) {
+      Ok(messages) => messages,
+      Err(err) => {
+        reject(client, err);
+        return false;
+      }
+    };
+    if let Err(err) = self.commit(&messages) {
+      // prepare should have caught this
+      warn!("Failed to commit {:?}: {}", messages, err);
+      if let Some(client) = self.clients.iter().find(|client| client.id == client_id) {
+        reject(client, err);
+      }
+      return false;
+    }
+
+    if let PlayerMessage::JoinGame(name) | PlayerMessage::JoinTeam(_, name) = msg {
+      info!("{} joined as player {}", name, from_player);
+      if let Some(client) = self
+        .clients
+        .iter_mut()
+        .find(|client| client.id == client_id)
+      {
+        client.player = Some(from_player);
+        match msg {
+          PlayerMessage::JoinTeam(..) => {
+            client.member = Some(name.clone());
+            if from_player == self.player_accounts.len() {
+              self.player_accounts.push(None);
+            }
+          }
+          _ => self
+            .player_accounts
+            .push(client.account.as_ref().map(|account| account.id)),
+        }
+        send(&client.sender, &ServerMessage::Joined(from_player));
+      }
+
+      // Tournament tables start by themselves, as there is no host
+      let full = self.reserved.as_ref().map(Vec::len) == Some(self.game.players.len());
+      if full {
+        let started = self
+          .game
+          .prepare(0, &PlayerMessage::StartGame)
+          .and_then(|messages| self.commit(&messages));
+        if let Err(err) = started {
+          warn!("Failed to start the game of room {}: {}", self.code, err);
+        }
+      }
+    }
+
+    messages.contains(&GameMessage::GameFinished)
+  }
+
+  /// Commits prepared messages and broadcasts them to all clients. The messages are committed to a copy of the game
+  /// first, so that if any of them fails, none are committed or broadcast and the clients stay in sync.
+  fn commit(&mut self, messages: &[GameMessage]) -> Result<(), InvalidAction> {
+    let mut game = self.game.clone();
+    for message in messages.iter() {
+      game.commit(message)?;
+    }
+    self.game = game;
+
+    for message in messages.iter() {
+      for client in self.clients.iter() {
+        send(&client.sender, &ServerMessage::Game(message.clone()));
+      }
+    }
+    Ok(())
+  }
+
+  /// Stamps a chat message or reaction by a player and broadcasts it to all clients.
+  pub fn chat(&mut self, client_id: usize, body: ChatBody) {
+    let client = match self
+      .clients
+      .iter_mut()
+      .find(|client| client.id == client_id)
+    {
+      Some(client) => client,
+      None => return,
+    };
+
+    let sender = match (&client.member, client.player) {
+      (Some(member), _) => member.clone(),
+      (None, Some(player)) => self.game.players[player].name.clone(),
+      (None, None) => {
+        return send(
+          &client.sender,
+          &ServerMessage::ChatRejected(InvalidChat::NotJoined),
+        )
+      }
+    };
+    let body = match body {
+      ChatBody::Text(text) => match validate_chat(&text) {
+        Ok(text) => ChatBody::Text(text),
+        Err(err) => return send(&client.sender, &ServerMessage::ChatRejected(err)),
+      },
+      reaction => reaction,
+    };
+
+    let now = Instant::now();
+    let window = Duration::from_secs(CHAT_WINDOW_SECS);
+    while let Some(&time) = client.chat_times.front() {
+      if now.duration_since(time) < window {
+        break;
+      }
+      client.chat_times.pop_front();
+    }
+    if client.chat_times.len() >= CHAT_BURST {
+      return send(
+        &client.sender,
+        &ServerMessage::ChatRejected(InvalidChat::TooFast),
+      );
+    }
+    client.chat_times.push_back(now);
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|since_epoch| since_epoch.as_millis() as u64)
+      .unwrap_or(0);
+
+    let msg = ChatMessage {
+      sender,
+      timestamp,
+      body,
+    };
+    for client in self.clients.iter() {
+      send(&client.sender, &ServerMessage::Chat(msg.clone()));
+    }
+    self.chat.push(msg);
+    if self.chat.len() > MAX_CHAT_HISTORY {
+      self.chat.remove(0);
+    }
+  }
+}
+
+fn reject(client: &Client, err: InvalidAction) {
+  send(&client.sender, &ServerMessage::Rejected(err));
+}
+
+pub fn send(sender: &UnboundedSender<Message>, msg: &ServerMessage) {
+  let text = serde_json::to_string(msg).expect("server messages should serialize");
+  // A closed channel means the client is disconnecting and will soon be removed
+  let _ = sender.send(Message::text(text));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use jatsi_shared::chat::Reaction;
+  use jatsi_shared::game::GameMessage;
+  use jatsi_shared::rules::mini_rules;
+  use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+  fn received(receiver: &mut UnboundedReceiver<Message>) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+    while let Ok(msg) = receiver.try_recv() {
+      messages.push(serde_json::from_str(msg.to_text().unwrap()).unwrap());
+    }
+    messages
+  }
+
+  #[test]
+  fn test_room() {
+    let mut room = Room::new("ABCDE".into(), mini_rules());
+    let entered = ServerMessage::RoomEntered {
+      code: "ABCDE".into(),
+      ruleset: mini_rules(),
+    };
+    let (henry_sender, mut henry) = unbounded_channel();
+    let (bobby_sender, mut bobby) = unbounded_channel();
+
+    let henry_id = room.connect(henry_sender, None);
+    room.handle(henry_id, &PlayerMessage::JoinGame("Henry".into()));
+    assert_eq!(
+      received(&mut henry),
+      [
+        entered.clone(),
+        ServerMessage::Game(GameMessage::PlayerMessage(
+          0,
+          PlayerMessage::JoinGame("Henry".into())
+        )),
+        ServerMessage::Joined(0),
+      ]
+    );
+
+    let bobby_id = room.connect(bobby_sender, None);
+    assert_eq!(
+      received(&mut bobby),
+      [
+        entered,
+        ServerMessage::Game(GameMessage::PlayerMessage(
+          0,
+          PlayerMessage::JoinGame("Henry".into())
+        ))
+      ],
+      "a new client catches up with the history"
+    );
+
+    room.handle(bobby_id, &PlayerMessage::StartGame);
+    assert_eq!(
+      received(&mut bobby),
+      [ServerMessage::Rejected(InvalidAction::NotYourTurn)],
+      "spectators cannot act"
+    );
+
+    room.handle(henry_id, &PlayerMessage::StartGame);
+    let expected = [
+      ServerMessage::Game(GameMessage::PlayerMessage(0, PlayerMessage::StartGame)),
+      ServerMessage::Game(GameMessage::PlayerTurn(0)),
+    ];
+    assert_eq!(received(&mut henry), expected);
+    assert_eq!(
+      received(&mut bobby),
+      expected,
+      "everyone sees the game progress"
+    );
+  }
+
+  #[test]
+  fn test_chat() {
+    let mut room = Room::new("ABCDE".into(), mini_rules());
+    let (henry_sender, mut henry) = unbounded_channel();
+    let (bobby_sender, mut bobby) = unbounded_channel();
+
+    let henry_id = room.connect(henry_sender, None);
+    room.handle(henry_id, &PlayerMessage::JoinGame("Henry".into()));
+    let bobby_id = room.connect(bobby_sender, None);
+    received(&mut henry);
+    received(&mut bobby);
+
+    room.chat(bobby_id, ChatBody::Text("hi".into()));
+    assert_eq!(
+      received(&mut bobby),
+      [ServerMessage::ChatRejected(InvalidChat::NotJoined)],
+      "spectators cannot chat"
+    );
+
+    room.chat(henry_id, ChatBody::Text("   ".into()));
+    assert_eq!(
+      received(&mut henry),
+      [ServerMessage::ChatRejected(InvalidChat::Empty)]
+    );
+
+    room.chat(henry_id, ChatBody::Text(" good luck ".into()));
+    let chat = match received(&mut bobby).as_slice() {
+      [ServerMessage::Chat(chat)] => chat.clone(),
+      other => panic!("expected a chat message, got {:?}", other),
+    };
+    assert_eq!(chat.sender, "Henry");
+    assert_eq!(chat.body, ChatBody::Text("good luck".into()));
+    assert!(chat.timestamp > 0);
+    assert_eq!(received(&mut henry), [ServerMessage::Chat(chat.clone())]);
+
+    let (japsu_sender, mut japsu) = unbounded_channel();
+    room.connect(japsu_sender, None);
+    assert_eq!(
+      received(&mut japsu).last(),
+      Some(&ServerMessage::Chat(chat)),
+      "a new client catches up with the chat"
+    );
+    assert_eq!(
+      room.game.message_history.len(),
+      1,
+      "chat is not part of the game"
+    );
+
+    for _ in 1..CHAT_BURST {
+      room.chat(henry_id, ChatBody::Text("gg".into()));
+    }
+    received(&mut henry);
+    room.chat(henry_id, ChatBody::Text("gg".into()));
+    assert_eq!(
+      received(&mut henry),
+      [ServerMessage::ChatRejected(InvalidChat::TooFast)],
+      "chatting is throttled"
+    );
+    assert_eq!(room.chat.len(), CHAT_BURST);
+
+    let earlier = room.chat[0].clone();
+    room.chat = vec![earlier; MAX_CHAT_HISTORY];
+    room.clients[0].chat_times.clear();
+    room.chat(henry_id, ChatBody::Reaction(Reaction::Laugh));
+    assert_eq!(room.chat.len(), MAX_CHAT_HISTORY, "old chat is forgotten");
+    assert_eq!(
+      room.chat.last().unwrap().body,
+      ChatBody::Reaction(Reaction::Laugh)
+    );
+  }
+
+  #[test]
+  fn test_teams() {
+    let mut room = Room::new("ABCDE".into(), mini_rules());
+    let (henry_sender, mut henry) = unbounded_channel();
+    let (bobby_sender, mut bobby) = unbounded_channel();
+
+    let henry_id = room.connect(henry_sender, None);
+    room.handle(
+      henry_id,
+      &PlayerMessage::JoinTeam("Red".into(), "Henry".into()),
+    );
+    let bobby_id = room.connect(bobby_sender, None);
+    room.handle(
+      bobby_id,
+      &PlayerMessage::JoinTeam("Red".into(), "Bobby".into()),
+    );
+    assert_eq!(received(&mut bobby).last(), Some(&ServerMessage::Joined(0)));
+    assert_eq!(room.player_accounts(), [None], "teams have no account");
+
+    room.handle(bobby_id, &PlayerMessage::StartGame);
+    assert_eq!(
+      received(&mut bobby).last(),
+      Some(&ServerMessage::Rejected(InvalidAction::NotYourTurn)),
+      "Henry takes the first turn of the team"
+    );
+    room.handle(henry_id, &PlayerMessage::StartGame);
+    room.handle(henry_id, &PlayerMessage::Roll);
+    room.handle(henry_id, &PlayerMessage::Place(0));
+    assert_eq!(room.game.member_in_turn(), Some("Bobby"));
+    received(&mut henry);
+    room.handle(henry_id, &PlayerMessage::Roll);
+    assert_eq!(
+      received(&mut henry),
+      [ServerMessage::Rejected(InvalidAction::NotYourTurn)]
+    );
+
+    room.chat(bobby_id, ChatBody::Text("my turn".into()));
+    assert!(
+      matches!(received(&mut henry).as_slice(), [ServerMessage::Chat(chat)] if chat.sender == "Bobby"),
+      "members chat under their own names"
+    );
+  }
+
+  #[test]
+  fn test_reserved() {
+    let names = vec!["Henry".to_string(), "Bobby".to_string()];
+    let mut room = Room::reserved("ABCDE".into(), mini_rules(), names);
+    let (sender, mut receiver) = unbounded_channel();
+
+    let japsu_id = room.connect(sender.clone(), None);
+    assert!(!room.accepts("Japsu"));
+    room.handle(japsu_id, &PlayerMessage::JoinGame("Japsu".into()));
+    assert_eq!(
+      received(&mut receiver).last(),
+      Some(&ServerMessage::Rejected(InvalidAction::WrongState)),
+      "only seated players can join"
+    );
+
+    let mut client_ids = Vec::new();
+    for name in ["Henry", "bobby"] {
+      let client_id = room.connect(sender.clone(), None);
+      assert!(room.accepts(name));
+      room.handle(client_id, &PlayerMessage::JoinGame(name.into()));
+      client_ids.push(client_id);
+    }
+    assert_eq!(
+      room.game.state,
+      State::FirstRoll,
+      "the game starts when everyone has joined"
+    );
+
+    assert_eq!(room.vacant_seat("Bobby"), None, "Bobby is still here");
+    room.disconnect(client_ids[1]);
+    assert_eq!(room.vacant_seat("Bobby"), Some(1));
+    received(&mut receiver);
+    let bobby_id = room.connect(sender.clone(), None);
+    room.handle(bobby_id, &PlayerMessage::JoinGame("Bobby".into()));
+    assert_eq!(
+      received(&mut receiver).last(),
+      Some(&ServerMessage::Joined(1)),
+      "players who left take their seat back"
+    );
+    assert_eq!(room.game.players.len(), 2);
+  }
+
+  #[test]
+  fn test_failed_commit() {
+    let mut room = Room::new("ABCDE".into(), mini_rules());
+    let (henry_sender, mut henry) = unbounded_channel();
+    let henry_id = room.connect(henry_sender, None);
+    room.handle(henry_id, &PlayerMessage::JoinGame("Henry".into()));
+    received(&mut henry);
+
+    let messages = [
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Bobby".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::ToggleHold(99)),
+    ];
+    assert_eq!(room.commit(&messages), Err(InvalidAction::OutOfBounds));
+    assert_eq!(room.game().players.len(), 1, "nothing was committed");
+    assert_eq!(received(&mut henry), [], "nothing was broadcast");
+  }
+}
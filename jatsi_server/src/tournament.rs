@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use jatsi_shared::protocol::{Account, ServerMessage};
+use jatsi_shared::tournament::Tournament;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::room::send;
+
+/// Who registered a player: their account, or the connection of a guest.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Owner {
+  Account(i64),
+  Client(usize),
+}
+
+impl Owner {
+  fn of(account: &Option<Account>, client_id: usize) -> Self {
+    match account {
+      Some(account) => Self::Account(account.id),
+      None => Self::Client(client_id),
+    }
+  }
+}
+
+/// A tournament and the clients following it. The games of its tables are played in rooms of their own.
+pub struct TournamentRoom {
+  code: String,
+  tournament: Tournament,
+  /// The first client still connected is the organizer, who starts the tournament.
+  clients: Vec<(usize, UnboundedSender<Message>)>,
+  next_client_id: usize,
+  /// Who registered each player. Only they can sit at the tables of the player.
+  owners: HashMap<usize, Owner>,
+}
+
+impl TournamentRoom {
+  pub fn new(code: String, tournament: Tournament) -> Self {
+    Self {
+      code,
+      tournament,
+      clients: Vec::new(),
+      next_client_id: 0,
+      owners: HashMap::new(),
+    }
+  }
+
+  /// Adds a client and sends it the tournament. Returns the id of the client.
+  pub fn connect(&mut self, sender: UnboundedSender<Message>) -> usize {
+    let id = self.next_client_id;
+    self.next_client_id += 1;
+
+    send(&sender, &self.message());
+    self.clients.push((id, sender));
+    id
+  }
+
+  pub fn disconnect(&mut self, client_id: usize) {
+    self.clients.retain(|(id, _)| *id != client_id);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.clients.is_empty()
+  }
+
+  pub fn is_organizer(&self, client_id: usize) -> bool {
+    self.clients.first().map(|(id, _)| *id) == Some(client_id)
+  }
+
+  /// Binds a player to the account or connection that registered them. Guests coming back on a new connection take
+  /// their player back once the old connection has left.
+  pub fn claim(&mut self, player: usize, account: &Option<Account>, client_id: usize) {
+    let owner = Owner::of(account, client_id);
+    match self.owners.get(&player) {
+      Some(Owner::Client(old)) if self.clients.iter().any(|(id, _)| id == old) => {}
+      Some(Owner::Account(_)) => {}
+      _ => {
+        self.owners.insert(player, owner);
+      }
+    }
+  }
+
+  /// Whether the account or the tournament connection `client_id` registered the player.
+  pub fn owns(&self, player: usize, account: &Option<Account>, client_id: Option<usize>) -> bool {
+    match self.owners.get(&player) {
+      Some(Owner::Account(id)) => account.as_ref().map(|account| account.id) == Some(*id),
+      Some(Owner::Client(id)) => client_id == Some(*id),
+      None => false,
+    }
+  }
+
+  pub fn tournament(&self) -> &Tournament {
+    &self.tournament
+  }
+
+  /// Changes to the tournament are broadcast with `broadcast`.
+  pub fn tournament_mut(&mut self) -> &mut Tournament {
+    &mut self.tournament
+  }
+
+  fn message(&self) -> ServerMessage {
+    ServerMessage::Tournament {
+      code: self.code.clone(),
+      tournament: self.tournament.clone(),
+    }
+  }
+
+  pub fn broadcast(&self) {
+    let msg = self.message();
+    for (_, sender) in self.clients.iter() {
+      send(sender, &msg);
+    }
+  }
+}
@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+
+use crate::errors::InvalidAction;
+use crate::game::{Game, GameMessage, Player, PlayerMessage, State};
+use crate::probability::{reroll_odds, solver_work, TurnSolver};
+use crate::rules::Ruleset;
+
+/// A single decision made by a player during their turn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecisionKind {
+  /// The player rolled again holding `keep`, while holding `best_keep` would have been optimal.
+  Hold {
+    roll: Vec<u64>,
+    keep: Vec<bool>,
+    best_keep: Vec<bool>,
+  },
+  /// The player placed their roll on `row` of `column`, while `best_row` of `best_column` would have been worth the
+  /// most. If rolling again would have been better than placing at all, `best_keep` tells which dice to hold.
+  Place {
+    roll: Vec<u64>,
+    column: usize,
+    row: usize,
+    best_column: usize,
+    best_row: usize,
+    best_keep: Option<Vec<bool>>,
+  },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decision {
+  pub kind: DecisionKind,
+  /// What the player could expect the turn to be worth with the decision they made: the points it gains less the
+  /// points the cell it uses up could be expected to score later. See `analyze`.
+  pub expected_value: f64,
+  /// What the player could expect the turn to be worth with the best decision.
+  pub best_expected_value: f64,
+}
+
+impl Decision {
+  /// Expected points lost by this decision compared to the best one.
+  pub fn loss(&self) -> f64 {
+    self.best_expected_value - self.expected_value
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TurnReport {
+  pub round: usize,
+  pub decisions: Vec<Decision>,
+}
+
+impl TurnReport {
+  pub fn loss(&self) -> f64 {
+    self.decisions.iter().map(Decision::loss).sum()
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerReport {
+  pub name: String,
+  pub turns: Vec<TurnReport>,
+}
+
+impl PlayerReport {
+  pub fn loss(&self) -> f64 {
+    self.turns.iter().map(TurnReport::loss).sum()
+  }
+
+  /// The decision that cost the player the most points, if any cost anything.
+  pub fn costliest(&self) -> Option<(usize, &Decision)> {
+    self
+      .turns
+      .iter()
+      .flat_map(|turn| {
+        turn
+          .decisions
+          .iter()
+          .map(move |decision| (turn.round, decision))
+      })
+      .filter(|(_, decision)| decision.loss() > LOSS_EPSILON)
+      .max_by(|(_, a), (_, b)| a.loss().total_cmp(&b.loss()))
+  }
+}
+
+/// Per-player report of how well each decision of a game was played, indexed like `Game::players`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalysisReport {
+  pub players: Vec<PlayerReport>,
+  /// The dice were too many to weigh the holds and the later turns in reasonable time. Only the placements were
+  /// analyzed, by the points they gained right away.
+  pub approximate: bool,
+}
+
+/// The most `solver_work` times rolls per turn that is analyzed fully, which takes a few seconds at most. Classic
+/// rules take less than half of this.
+pub const MAX_ANALYSIS_WORK: f64 = 3e6;
+
+/// Losses smaller than this are rounding errors, not mistakes.
+const LOSS_EPSILON: f64 = 1e-9;
+
+/// Replays the message history of `game` and evaluates each hold and row selection made.
+///
+/// Decisions are evaluated one turn at a time, counting what a turn costs the later ones: the value of placing a roll
+/// on a cell is the points it gains less the points the cell could be expected to score if it were saved for a later
+/// turn, in which every hold aims at that cell alone. This is an approximation. It overrates the later turns, as not
+/// all of them can aim at a single cell, and it leaves out the bonus still to be earned.
+pub fn analyze(game: &Game) -> Result<AnalysisReport, InvalidAction> {
+  let mut analyzer = Analyzer::new(game);
+  while analyzer.step()? {}
+  Ok(analyzer.report())
+}
+
+/// Analyzes a game a turn at a time, so that a long analysis can be spread out, like `analyze` does all at once.
+pub struct Analyzer<'a> {
+  game: &'a Game,
+  replay: Game,
+  /// Index of the next message of the history to analyze.
+  next: usize,
+  players: Vec<PlayerReport>,
+  /// The later value of each cell by the number of rolls in a turn, which handicaps may change.
+  later_values: HashMap<u64, Vec<Vec<f64>>>,
+  turn: Option<TurnEvaluator<'a>>,
+  approximate: bool,
+}
+
+impl<'a> Analyzer<'a> {
+  pub fn new(game: &'a Game) -> Self {
+    let extra_rolls = game
+      .players
+      .iter()
+      .map(|player| player.handicap.extra_rolls)
+      .max()
+      .unwrap_or(0);
+    let rolls = game.ruleset.rolls.saturating_add(extra_rolls);
+    let work = solver_work(&game.ruleset.dice) * rolls as f64;
+
+    Self {
+      game,
+      replay: Game::new(game.ruleset.clone()),
+      next: 0,
+      players: Vec::new(),
+      later_values: HashMap::new(),
+      turn: None,
+      approximate: work > MAX_ANALYSIS_WORK,
+    }
+  }
+
+  /// Analyzes the messages up to the start of the next turn. Returns whether any messages are left.
+  pub fn step(&mut self) -> Result<bool, InvalidAction> {
+    let history = &self.game.message_history;
+    while let Some(msg) = history.get(self.next) {
+      self.analyze(msg)?;
+      self.next += 1;
+      if let Some(GameMessage::PlayerTurn(_)) = history.get(self.next) {
+        break;
+      }
+    }
+    Ok(self.next < history.len())
+  }
+
+  /// The number of messages analyzed so far and the number of messages in all.
+  pub fn progress(&self) -> (usize, usize) {
+    (self.next, self.game.message_history.len())
+  }
+
+  pub fn report(self) -> AnalysisReport {
+    AnalysisReport {
+      players: self.players,
+      approximate: self.approximate,
+    }
+  }
+
+  fn analyze(&mut self, msg: &GameMessage) -> Result<(), InvalidAction> {
+    let ruleset = &self.game.ruleset;
+    match msg {
+      GameMessage::PlayerMessage(_, PlayerMessage::JoinGame(name)) => {
+        self.players.push(PlayerReport {
+          name: name.clone(),
+          turns: Vec::new(),
+        })
+      }
+      // Teams are analyzed as one player
+      GameMessage::PlayerMessage(_, PlayerMessage::JoinTeam(team, _))
+        if self.replay.player_index(team).is_none() =>
+      {
+        self.players.push(PlayerReport {
+          name: team.clone(),
+          turns: Vec::new(),
+        })
+      }
+      GameMessage::PlayerTurn(player) => {
+        let round = if *player == 0 {
+          self.replay.round + 1
+        } else {
+          self.replay.round
+        };
+        self
+          .players
+          .get_mut(*player)
+          .ok_or(InvalidAction::OutOfBounds)?
+          .turns
+          .push(TurnReport {
+            round,
+            decisions: Vec::new(),
+          });
+        self.turn = None;
+      }
+      GameMessage::PlayerMessage(player, PlayerMessage::Roll)
+        if self.replay.state == State::Reroll && !self.approximate =>
+      {
+        let evaluator = evaluator(
+          &mut self.turn,
+          &mut self.later_values,
+          ruleset,
+          &self.replay,
+        )?;
+        let decision = evaluator.evaluate_hold(&self.replay);
+        push_decision(&mut self.players, *player, decision)?;
+      }
+      GameMessage::PlayerMessage(player, player_msg) if player_msg.placement().is_some() => {
+        let (column, row) = player_msg.placement().expect("the message is a placement");
+        let decision = if self.approximate {
+          evaluate_place_now(&self.replay, column, row)?
+        } else {
+          let evaluator = evaluator(
+            &mut self.turn,
+            &mut self.later_values,
+            ruleset,
+            &self.replay,
+          )?;
+          evaluator.evaluate_place(&self.replay, column, row)?
+        };
+        push_decision(&mut self.players, *player, decision)?;
+      }
+      _ => {}
+    }
+
+    self.replay.commit(msg)
+  }
+}
+
+/// The evaluator of the turn in progress in `game`. A new one is needed once a row is announced, as that changes the
+/// values.
+fn evaluator<'a, 'b>(
+  turn: &'b mut Option<TurnEvaluator<'a>>,
+  later_values: &mut HashMap<u64, Vec<Vec<f64>>>,
+  ruleset: &'a Ruleset,
+  game: &Game,
+) -> Result<&'b mut TurnEvaluator<'a>, InvalidAction> {
+  if turn
+    .as_ref()
+    .is_none_or(|turn| turn.announced != game.announced)
+  {
+    let later_values = later_values
+      .entry(game.rolls())
+      .or_insert_with(|| self::later_values(ruleset, game.rolls()))
+      .clone();
+    let player = player_in_turn(game)?.clone();
+    *turn = Some(TurnEvaluator::new(
+      ruleset,
+      player,
+      game.announced,
+      later_values,
+    ));
+  }
+  Ok(turn.as_mut().expect("the evaluator was just made"))
+}
+
+fn push_decision(
+  players: &mut [PlayerReport],
+  player: usize,
+  decision: Decision,
+) -> Result<(), InvalidAction> {
+  players
+    .get_mut(player)
+    .and_then(|player| player.turns.last_mut())
+    .ok_or(InvalidAction::OutOfBounds)?
+    .decisions
+    .push(decision);
+  Ok(())
+}
+
+/// The points each cell of a score sheet could be expected to score in a turn that aims at it alone, indexed by column
+/// and row. The bonus row is left at zero.
+fn later_values(ruleset: &Ruleset, rolls: u64) -> Vec<Vec<f64>> {
+  let dice = ruleset.dice.len();
+  let odds = reroll_odds(ruleset, &vec![0; dice], &vec![false; dice], rolls);
+  ruleset
+    .columns
+    .iter()
+    .map(|&multiplier| {
+      odds
+        .iter()
+        .map(|odds| odds.expected_score * multiplier as f64)
+        .collect()
+    })
+    .collect()
+}
+
+type RollValue<'a> = Box<dyn Fn(&[u64]) -> Vec<f64> + 'a>;
+
+/// Evaluates the decisions of one turn of a player. The expected values solved for one decision are reused for the
+/// next ones of the turn.
+struct TurnEvaluator<'a> {
+  ruleset: &'a Ruleset,
+  player: Player,
+  announced: Option<usize>,
+  later_values: Vec<Vec<f64>>,
+  solver: TurnSolver<'a, RollValue<'a>>,
+}
+
+impl<'a> TurnEvaluator<'a> {
+  fn new(
+    ruleset: &'a Ruleset,
+    player: Player,
+    announced: Option<usize>,
+    later_values: Vec<Vec<f64>>,
+  ) -> Self {
+    let value: RollValue<'a> = {
+      let (player, later_values) = (player.clone(), later_values.clone());
+      Box::new(move |roll: &[u64]| {
+        vec![best_cell(ruleset, &player, announced, &later_values, roll).1]
+      })
+    };
+    Self {
+      ruleset,
+      player,
+      announced,
+      later_values,
+      solver: TurnSolver::new(&ruleset.dice, value),
+    }
+  }
+
+  fn evaluate_hold(&mut self, game: &Game) -> Decision {
+    let rolls_left = rolls_left(game);
+    let expected_value = self
+      .solver
+      .expected_values(&game.roll, &game.keep, rolls_left)[0];
+    let (best_keep, best_expected_value) = self.solver.best_keep(&game.roll, rolls_left);
+
+    Decision {
+      kind: DecisionKind::Hold {
+        roll: game.roll.clone(),
+        keep: game.keep.clone(),
+        best_keep,
+      },
+      expected_value,
+      best_expected_value,
+    }
+  }
+
+  fn evaluate_place(
+    &mut self,
+    game: &Game,
+    column: usize,
+    row: usize,
+  ) -> Result<Decision, InvalidAction> {
+    let (ruleset, player) = (self.ruleset, &self.player);
+    let expected_value = value(
+      ruleset,
+      player,
+      &self.later_values,
+      (column, row),
+      &game.roll,
+    )
+    .ok_or(InvalidAction::NotSelectable)?;
+    let ((best_column, best_row), mut best_expected_value) = best_cell(
+      ruleset,
+      player,
+      self.announced,
+      &self.later_values,
+      &game.roll,
+    );
+
+    let mut best_keep_instead = None;
+    if game.state == State::Reroll {
+      let (keep, continue_value) = self.solver.best_keep(&game.roll, rolls_left(game));
+      if continue_value > best_expected_value + LOSS_EPSILON {
+        best_keep_instead = Some(keep);
+        best_expected_value = continue_value;
+      }
+    }
+
+    Ok(Decision {
+      kind: DecisionKind::Place {
+        roll: game.roll.clone(),
+        column,
+        row,
+        best_column,
+        best_row,
+        best_keep: best_keep_instead,
+      },
+      expected_value,
+      best_expected_value,
+    })
+  }
+}
+
+/// Evaluates a placement by the points it gains right away, for when the turns are too much work to solve.
+fn evaluate_place_now(game: &Game, column: usize, row: usize) -> Result<Decision, InvalidAction> {
+  let (ruleset, player) = (&game.ruleset, player_in_turn(game)?);
+  let no_later_values = vec![vec![0.0; ruleset.scorings.len()]; ruleset.columns.len()];
+  let expected_value = value(ruleset, player, &no_later_values, (column, row), &game.roll)
+    .ok_or(InvalidAction::NotSelectable)?;
+  let ((best_column, best_row), best_expected_value) = best_cell(
+    ruleset,
+    player,
+    game.announced,
+    &no_later_values,
+    &game.roll,
+  );
+
+  Ok(Decision {
+    kind: DecisionKind::Place {
+      roll: game.roll.clone(),
+      column,
+      row,
+      best_column,
+      best_row,
+      best_keep: None,
+    },
+    expected_value,
+    best_expected_value,
+  })
+}
+
+fn player_in_turn(game: &Game) -> Result<&Player, InvalidAction> {
+  game
+    .players
+    .get(game.player_in_turn)
+    .ok_or(InvalidAction::OutOfBounds)
+}
+
+fn rolls_left(game: &Game) -> u64 {
+  game.rolls().saturating_sub(game.times_rolled)
+}
+
+/// Points gained by placing `roll` on a cell, including any bonus it triggers, or `None` if the cell cannot be chosen.
+fn gain(
+  ruleset: &Ruleset,
+  player: &Player,
+  (column, row): (usize, usize),
+  roll: &[u64],
+) -> Option<u64> {
+  let score_sheet = &player.score_sheet;
+  ruleset
+    .place(score_sheet, column, row, roll, &player.handicap)
+    .ok()
+    .map(|new_score_sheet| ruleset.total(&new_score_sheet) - ruleset.total(score_sheet))
+}
+
+/// The value of placing `roll` on a cell: the points gained less the later value of the cell that is used up, or
+/// `None` if the cell cannot be chosen.
+fn value(
+  ruleset: &Ruleset,
+  player: &Player,
+  later_values: &[Vec<f64>],
+  (column, row): (usize, usize),
+  roll: &[u64],
+) -> Option<f64> {
+  gain(ruleset, player, (column, row), roll).map(|points| points as f64 - later_values[column][row])
+}
+
+/// The column and row of most value for `roll` and that value. Only the `announced` row is considered if there is
+/// one.
+fn best_cell(
+  ruleset: &Ruleset,
+  player: &Player,
+  announced: Option<usize>,
+  later_values: &[Vec<f64>],
+  roll: &[u64],
+) -> ((usize, usize), f64) {
+  (0..ruleset.columns.len())
+    .flat_map(|column| (0..ruleset.scorings.len()).map(move |row| (column, row)))
+    .filter(|&(_, row)| announced.is_none_or(|announced| announced == row))
+    .filter_map(|cell| value(ruleset, player, later_values, cell, roll).map(|value| (cell, value)))
+    .fold(
+      None,
+      |best: Option<((usize, usize), f64)>, (cell, value)| match best {
+        Some((_, best_value)) if best_value >= value => best,
+        _ => Some((cell, value)),
+      },
+    )
+    .unwrap_or(((0, 0), 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::{mini_rules, roleplayers_rules};
+
+  #[test]
+  fn test_analyze() {
+    let (sixes, small_straight) = (0, 3);
+    let mut game = Game::new(mini_rules());
+    for msg in [
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+      GameMessage::PlayerTurn(0),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![6, 6, 6, 6, 5]),
+      // Holding the five instead of the sixes. Whatever for?
+      GameMessage::PlayerMessage(0, PlayerMessage::ToggleHold(4)),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![1, 2, 3, 4, 5]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Place(small_straight)),
+      GameMessage::PlayerTurn(0),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![6, 6, 1, 2, 3]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![6, 6, 6, 2, 3]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![6, 6, 6, 6, 3]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Place(sixes)),
+    ] {
+      game.commit(&msg).unwrap();
+    }
+
+    let report = analyze(&game).unwrap();
+    let later_values = &later_values(&game.ruleset, 3)[0];
+    assert_eq!(report.players.len(), 1);
+    let henry = &report.players[0];
+    assert_eq!(henry.name, "Henry");
+    assert_eq!(
+      henry
+        .turns
+        .iter()
+        .map(|turn| turn.round)
+        .collect::<Vec<_>>(),
+      [1, 2]
+    );
+    assert_eq!(
+      henry.turns[0].decisions.len(),
+      2,
+      "one hold and one placement"
+    );
+    assert_eq!(
+      henry.turns[1].decisions.len(),
+      3,
+      "two rerolls without holding and one placement"
+    );
+
+    let (round, costliest) = henry.costliest().unwrap();
+    assert_eq!(round, 1);
+    assert!(
+      matches!(&costliest.kind, DecisionKind::Hold { best_keep, .. } if best_keep == &[true, true, true, true, false]),
+      "holding the five was the costliest decision, holding the sixes would have been better: {:?}",
+      costliest
+    );
+
+    let placement = &henry.turns[0].decisions[1];
+    assert!(
+      matches!(placement.kind, DecisionKind::Place { best_row, .. } if best_row == small_straight),
+      "the straight was the best row for that roll"
+    );
+    assert!(
+      (placement.expected_value - (30.0 - later_values[small_straight])).abs() < LOSS_EPSILON
+    );
+
+    let last = henry.turns[1].decisions.last().unwrap();
+    assert!(
+      last.loss().abs() < LOSS_EPSILON,
+      "the sixes and bonus were the best choice with no rolls left"
+    );
+    assert!((last.expected_value - (24.0 + 50.0 - later_values[sixes])).abs() < LOSS_EPSILON);
+  }
+
+  #[test]
+  fn test_later_values() {
+    let (sixes, full_house, small_straight) = (0, 2, 3);
+    let later_values = &later_values(&mini_rules(), 3)[0];
+    assert!(later_values[full_house] < later_values[sixes]);
+    assert!(later_values[sixes] < later_values[small_straight]);
+
+    let mut game = Game::new(mini_rules());
+    for msg in [
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+      GameMessage::PlayerTurn(0),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![1, 1, 2, 3, 5]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![1, 1, 2, 3, 5]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![1, 1, 2, 3, 5]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Place(sixes)),
+    ] {
+      game.commit(&msg).unwrap();
+    }
+
+    let report = analyze(&game).unwrap();
+    let placement = report.players[0].turns[0].decisions.last().unwrap();
+    assert!(
+      matches!(placement.kind, DecisionKind::Place { best_row, .. } if best_row == full_house),
+      "a roll that scores nothing is best placed on the row least likely to score later: {:?}",
+      placement
+    );
+    assert!(
+      (placement.loss() - (later_values[sixes] - later_values[full_house])).abs() < LOSS_EPSILON
+    );
+  }
+
+  #[test]
+  fn test_approximate() {
+    let mut game = Game::new(roleplayers_rules());
+    let mut messages = vec![
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+    ];
+    for row in [0, 1, 2] {
+      messages.extend([
+        GameMessage::PlayerTurn(0),
+        GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+        GameMessage::RollResult(vec![1, 2, 3, 4, 5]),
+        GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+        GameMessage::RollResult(vec![1, 2, 3, 4, 5]),
+        GameMessage::PlayerMessage(0, PlayerMessage::Place(row)),
+      ]);
+    }
+    for msg in messages.iter() {
+      game.commit(msg).unwrap();
+    }
+
+    let report = analyze(&game).unwrap();
+    assert!(report.approximate, "the dice of the ruleset are too many");
+    assert!(
+      report.players[0].turns.iter().all(|turn| matches!(
+        turn.decisions[..],
+        [Decision {
+          kind: DecisionKind::Place { .. },
+          ..
+        }]
+      )),
+      "only the placements are analyzed"
+    );
+    assert!(!analyze(&Game::new(mini_rules())).unwrap().approximate);
+  }
+}
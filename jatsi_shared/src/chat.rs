@@ -0,0 +1,92 @@
+use serde::{self, Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+use crate::errors::InvalidChat;
+
+/// The longest chat message the server accepts, in characters.
+pub const MAX_CHAT_LENGTH: usize = 300;
+
+/// How many chat messages a player can send within `CHAT_WINDOW_SECS` seconds.
+pub const CHAT_BURST: usize = 5;
+pub const CHAT_WINDOW_SECS: u64 = 10;
+
+/// Quick reactions that can be sent with a single click.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Reaction {
+  Yahtzee,
+  NiceRoll,
+  Ouch,
+  Laugh,
+}
+
+impl Reaction {
+  pub fn all() -> [Reaction; 4] {
+    [Self::Yahtzee, Self::NiceRoll, Self::Ouch, Self::Laugh]
+  }
+}
+
+impl Display for Reaction {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::Yahtzee => write!(f, "🎉 Yahtzee!"),
+      Self::NiceRoll => write!(f, "👍 Nice roll!"),
+      Self::Ouch => write!(f, "😬 Ouch!"),
+      Self::Laugh => write!(f, "😂"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ChatBody {
+  Text(String),
+  Reaction(Reaction),
+}
+
+impl Display for ChatBody {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::Text(text) => write!(f, "{}", text),
+      Self::Reaction(reaction) => write!(f, "{}", reaction),
+    }
+  }
+}
+
+/// A chat message as stamped by the server. Chat is kept apart from the message history of the game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+  pub sender: String,
+  /// Milliseconds since the Unix epoch.
+  pub timestamp: u64,
+  pub body: ChatBody,
+}
+
+/// Trims a chat message and checks that it is neither empty nor too long.
+pub fn validate_chat(text: &str) -> Result<String, InvalidChat> {
+  let text = text.trim();
+  let length = text.chars().count();
+  if length == 0 {
+    Err(InvalidChat::Empty)
+  } else if length > MAX_CHAT_LENGTH {
+    Err(InvalidChat::TooLong(length))
+  } else {
+    Ok(text.to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_validate_chat() {
+    assert_eq!(validate_chat("  gg  "), Ok("gg".into()));
+    assert_eq!(validate_chat(" \n "), Err(InvalidChat::Empty));
+
+    let longest = "ä".repeat(MAX_CHAT_LENGTH);
+    assert_eq!(validate_chat(&longest), Ok(longest.clone()));
+    assert_eq!(
+      validate_chat(&format!("{}!", longest)),
+      Err(InvalidChat::TooLong(MAX_CHAT_LENGTH + 1))
+    );
+  }
+}
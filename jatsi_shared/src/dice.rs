@@ -1,23 +1,124 @@
 use itertools::izip;
 use rand::Rng;
+use serde::{self, Deserialize, Serialize};
 
-pub fn roll_dice(dice: &[u64]) -> Vec<u64> {
+/// The most dice a ruleset can have. The odds are worked out over every subset of the dice to reroll.
+pub const MAX_DICE: usize = 20;
+
+/// The most sides a die can have.
+pub const MAX_SIDES: usize = 1000;
+
+/// The highest value a face can show, which keeps the sums and products of scoring well within bounds.
+pub const MAX_FACE_VALUE: u64 = 1_000_000;
+
+/// A die of a ruleset. Ruleset files give ordinary dice as their number of sides, like `6`, and other dice as the list
+/// of their faces, like `[0, 0, 1, 1, 2, 3]`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Die {
+  /// Faces from one to the number of sides.
+  Sides(u64),
+  /// Faces with the given values, which may repeat.
+  Faces(Vec<u64>),
+}
+
+impl Die {
+  /// The values of the faces, each face equally likely.
+  pub fn faces(&self) -> Vec<u64> {
+    match self {
+      Self::Sides(sides) => (1..=*sides).collect(),
+      Self::Faces(faces) => faces.clone(),
+    }
+  }
+
+  pub fn sides(&self) -> usize {
+    match self {
+      Self::Sides(sides) => *sides as usize,
+      Self::Faces(faces) => faces.len(),
+    }
+  }
+
+  /// The number of sides of an ordinary die, one with faces from one up.
+  pub fn ordinary_sides(&self) -> Option<u64> {
+    match self {
+      Self::Sides(sides) => Some(*sides),
+      Self::Faces(faces) if faces.iter().copied().eq(1..=faces.len() as u64) => {
+        Some(faces.len() as u64)
+      }
+      Self::Faces(..) => None,
+    }
+  }
+
+  fn roll(&self, rng: &mut impl Rng) -> u64 {
+    match self {
+      Self::Sides(sides) => rng.gen_range(1..=*sides),
+      Self::Faces(faces) => faces[rng.gen_range(0..faces.len())],
+    }
+  }
+}
+
+pub fn roll_dice(dice: &[Die]) -> Vec<u64> {
   let mut rng = rand::thread_rng();
   dice
     .iter()
-    .map(|&sides| rng.gen_range(1..=sides))
+    .map(|die| die.roll(&mut rng))
     .collect::<Vec<u64>>()
 }
 
-pub fn roll_dice_keeping(dice: &[u64], old_roll: &[u64], keep: &[bool]) -> Vec<u64> {
+pub fn roll_dice_keeping(dice: &[Die], old_roll: &[u64], keep: &[bool]) -> Vec<u64> {
   let mut rng = rand::thread_rng();
   izip!(dice, old_roll, keep)
-    .map(|(&sides, &old_value, &kept)| {
-      if kept {
-        old_value
-      } else {
-        rng.gen_range(1..=sides)
-      }
-    })
+    .map(
+      |(die, &old_value, &kept)| {
+        if kept {
+          old_value
+        } else {
+          die.roll(&mut rng)
+        }
+      },
+    )
     .collect::<Vec<u64>>()
 }
+
+/// The length of the longest run of consecutive values in `roll`, in any order.
+pub fn longest_run(roll: &[u64]) -> u64 {
+  let mut values = roll.to_vec();
+  values.sort_unstable();
+  values.dedup();
+
+  let mut longest = 0;
+  let mut length = 0;
+  for (i, &value) in values.iter().enumerate() {
+    length = match i {
+      0 => 1,
+      _ if values[i - 1] + 1 == value => length + 1,
+      _ => 1,
+    };
+    longest = longest.max(length);
+  }
+  longest
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_faces() {
+    let die = Die::Faces(vec![0, 0, 1, 1, 2, 3]);
+    assert_eq!(die.sides(), 6);
+    assert_eq!(die.ordinary_sides(), None);
+    assert_eq!(Die::Faces(vec![1, 2, 3, 4]).ordinary_sides(), Some(4));
+    assert_eq!(Die::Sides(6).faces(), [1, 2, 3, 4, 5, 6]);
+
+    let rolls = roll_dice(&vec![die; 100]);
+    assert!(rolls.iter().all(|value| [0, 1, 2, 3].contains(value)));
+    assert!(rolls.contains(&0), "a hundred dice roll at least one zero");
+
+    assert_eq!(
+      serde_json::from_str::<Vec<Die>>("[6, [0, 0, 1, 1, 2, 3]]").unwrap(),
+      [Die::Sides(6), Die::Faces(vec![0, 0, 1, 1, 2, 3])],
+      "ruleset files can mix both kinds of dice"
+    );
+  }
+}
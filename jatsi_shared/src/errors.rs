@@ -1,13 +1,18 @@
+use serde::{self, Deserialize, Serialize};
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum InvalidAction {
   NotYourTurn,
   WrongState,
   OutOfBounds,
   AlreadyOccupied,
   NotSelectable,
+  OutOfOrder,
+  NotATeam,
+  InvalidHandicap,
+  NameTaken,
 }
 
 impl Display for InvalidAction {
@@ -18,8 +23,245 @@ impl Display for InvalidAction {
       Self::OutOfBounds => write!(f, "out of bounds (this should'nt happen :)"),
       Self::AlreadyOccupied => write!(f, "the selected scoring row is already occupied"),
       Self::NotSelectable => write!(f, "the bonus row is not selectable"),
+      Self::OutOfOrder => write!(f, "the rules require another row, or announcing one first"),
+      Self::NotATeam => write!(f, "a player of that name is playing alone"),
+      Self::InvalidHandicap => write!(f, "the handicap is larger than allowed"),
+      Self::NameTaken => write!(f, "someone of that name has already joined"),
     }
   }
 }
 
 impl Error for InvalidAction {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvalidRuleset {
+  Unreadable(String),
+  NoDice,
+  TooManyDice(usize),
+  NoSides(usize),
+  TooManySides(usize),
+  FaceTooHigh(usize),
+  NoRolls,
+  NoColumns,
+  NoScorings,
+  MultipleBonuses,
+  TooManyWildAssignments,
+}
+
+impl Display for InvalidRuleset {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::Unreadable(reason) => write!(f, "cannot read the ruleset: {}", reason),
+      Self::NoDice => write!(f, "there must be at least one die"),
+      Self::TooManyDice(count) => write!(
+        f,
+        "there are {} dice, the limit is {}",
+        count,
+        crate::dice::MAX_DICE
+      ),
+      Self::NoSides(index) => write!(f, "die {} has no sides", index + 1),
+      Self::TooManySides(index) => write!(
+        f,
+        "die {} has more than {} sides",
+        index + 1,
+        crate::dice::MAX_SIDES
+      ),
+      Self::FaceTooHigh(index) => write!(
+        f,
+        "die {} has a face above {}",
+        index + 1,
+        crate::dice::MAX_FACE_VALUE
+      ),
+      Self::NoRolls => write!(f, "there must be at least one roll per turn"),
+      Self::NoColumns => write!(f, "there must be at least one column"),
+      Self::NoScorings => write!(
+        f,
+        "there must be at least one scoring row besides the bonus"
+      ),
+      Self::MultipleBonuses => write!(f, "there can be only one bonus row"),
+      Self::TooManyWildAssignments => write!(
+        f,
+        "there are more than {} ways to assign the wilds, too many for custom rows",
+        crate::rules::MAX_WILD_ASSIGNMENTS
+      ),
+    }
+  }
+}
+
+impl Error for InvalidRuleset {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvalidChat {
+  Empty,
+  TooLong(usize),
+  NotJoined,
+  TooFast,
+}
+
+impl Display for InvalidChat {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::Empty => write!(f, "the message is empty"),
+      Self::TooLong(length) => write!(
+        f,
+        "the message is {} characters long, the limit is {}",
+        length,
+        crate::chat::MAX_CHAT_LENGTH
+      ),
+      Self::NotJoined => write!(f, "only players can chat"),
+      Self::TooFast => write!(
+        f,
+        "slow down, the limit is {} messages in {} seconds",
+        crate::chat::CHAT_BURST,
+        crate::chat::CHAT_WINDOW_SECS
+      ),
+    }
+  }
+}
+
+impl Error for InvalidChat {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvalidLogin {
+  InvalidUsername,
+  PasswordTooShort(usize),
+  UsernameTaken,
+  WrongPassword,
+  /// The token has expired or the account has logged out.
+  UnknownToken,
+  /// The server could not reach its database.
+  Unavailable,
+  /// Too many attempts to log in or register from the same address or to the same account.
+  TooManyAttempts,
+}
+
+impl Display for InvalidLogin {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::InvalidUsername => write!(
+        f,
+        "usernames are 3 to 20 letters, digits, dashes or underscores"
+      ),
+      Self::PasswordTooShort(min_length) => write!(
+        f,
+        "the password must be at least {} characters long",
+        min_length
+      ),
+      Self::UsernameTaken => write!(f, "the username is already taken"),
+      Self::WrongPassword => write!(f, "wrong username or password"),
+      Self::UnknownToken => write!(f, "the login has expired, please log in again"),
+      Self::Unavailable => write!(f, "accounts are not available right now"),
+      Self::TooManyAttempts => write!(f, "too many attempts, please try again in a minute"),
+    }
+  }
+}
+
+impl Error for InvalidLogin {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvalidTournament {
+  NoSuchTournament(String),
+  TableTooSmall,
+  /// At most half of each table may advance, or the bracket would never get smaller.
+  TooManyAdvancing,
+  NoRounds,
+  EmptyName,
+  NameTaken,
+  AlreadyStarted,
+  NotEnoughPlayers,
+  RoundInProgress,
+  Finished,
+  NoSuchTable,
+  /// The result does not have the players seated at the table.
+  WrongPlayers,
+  AlreadyRecorded,
+  NotOrganizer,
+  /// The player has no unfinished game to forfeit.
+  NotPlaying,
+  /// The player is registered by another account or connection.
+  NotYourSeat,
+}
+
+impl Display for InvalidTournament {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::NoSuchTournament(code) => write!(f, "there is no tournament with the code {}", code),
+      Self::TableTooSmall => write!(f, "there must be at least two players per table"),
+      Self::TooManyAdvancing => write!(
+        f,
+        "at least one and at most half of the players of each table must advance"
+      ),
+      Self::NoRounds => write!(f, "there must be at least one round"),
+      Self::EmptyName => write!(f, "the name is empty"),
+      Self::NameTaken => write!(f, "someone with that name has already registered"),
+      Self::AlreadyStarted => write!(f, "the tournament has already started"),
+      Self::NotEnoughPlayers => write!(f, "a tournament needs at least two players"),
+      Self::RoundInProgress => write!(f, "the current round has not finished yet"),
+      Self::Finished => write!(f, "the tournament has finished"),
+      Self::NoSuchTable => write!(f, "there is no such table"),
+      Self::WrongPlayers => write!(f, "the result is not of the players of the table"),
+      Self::AlreadyRecorded => write!(f, "the result of the table has already been recorded"),
+      Self::NotOrganizer => write!(
+        f,
+        "only the organizer can start the tournament or forfeit for others"
+      ),
+      Self::NotPlaying => write!(f, "the player has no game in progress"),
+      Self::NotYourSeat => write!(
+        f,
+        "that player registered from another account or connection"
+      ),
+    }
+  }
+}
+
+impl Error for InvalidTournament {}
+
+/// Errors in the scoring expressions of ruleset files. Positions are characters counted from one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InvalidExpression {
+  UnexpectedCharacter(usize, char),
+  UnexpectedToken(usize, String),
+  UnexpectedEnd(usize),
+  UnknownVariable(usize, String),
+  UnknownFunction(usize, String),
+  NumberTooLarge(usize),
+  TooDeep(usize),
+  /// The length of the expression in characters.
+  TooLong(usize),
+}
+
+impl Display for InvalidExpression {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::UnexpectedCharacter(position, c) => {
+        write!(f, "unexpected character '{}' at position {}", c, position)
+      }
+      Self::UnexpectedToken(position, token) => {
+        write!(f, "unexpected '{}' at position {}", token, position)
+      }
+      Self::UnexpectedEnd(position) => {
+        write!(f, "the expression ends too soon at position {}", position)
+      }
+      Self::UnknownVariable(position, name) => {
+        write!(f, "unknown variable '{}' at position {}", name, position)
+      }
+      Self::UnknownFunction(position, name) => {
+        write!(f, "unknown function '{}' at position {}", name, position)
+      }
+      Self::NumberTooLarge(position) => {
+        write!(f, "the number at position {} is too large", position)
+      }
+      Self::TooDeep(position) => {
+        write!(f, "the expression nests too deep at position {}", position)
+      }
+      Self::TooLong(length) => write!(
+        f,
+        "the expression is {} characters long, the limit is {}",
+        length,
+        crate::expression::MAX_LENGTH
+      ),
+    }
+  }
+}
+
+impl Error for InvalidExpression {}
@@ -0,0 +1,382 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::errors::InvalidAction;
+use crate::game::{Game, GameMessage, PlayerMessage, State};
+use crate::handicap::Handicap;
+use crate::i18n::Language;
+use crate::rules::and;
+
+/// Something that happened in a game, in a form fit for people to read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+  Joined {
+    name: String,
+  },
+  JoinedTeam {
+    name: String,
+    team: String,
+  },
+  Handicapped {
+    name: String,
+    handicap: Handicap,
+  },
+  GameStarted,
+  RoundBegins(usize),
+  /// Dice are indexed from zero but displayed from one.
+  Held {
+    name: String,
+    dice: Vec<usize>,
+  },
+  Rolled {
+    name: String,
+    roll: Vec<u64>,
+  },
+  Announced {
+    name: String,
+    row: String,
+  },
+  /// Points are weighted by the multiplier of the column. The column is only given if the score sheet has several.
+  /// The values the wild dice of the roll counted as are listed in the order of the dice. The reason is that of the
+  /// points of the row before the multiplier.
+  Scored {
+    name: String,
+    points: u64,
+    row: String,
+    column: Option<usize>,
+    reason: String,
+    wilds: Vec<u64>,
+    bonus: Option<u64>,
+  },
+  GameFinished {
+    winners: Vec<String>,
+    points: u64,
+  },
+}
+
+/// Joins items like "1, 2 and 3" in `language`.
+pub(crate) fn and_list<T: Display>(items: &[T], language: Language) -> String {
+  match items {
+    [] => String::new(),
+    [item] => item.to_string(),
+    [rest @ .., last] => format!(
+      "{} {} {}",
+      rest
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<String>>()
+        .join(", "),
+      and(language),
+      last
+    ),
+  }
+}
+
+impl Display for Event {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self {
+      Self::Joined { name } => write!(f, "{} joined the game", name),
+      Self::JoinedTeam { name, team } => write!(f, "{} joined the team {}", name, team),
+      Self::Handicapped { name, handicap } => write!(f, "{} plays with {}", name, handicap),
+      Self::GameStarted => write!(f, "The game begins"),
+      Self::RoundBegins(round) => write!(f, "Round {} begins", round),
+      Self::Held { name, dice } => {
+        let dice: Vec<usize> = dice.iter().map(|die| die + 1).collect();
+        let noun = if dice.len() == 1 { "die" } else { "dice" };
+        write!(
+          f,
+          "{} held {} {}",
+          name,
+          noun,
+          and_list(&dice, Language::English)
+        )
+      }
+      Self::Rolled { name, roll } => {
+        let roll: Vec<String> = roll.iter().map(|value| value.to_string()).collect();
+        write!(f, "{} rolled {}", name, roll.join(" "))
+      }
+      Self::Announced { name, row } => write!(f, "{} announced {}", name, row),
+      Self::Scored {
+        name,
+        points,
+        row,
+        column,
+        reason,
+        wilds,
+        bonus,
+      } => {
+        write!(f, "{} scored {} in {}", name, points, row)?;
+        if let Some(column) = column {
+          write!(f, " of column {}", column + 1)?;
+        }
+        write!(f, " ({})", reason)?;
+        match wilds.len() {
+          0 => {}
+          1 => write!(f, " with the wild as {}", wilds[0])?,
+          _ => write!(
+            f,
+            " with the wilds as {}",
+            and_list(wilds, Language::English)
+          )?,
+        }
+        match bonus {
+          Some(bonus) => write!(f, " and got the bonus of {}", bonus),
+          None => Ok(()),
+        }
+      }
+      Self::GameFinished { winners, points } => match winners.len() {
+        1 => write!(f, "Game over! {} wins with {} points", winners[0], points),
+        _ => write!(
+          f,
+          "Game over! {} share the win with {} points",
+          and_list(winners, Language::English),
+          points
+        ),
+      },
+    }
+  }
+}
+
+/// Describes the message history of `game` as events, naming the rows and explaining the scores in `language`.
+pub fn events(game: &Game, language: Language) -> Result<Vec<Event>, InvalidAction> {
+  let mut replay = Game::new(game.ruleset.clone());
+  let mut events = Vec::new();
+
+  for msg in game.message_history.iter() {
+    // Teams are named along with the member taking the turn
+    let name_in_turn = replay
+      .players
+      .get(replay.player_in_turn)
+      .map(|player| match player.member_in_turn(replay.round) {
+        Some(member) => format!("{} ({})", player.name, member),
+        None => player.name.clone(),
+      })
+      .unwrap_or_default();
+    let score_sheet_before = replay
+      .players
+      .get(replay.player_in_turn)
+      .map(|player| player.score_sheet.clone())
+      .unwrap_or_default();
+    let roll_before = replay.roll.clone();
+
+    if let GameMessage::PlayerMessage(_, PlayerMessage::Roll) = msg {
+      let held: Vec<usize> = (0..replay.keep.len()).filter(|&i| replay.keep[i]).collect();
+      if replay.state == State::Reroll && !held.is_empty() {
+        events.push(Event::Held {
+          name: name_in_turn.clone(),
+          dice: held,
+        });
+      }
+    }
+
+    let round_before = replay.round;
+    replay.commit(msg)?;
+
+    match msg {
+      GameMessage::PlayerMessage(_, PlayerMessage::JoinGame(name)) => {
+        events.push(Event::Joined { name: name.clone() })
+      }
+      GameMessage::PlayerMessage(_, PlayerMessage::JoinTeam(team, name)) => {
+        events.push(Event::JoinedTeam {
+          name: name.clone(),
+          team: team.clone(),
+        })
+      }
+      GameMessage::PlayerMessage(_, PlayerMessage::SetHandicap(player, handicap)) => {
+        events.push(Event::Handicapped {
+          name: replay.players[*player].name.clone(),
+          handicap: handicap.clone(),
+        })
+      }
+      GameMessage::PlayerMessage(_, PlayerMessage::StartGame) => events.push(Event::GameStarted),
+      GameMessage::PlayerMessage(_, PlayerMessage::Announce(row)) => {
+        events.push(Event::Announced {
+          name: name_in_turn,
+          row: replay.ruleset.row_name(*row, language),
+        })
+      }
+      GameMessage::PlayerMessage(_, player_msg) if player_msg.placement().is_some() => {
+        let (column, row) = player_msg.placement().expect("the message is a placement");
+        let multiplier = replay.ruleset.columns[column];
+        let score_sheet = &replay.players[replay.player_in_turn].score_sheet[column];
+        let before = &score_sheet_before[column];
+        let gained = |index: usize| {
+          multiplier.saturating_mul(score_sheet[index].unwrap_or(0) - before[index].unwrap_or(0))
+        };
+        let bonus = (0..score_sheet.len())
+          .find(|&index| index != row && score_sheet[index] != before[index])
+          .map(gained)
+          .filter(|&bonus| bonus > 0);
+
+        events.push(Event::Scored {
+          name: name_in_turn,
+          points: gained(row),
+          row: replay.ruleset.row_name(row, language),
+          column: Some(column).filter(|_| replay.ruleset.columns.len() > 1),
+          reason: replay
+            .ruleset
+            .explain(row, &roll_before, language)
+            .map(|explanation| explanation.reason)
+            .unwrap_or_default(),
+          wilds: replay.ruleset.assigned_wilds(row, &roll_before),
+          bonus,
+        });
+      }
+      GameMessage::PlayerTurn(..) if replay.round != round_before => {
+        events.push(Event::RoundBegins(replay.round))
+      }
+      GameMessage::RollResult(roll) => events.push(Event::Rolled {
+        name: name_in_turn,
+        roll: roll.clone(),
+      }),
+      GameMessage::GameFinished => {
+        let scoreboard = replay.scoreboard();
+        let points = scoreboard.first().map(|&(points, _)| points).unwrap_or(0);
+        // In joining order rather than the order of the scoreboard
+        let winners: Vec<String> = replay
+          .players
+          .iter()
+          .filter(|player| player.total(&replay.ruleset) == points)
+          .map(|player| player.name.clone())
+          .collect();
+        events.push(Event::GameFinished { winners, points });
+      }
+      _ => {}
+    }
+  }
+
+  Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::i18n::Localize;
+  use crate::rules::mini_rules;
+
+  #[test]
+  fn test_events() {
+    let (sixes, full_house) = (0, 2);
+    let messages = [
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+      GameMessage::PlayerMessage(1, PlayerMessage::JoinGame("Bobby".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+      GameMessage::PlayerTurn(0),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![5, 3, 4, 5, 2]),
+      GameMessage::PlayerMessage(0, PlayerMessage::ToggleHold(0)),
+      GameMessage::PlayerMessage(0, PlayerMessage::ToggleHold(3)),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![5, 5, 1, 5, 1]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Place(full_house)),
+      GameMessage::PlayerTurn(1),
+      GameMessage::PlayerMessage(1, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![6, 6, 6, 6, 3]),
+      GameMessage::PlayerMessage(1, PlayerMessage::Place(sixes)),
+      GameMessage::PlayerTurn(0),
+    ];
+    let game = Game::replay(mini_rules(), &messages).unwrap();
+
+    let descriptions: Vec<String> = events(&game, Language::English)
+      .unwrap()
+      .iter()
+      .map(|event| event.to_string())
+      .collect();
+    assert_eq!(
+      descriptions,
+      [
+        "Henry joined the game",
+        "Bobby joined the game",
+        "The game begins",
+        "Round 1 begins",
+        "Henry rolled 5 3 4 5 2",
+        "Henry held dice 1 and 4",
+        "Henry rolled 5 5 1 5 1",
+        "Henry scored 25 in Full House (three 5s and two 1s)",
+        "Bobby rolled 6 6 6 6 3",
+        "Bobby scored 24 in Sixes (four 6s = 24) and got the bonus of 50",
+        "Round 2 begins",
+      ]
+    );
+
+    let descriptions: Vec<String> = events(&game, Language::Finnish)
+      .unwrap()
+      .iter()
+      .map(|event| event.localize(Language::Finnish))
+      .collect();
+    assert_eq!(
+      descriptions[5..8],
+      [
+        "Henry piti nopat 1 ja 4",
+        "Henry heitti 5 5 1 5 1",
+        "Henry sai 25 pistettä riviltä Täyskäsi (kolme vitosta ja kaksi ykköstä)",
+      ]
+    );
+    assert_eq!(
+      descriptions[9],
+      "Bobby sai 24 pistettä riviltä Kuutoset (neljä kutosta = 24) ja 50 pisteen bonuksen"
+    );
+  }
+
+  #[test]
+  fn test_columns() {
+    let ruleset = crate::rules::Ruleset {
+      columns: vec![1, 3],
+      ..mini_rules()
+    };
+    let messages = [
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+      GameMessage::PlayerTurn(0),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![6, 6, 6, 6, 3]),
+      GameMessage::PlayerMessage(0, PlayerMessage::PlaceInColumn(1, 0)),
+    ];
+    let game = Game::replay(ruleset, &messages).unwrap();
+    assert_eq!(
+      events(&game, Language::English)
+        .unwrap()
+        .last()
+        .unwrap()
+        .to_string(),
+      "Henry scored 72 in Sixes of column 2 (four 6s = 24) and got the bonus of 150"
+    );
+  }
+
+  #[test]
+  fn test_wilds() {
+    let ruleset = crate::rules::Ruleset {
+      wild: Some(1),
+      ..mini_rules()
+    };
+    let full_house = 2;
+    let messages = [
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+      GameMessage::PlayerTurn(0),
+      GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+      GameMessage::RollResult(vec![1, 3, 3, 5, 1]),
+      GameMessage::PlayerMessage(0, PlayerMessage::Place(full_house)),
+    ];
+    let game = Game::replay(ruleset, &messages).unwrap();
+    assert_eq!(
+      events(&game, Language::English)
+        .unwrap()
+        .last()
+        .unwrap()
+        .to_string(),
+      "Henry scored 25 in Full House (three 3s and two 5s) with the wilds as 3 and 5"
+    );
+  }
+
+  #[test]
+  fn test_game_finished() {
+    let event = Event::GameFinished {
+      winners: vec!["Henry".into(), "Bobby".into(), "Japsu".into()],
+      points: 99,
+    };
+    assert_eq!(
+      event.to_string(),
+      "Game over! Henry, Bobby and Japsu share the win with 99 points"
+    );
+  }
+}
@@ -0,0 +1,571 @@
+//! A small expression language for scoring rows defined in ruleset files, like `if count_max >= 3 then sum else 0`.
+//!
+//! Expressions compute whole numbers from a roll. They have
+//! - numbers, `+`, `-`, `*` and `/`, where dividing by zero gives zero,
+//! - comparisons `==`, `!=`, `<`, `<=`, `>` and `>=`, and `and`, `or` and `not`, with zero as false and one as true,
+//! - `if … then … else …`,
+//! - the variables `sum`, `dice`, `max`, `min`, `count_max` (the most dice showing the same value), `pairs` (values
+//!   shown by at least two dice), `distinct` (different values) and `run` (the longest run of consecutive values),
+//! - the function `count(value)`, the number of dice showing a value.
+//!
+//! There are no loops or calls of other rows, and the length and nesting of expressions is limited, so evaluating an
+//! expression always ends. Negative results score zero.
+
+use serde::{self, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::dice::longest_run;
+use crate::errors::InvalidExpression;
+
+/// How deep expressions may nest and how long they may be, so that parsing and evaluating them cannot run out of stack.
+const MAX_DEPTH: usize = 32;
+pub const MAX_LENGTH: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Variable {
+  Sum,
+  Dice,
+  Max,
+  Min,
+  CountMax,
+  Pairs,
+  Distinct,
+  Run,
+}
+
+impl Variable {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "sum" => Some(Self::Sum),
+      "dice" => Some(Self::Dice),
+      "max" => Some(Self::Max),
+      "min" => Some(Self::Min),
+      "count_max" => Some(Self::CountMax),
+      "pairs" => Some(Self::Pairs),
+      "distinct" => Some(Self::Distinct),
+      "run" => Some(Self::Run),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  Equal,
+  NotEqual,
+  Less,
+  LessOrEqual,
+  Greater,
+  GreaterOrEqual,
+  And,
+  Or,
+}
+
+impl Operator {
+  fn apply(self, a: i64, b: i64) -> i64 {
+    match self {
+      Self::Add => a.saturating_add(b),
+      Self::Subtract => a.saturating_sub(b),
+      Self::Multiply => a.saturating_mul(b),
+      Self::Divide => a.checked_div(b).unwrap_or(0),
+      Self::Equal => (a == b) as i64,
+      Self::NotEqual => (a != b) as i64,
+      Self::Less => (a < b) as i64,
+      Self::LessOrEqual => (a <= b) as i64,
+      Self::Greater => (a > b) as i64,
+      Self::GreaterOrEqual => (a >= b) as i64,
+      Self::And => (a != 0 && b != 0) as i64,
+      Self::Or => (a != 0 || b != 0) as i64,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+  Number(i64),
+  Variable(Variable),
+  Count(Box<Node>),
+  Negate(Box<Node>),
+  Not(Box<Node>),
+  Binary(Operator, Box<Node>, Box<Node>),
+  If(Box<Node>, Box<Node>, Box<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+  Number(i64),
+  Word(String),
+  Symbol(&'static str),
+}
+
+impl Display for Token {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Self::Number(number) => write!(f, "{}", number),
+      Self::Word(word) => write!(f, "{}", word),
+      Self::Symbol(symbol) => write!(f, "{}", symbol),
+    }
+  }
+}
+
+const SYMBOLS: [&str; 12] = [
+  "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "(", ")",
+];
+
+/// Splits the source into tokens, each with its position.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, InvalidExpression> {
+  let chars: Vec<char> = source.chars().collect();
+  let mut tokens = Vec::new();
+  let mut i = 0;
+
+  while i < chars.len() {
+    let position = i + 1;
+    let c = chars[i];
+    if c.is_whitespace() {
+      i += 1;
+    } else if c.is_ascii_digit() {
+      let digits: String = chars[i..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+      i += digits.len();
+      let number = digits
+        .parse()
+        .map_err(|_| InvalidExpression::NumberTooLarge(position))?;
+      tokens.push((position, Token::Number(number)));
+    } else if c.is_alphabetic() || c == '_' {
+      let word: String = chars[i..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_')
+        .collect();
+      i += word.chars().count();
+      tokens.push((position, Token::Word(word)));
+    } else {
+      let rest: String = chars[i..].iter().take(2).collect();
+      let symbol = SYMBOLS
+        .iter()
+        .find(|symbol| rest.starts_with(*symbol))
+        .ok_or(InvalidExpression::UnexpectedCharacter(position, c))?;
+      i += symbol.len();
+      tokens.push((position, Token::Symbol(symbol)));
+    }
+  }
+
+  Ok(tokens)
+}
+
+/// A recursive descent parser, from the loosest binding rules to the tightest.
+struct Parser {
+  tokens: Vec<(usize, Token)>,
+  next: usize,
+  depth: usize,
+  /// The position just past the source, where a missing token would be.
+  end: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.next).map(|(_, token)| token)
+  }
+
+  fn position(&self) -> usize {
+    self
+      .tokens
+      .get(self.next)
+      .map_or(self.end, |&(position, _)| position)
+  }
+
+  fn advance(&mut self) -> Result<Token, InvalidExpression> {
+    let (_, token) = self
+      .tokens
+      .get(self.next)
+      .cloned()
+      .ok_or(InvalidExpression::UnexpectedEnd(self.end))?;
+    self.next += 1;
+    Ok(token)
+  }
+
+  fn unexpected(&self) -> InvalidExpression {
+    match self.tokens.get(self.next) {
+      Some((position, token)) => InvalidExpression::UnexpectedToken(*position, token.to_string()),
+      None => InvalidExpression::UnexpectedEnd(self.end),
+    }
+  }
+
+  fn is_word(&self, word: &str) -> bool {
+    matches!(self.peek(), Some(Token::Word(next)) if next == word)
+  }
+
+  fn expect(&mut self, expected: Token) -> Result<(), InvalidExpression> {
+    match self.peek() {
+      Some(token) if *token == expected => {
+        self.next += 1;
+        Ok(())
+      }
+      _ => Err(self.unexpected()),
+    }
+  }
+
+  /// Parses a part that may nest further.
+  fn nested(
+    &mut self,
+    parse: fn(&mut Self) -> Result<Node, InvalidExpression>,
+  ) -> Result<Node, InvalidExpression> {
+    self.depth += 1;
+    if self.depth > MAX_DEPTH {
+      return Err(InvalidExpression::TooDeep(self.position()));
+    }
+    let node = parse(self);
+    self.depth -= 1;
+    node
+  }
+
+  fn expression(&mut self) -> Result<Node, InvalidExpression> {
+    self.nested(Self::conditional)
+  }
+
+  fn conditional(&mut self) -> Result<Node, InvalidExpression> {
+    if self.is_word("if") {
+      self.next += 1;
+      let condition = self.expression()?;
+      self.expect(Token::Word("then".into()))?;
+      let then = self.expression()?;
+      self.expect(Token::Word("else".into()))?;
+      let otherwise = self.expression()?;
+      Ok(Node::If(
+        Box::new(condition),
+        Box::new(then),
+        Box::new(otherwise),
+      ))
+    } else {
+      self.or()
+    }
+  }
+
+  /// Parses operands joined by any of `operators`, left to right.
+  fn binary(
+    &mut self,
+    operators: &[(Token, Operator)],
+    operand: fn(&mut Self) -> Result<Node, InvalidExpression>,
+  ) -> Result<Node, InvalidExpression> {
+    let mut node = operand(self)?;
+    while let Some(&(_, operator)) = operators
+      .iter()
+      .find(|(token, _)| self.peek() == Some(token))
+    {
+      self.next += 1;
+      node = Node::Binary(operator, Box::new(node), Box::new(operand(self)?));
+    }
+    Ok(node)
+  }
+
+  fn or(&mut self) -> Result<Node, InvalidExpression> {
+    self.binary(&[(Token::Word("or".into()), Operator::Or)], Self::and)
+  }
+
+  fn and(&mut self) -> Result<Node, InvalidExpression> {
+    self.binary(&[(Token::Word("and".into()), Operator::And)], Self::not)
+  }
+
+  fn not(&mut self) -> Result<Node, InvalidExpression> {
+    if self.is_word("not") {
+      self.next += 1;
+      Ok(Node::Not(Box::new(self.nested(Self::not)?)))
+    } else {
+      self.comparison()
+    }
+  }
+
+  fn comparison(&mut self) -> Result<Node, InvalidExpression> {
+    self.binary(
+      &[
+        (Token::Symbol("=="), Operator::Equal),
+        (Token::Symbol("!="), Operator::NotEqual),
+        (Token::Symbol("<="), Operator::LessOrEqual),
+        (Token::Symbol(">="), Operator::GreaterOrEqual),
+        (Token::Symbol("<"), Operator::Less),
+        (Token::Symbol(">"), Operator::Greater),
+      ],
+      Self::sum,
+    )
+  }
+
+  fn sum(&mut self) -> Result<Node, InvalidExpression> {
+    self.binary(
+      &[
+        (Token::Symbol("+"), Operator::Add),
+        (Token::Symbol("-"), Operator::Subtract),
+      ],
+      Self::product,
+    )
+  }
+
+  fn product(&mut self) -> Result<Node, InvalidExpression> {
+    self.binary(
+      &[
+        (Token::Symbol("*"), Operator::Multiply),
+        (Token::Symbol("/"), Operator::Divide),
+      ],
+      Self::unary,
+    )
+  }
+
+  fn unary(&mut self) -> Result<Node, InvalidExpression> {
+    if self.peek() == Some(&Token::Symbol("-")) {
+      self.next += 1;
+      Ok(Node::Negate(Box::new(self.nested(Self::unary)?)))
+    } else {
+      self.atom()
+    }
+  }
+
+  fn atom(&mut self) -> Result<Node, InvalidExpression> {
+    let position = self.position();
+    match self.advance()? {
+      Token::Number(number) => Ok(Node::Number(number)),
+      Token::Symbol("(") => {
+        let node = self.expression()?;
+        self.expect(Token::Symbol(")"))?;
+        Ok(node)
+      }
+      Token::Word(name) if self.peek() == Some(&Token::Symbol("(")) => {
+        if name != "count" {
+          return Err(InvalidExpression::UnknownFunction(position, name));
+        }
+        self.next += 1;
+        let argument = self.expression()?;
+        self.expect(Token::Symbol(")"))?;
+        Ok(Node::Count(Box::new(argument)))
+      }
+      Token::Word(name) => match Variable::from_name(&name) {
+        Some(variable) => Ok(Node::Variable(variable)),
+        None => Err(InvalidExpression::UnknownVariable(position, name)),
+      },
+      token => Err(InvalidExpression::UnexpectedToken(
+        position,
+        token.to_string(),
+      )),
+    }
+  }
+}
+
+/// The figures of a roll that expressions can refer to.
+struct Figures {
+  counts: BTreeMap<u64, i64>,
+  variables: [(Variable, i64); 8],
+}
+
+impl Figures {
+  fn new(roll: &[u64]) -> Self {
+    let mut counts = BTreeMap::new();
+    for &value in roll {
+      *counts.entry(value).or_insert(0) += 1;
+    }
+    let number = |value: u64| value.min(i64::MAX as u64) as i64;
+    let variables = [
+      (Variable::Sum, number(roll.iter().sum())),
+      (Variable::Dice, roll.len() as i64),
+      (
+        Variable::Max,
+        number(roll.iter().copied().max().unwrap_or(0)),
+      ),
+      (
+        Variable::Min,
+        number(roll.iter().copied().min().unwrap_or(0)),
+      ),
+      (
+        Variable::CountMax,
+        counts.values().copied().max().unwrap_or(0),
+      ),
+      (
+        Variable::Pairs,
+        counts.values().filter(|&&count| count >= 2).count() as i64,
+      ),
+      (Variable::Distinct, counts.len() as i64),
+      (Variable::Run, number(longest_run(roll))),
+    ];
+    Self { counts, variables }
+  }
+
+  fn evaluate(&self, node: &Node) -> i64 {
+    match node {
+      Node::Number(number) => *number,
+      Node::Variable(variable) => self
+        .variables
+        .iter()
+        .find(|(known, _)| known == variable)
+        .map_or(0, |&(_, value)| value),
+      Node::Count(value) => match u64::try_from(self.evaluate(value)) {
+        Ok(value) => self.counts.get(&value).copied().unwrap_or(0),
+        Err(_) => 0,
+      },
+      Node::Negate(node) => self.evaluate(node).saturating_neg(),
+      Node::Not(node) => (self.evaluate(node) == 0) as i64,
+      Node::Binary(operator, a, b) => operator.apply(self.evaluate(a), self.evaluate(b)),
+      Node::If(condition, then, otherwise) => match self.evaluate(condition) {
+        0 => self.evaluate(otherwise),
+        _ => self.evaluate(then),
+      },
+    }
+  }
+}
+
+/// A parsed scoring expression. Ruleset files have the source, from which it is parsed again when read.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Expression {
+  source: String,
+  node: Node,
+}
+
+impl Expression {
+  pub fn parse(source: &str) -> Result<Self, InvalidExpression> {
+    let length = source.chars().count();
+    if length > MAX_LENGTH {
+      return Err(InvalidExpression::TooLong(length));
+    }
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+      tokens,
+      next: 0,
+      depth: 0,
+      end: source.chars().count() + 1,
+    };
+    let node = parser.expression()?;
+    if parser.peek().is_some() {
+      return Err(parser.unexpected());
+    }
+
+    Ok(Self {
+      source: source.to_string(),
+      node,
+    })
+  }
+
+  /// The points of `roll`, zero if the expression comes out negative.
+  pub fn evaluate(&self, roll: &[u64]) -> u64 {
+    Figures::new(roll).evaluate(&self.node).max(0) as u64
+  }
+}
+
+impl FromStr for Expression {
+  type Err = InvalidExpression;
+
+  fn from_str(source: &str) -> Result<Self, Self::Err> {
+    Self::parse(source)
+  }
+}
+
+impl TryFrom<String> for Expression {
+  type Error = InvalidExpression;
+
+  fn try_from(source: String) -> Result<Self, Self::Error> {
+    Self::parse(&source)
+  }
+}
+
+impl From<Expression> for String {
+  fn from(expression: Expression) -> Self {
+    expression.source
+  }
+}
+
+impl Display for Expression {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", self.source)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn score(source: &str, roll: &[u64]) -> u64 {
+    Expression::parse(source).unwrap().evaluate(roll)
+  }
+
+  #[test]
+  fn test_evaluate() {
+    let set_of_three = "if count_max >= 3 then sum else 0";
+    assert_eq!(score(set_of_three, &[4, 4, 4, 1, 2]), 15);
+    assert_eq!(score(set_of_three, &[4, 4, 3, 1, 2]), 0);
+
+    assert_eq!(score("count(6) * 6", &[6, 6, 1, 2, 6]), 18);
+    assert_eq!(score("1 + 2 * 3 - 4 / 2", &[]), 5, "usual precedence");
+    assert_eq!(score("(1 + 2) * 3", &[]), 9);
+    assert_eq!(
+      score(
+        "if pairs == 2 and not distinct == 2 then 20 else 0",
+        &[1, 1, 2, 2, 3]
+      ),
+      20
+    );
+    assert_eq!(
+      score("if run >= 4 or dice < 5 then 30 else 0", &[3, 1, 4, 2, 6]),
+      30
+    );
+    assert_eq!(score("max - min", &[3, 1, 4, 2, 6]), 5);
+    assert_eq!(score("sum / 0", &[3]), 0, "dividing by zero gives zero");
+    assert_eq!(score("-sum", &[3]), 0, "negative points are zero");
+    assert_eq!(score("count(-1)", &[3]), 0);
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert_eq!(
+      Expression::parse("if sum > 3 than 1 else 0"),
+      Err(InvalidExpression::UnexpectedToken(12, "than".into()))
+    );
+    assert_eq!(
+      Expression::parse("sum +"),
+      Err(InvalidExpression::UnexpectedEnd(6))
+    );
+    assert_eq!(
+      Expression::parse("sum $ 2"),
+      Err(InvalidExpression::UnexpectedCharacter(5, '$'))
+    );
+    assert_eq!(
+      Expression::parse("2 * sixes"),
+      Err(InvalidExpression::UnknownVariable(5, "sixes".into()))
+    );
+    assert_eq!(
+      Expression::parse("total(6)"),
+      Err(InvalidExpression::UnknownFunction(1, "total".into()))
+    );
+    assert_eq!(
+      Expression::parse("sum 2"),
+      Err(InvalidExpression::UnexpectedToken(5, "2".into()))
+    );
+    assert!(matches!(
+      Expression::parse(&"(".repeat(100)),
+      Err(InvalidExpression::TooDeep(_))
+    ));
+    assert!(matches!(
+      Expression::parse(&"-".repeat(100)),
+      Err(InvalidExpression::TooDeep(_))
+    ));
+    assert_eq!(
+      Expression::parse(&"1+".repeat(1000)),
+      Err(InvalidExpression::TooLong(2000))
+    );
+  }
+
+  #[test]
+  fn test_serde() {
+    let expression: Expression = serde_json::from_str("\"count(1) * 100\"").unwrap();
+    assert_eq!(expression.evaluate(&[1, 1]), 200);
+    assert_eq!(
+      serde_json::to_string(&expression).unwrap(),
+      "\"count(1) * 100\""
+    );
+
+    let err = serde_json::from_str::<Expression>("\"sum +\"").unwrap_err();
+    assert!(err.to_string().contains("position 6"), "{}", err);
+  }
+}
@@ -1,13 +1,42 @@
 use serde::{self, Deserialize, Serialize};
 
+use crate::chat::ChatMessage;
 use crate::dice::{roll_dice, roll_dice_keeping};
 use crate::errors::InvalidAction;
-use crate::rules::{ee_rules, update_score_sheet, Ruleset};
+use crate::handicap::Handicap;
+use crate::probability::{reroll_odds, RowOdds};
+use crate::rules::{ee_rules, PlacementOrder, Ruleset, Scoring};
 
+/// A player or a team of players sharing a score sheet.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Player {
   pub name: String,
-  pub score_sheet: Vec<Option<u64>>,
+  /// One column of rows for each column of the ruleset.
+  pub score_sheet: Vec<Vec<Option<u64>>>,
+  /// The members of a team in joining order. Empty for players playing alone.
+  pub members: Vec<String>,
+  pub handicap: Handicap,
+}
+
+impl Player {
+  /// The points of the player, including the starting points of their handicap.
+  pub fn total(&self, ruleset: &Ruleset) -> u64 {
+    ruleset
+      .total(&self.score_sheet)
+      .saturating_add(self.handicap.starting_points)
+  }
+
+  pub fn is_team(&self) -> bool {
+    !self.members.is_empty()
+  }
+
+  /// The member of a team taking its turn in `round`. Members take turns alternately, one round each.
+  pub fn member_in_turn(&self, round: usize) -> Option<&str> {
+    match self.members.len() {
+      0 => None,
+      len => Some(self.members[round.saturating_sub(1) % len].as_str()),
+    }
+  }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -22,10 +51,30 @@ pub enum State {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PlayerMessage {
   JoinGame(String),
+  /// Joins a team, forming it if there is no player of that name yet: `JoinTeam(team, name)`.
+  JoinTeam(String, String),
   StartGame,
   ToggleHold(usize),
   Roll,
+  /// Places the roll on a row of the first column. Games saved before score sheets had columns use this.
   Place(usize),
+  /// Places the roll on a row of a column: `PlaceInColumn(column, row)`.
+  PlaceInColumn(usize, usize),
+  /// Announces the row the roll will be placed on, in rulesets with `PlacementOrder::Announced`.
+  Announce(usize),
+  /// Sets the handicap of a player before the game starts: `SetHandicap(player, handicap)`. Only the host can.
+  SetHandicap(usize, Handicap),
+}
+
+impl PlayerMessage {
+  /// The column and row a placement is made on.
+  pub fn placement(&self) -> Option<(usize, usize)> {
+    match *self {
+      PlayerMessage::Place(row) => Some((0, row)),
+      PlayerMessage::PlaceInColumn(column, row) => Some((column, row)),
+      _ => None,
+    }
+  }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -36,6 +85,32 @@ pub enum GameMessage {
   GameFinished,
 }
 
+/// A game as stored for resuming or replaying it later: the rules and everything that happened.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedGame {
+  pub ruleset: Ruleset,
+  pub message_history: Vec<GameMessage>,
+  /// Chat from the room the game was played in. Not part of the message history, so it plays no part in replaying.
+  #[serde(default)]
+  pub chat: Vec<ChatMessage>,
+}
+
+impl SavedGame {
+  /// Reconstructs the game by replaying its message history.
+  pub fn load(&self) -> Result<Game, InvalidAction> {
+    Game::replay(self.ruleset.clone(), &self.message_history)
+  }
+}
+
+/// The dice before they are rolled, each showing its first face.
+fn unrolled(ruleset: &Ruleset) -> Vec<u64> {
+  ruleset
+    .dice
+    .iter()
+    .map(|die| die.faces().first().copied().unwrap_or(1))
+    .collect()
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Game {
   pub message_history: Vec<GameMessage>,
@@ -47,11 +122,14 @@ pub struct Game {
   pub times_rolled: u64,
   pub roll: Vec<u64>,
   pub keep: Vec<bool>,
+  /// The row announced by the player in turn.
+  pub announced: Option<usize>,
 }
 
 impl Game {
   pub fn new(ruleset: Ruleset) -> Self {
     let num_dice = ruleset.dice.len();
+    let roll = unrolled(&ruleset);
     Self {
       message_history: Vec::new(),
       players: Vec::new(),
@@ -60,8 +138,26 @@ impl Game {
       round: 0,
       player_in_turn: 0,
       times_rolled: 0,
-      roll: vec![1; num_dice],
+      roll,
       keep: vec![false; num_dice],
+      announced: None,
+    }
+  }
+
+  /// Creates a game and commits `messages` to it in order.
+  pub fn replay(ruleset: Ruleset, messages: &[GameMessage]) -> Result<Self, InvalidAction> {
+    let mut game = Self::new(ruleset);
+    for message in messages {
+      game.commit(message)?;
+    }
+    Ok(game)
+  }
+
+  pub fn save(&self) -> SavedGame {
+    SavedGame {
+      ruleset: self.ruleset.clone(),
+      message_history: self.message_history.clone(),
+      chat: Vec::new(),
     }
   }
 
@@ -69,12 +165,15 @@ impl Game {
     let rules = ee_rules();
     let mut game = Self::new(rules);
 
-    let messages = game
-      .prepare(0, &PlayerMessage::JoinGame("Japsu".into()))
-      .unwrap();
+    for msg in [
+      PlayerMessage::JoinGame("Japsu".into()),
+      PlayerMessage::StartGame,
+    ] {
+      let messages = game.prepare(0, &msg).unwrap();
 
-    for message in messages {
-      game.commit(&message).unwrap();
+      for message in messages {
+        game.commit(&message).unwrap();
+      }
     }
 
     game
@@ -93,7 +192,11 @@ impl Game {
     msg: &PlayerMessage,
   ) -> Result<Vec<GameMessage>, InvalidAction> {
     if from_player != self.player_in_turn {
-      if matches!(self.state, State::Start) && matches!(msg, PlayerMessage::JoinGame(..)) {
+      let joining = matches!(
+        msg,
+        PlayerMessage::JoinGame(..) | PlayerMessage::JoinTeam(..)
+      );
+      if matches!(self.state, State::Start) && joining {
         // Joining the game in the Start state does not care about turn order
       } else {
         // All other messages do
@@ -105,7 +208,29 @@ impl Game {
     messages.push(GameMessage::PlayerMessage(from_player, msg.clone()));
 
     match msg {
-      PlayerMessage::JoinGame(..) => match &self.state {
+      PlayerMessage::JoinGame(name) => match &self.state {
+        State::Start if self.is_name_taken(name) => return Err(InvalidAction::NameTaken),
+        State::Start => {}
+        _ => return Err(InvalidAction::WrongState),
+      },
+      PlayerMessage::JoinTeam(team, name) => match &self.state {
+        State::Start => {
+          let existing = self.player_index(team).map(|index| &self.players[index]);
+          if existing.is_some_and(|player| !player.is_team()) {
+            return Err(InvalidAction::NotATeam);
+          }
+          // A new team takes its name too
+          if self.is_name_taken(name)
+            || (existing.is_none() && (self.is_name_taken(team) || team == name))
+          {
+            return Err(InvalidAction::NameTaken);
+          }
+        }
+        _ => return Err(InvalidAction::WrongState),
+      },
+      PlayerMessage::SetHandicap(player, handicap) => match &self.state {
+        State::Start if *player >= self.players.len() => return Err(InvalidAction::OutOfBounds),
+        State::Start if !handicap.is_valid() => return Err(InvalidAction::InvalidHandicap),
         State::Start => {}
         _ => return Err(InvalidAction::WrongState),
       },
@@ -128,16 +253,21 @@ impl Game {
           messages.push(GameMessage::RollResult(roll));
         }
         State::Reroll => {
+          if self.ruleset.order == PlacementOrder::Announced && self.announced.is_none() {
+            return Err(InvalidAction::OutOfOrder);
+          }
           let roll = self.roll_dice_keeping();
           messages.push(GameMessage::RollResult(roll));
         }
         _ => return Err(InvalidAction::WrongState),
       },
-      PlayerMessage::Place(num) => match &self.state {
+      PlayerMessage::Place(..) | PlayerMessage::PlaceInColumn(..) => match &self.state {
         State::Reroll | State::Place => {
-          if *num >= self.ruleset.scorings.len() {
+          let (column, row) = msg.placement().expect("the message is a placement");
+          if column >= self.ruleset.columns.len() || row >= self.ruleset.scorings.len() {
             return Err(InvalidAction::OutOfBounds);
           }
+          self.preview_place(column, row)?;
 
           let next_player = self.player_in_turn + 1;
           let next_round = self.round + 1;
@@ -151,6 +281,32 @@ impl Game {
         }
         _ => return Err(InvalidAction::WrongState),
       },
+      PlayerMessage::Announce(row) => {
+        // Only once per turn, between the first roll and the reroll
+        let first_roll = self.state == State::Reroll && self.times_rolled == 1;
+        if self.ruleset.order != PlacementOrder::Announced
+          || !first_roll
+          || self.announced.is_some()
+        {
+          return Err(InvalidAction::WrongState);
+        }
+        match self.ruleset.scorings.get(*row) {
+          None => return Err(InvalidAction::OutOfBounds),
+          Some(Scoring::Bonus { .. }) => return Err(InvalidAction::NotSelectable),
+          Some(_) => {}
+        }
+        let player = self
+          .players
+          .get(self.player_in_turn)
+          .ok_or(InvalidAction::OutOfBounds)?;
+        if player
+          .score_sheet
+          .iter()
+          .all(|column| column[*row].is_some())
+        {
+          return Err(InvalidAction::AlreadyOccupied);
+        }
+      }
     }
 
     Ok(messages)
@@ -159,16 +315,29 @@ impl Game {
   /// Given a GameMessage returned by `prepare`, commits it to the game state.
   /// Called at the followers to process game state updates from the leader.
   pub fn commit(&mut self, msg: &GameMessage) -> Result<(), InvalidAction> {
-    let num_rows = self.ruleset.scorings.len();
     let num_dice = self.ruleset.dice.len();
 
     match msg {
       GameMessage::PlayerMessage(_from_player, player_msg) => match player_msg {
         PlayerMessage::JoinGame(name) => {
           let name = name.clone();
-          let score_sheet = vec![None; num_rows];
-          self.players.push(Player { name, score_sheet });
+          let score_sheet = self.ruleset.empty_score_sheet();
+          self.players.push(Player {
+            name,
+            score_sheet,
+            members: Vec::new(),
+            handicap: Handicap::default(),
+          });
         }
+        PlayerMessage::JoinTeam(team, name) => match self.player_index(team) {
+          Some(index) => self.players[index].members.push(name.clone()),
+          None => self.players.push(Player {
+            name: team.clone(),
+            score_sheet: self.ruleset.empty_score_sheet(),
+            members: vec![name.clone()],
+            handicap: Handicap::default(),
+          }),
+        },
         PlayerMessage::StartGame => {
           // all logic handled in GameMessage::PlayerTurn(0)
         }
@@ -179,24 +348,37 @@ impl Game {
         PlayerMessage::Roll => {
           self.times_rolled = self.times_rolled + 1;
         }
-        PlayerMessage::Place(selected_row) => {
+        PlayerMessage::Place(..) | PlayerMessage::PlaceInColumn(..) => {
+          let (column, row) = player_msg.placement().expect("the message is a placement");
           let mut player = self
             .players
             .get_mut(self.player_in_turn)
             .ok_or(InvalidAction::OutOfBounds)?;
 
-          player.score_sheet = update_score_sheet(
+          player.score_sheet = self.ruleset.place(
             &player.score_sheet,
-            &self.ruleset.scorings,
-            *selected_row,
+            column,
+            row,
             &self.roll,
+            &player.handicap,
           )?;
         }
+        PlayerMessage::Announce(row) => {
+          self.announced = Some(*row);
+        }
+        PlayerMessage::SetHandicap(player, handicap) => {
+          self
+            .players
+            .get_mut(*player)
+            .ok_or(InvalidAction::OutOfBounds)?
+            .handicap = handicap.clone();
+        }
       },
       GameMessage::PlayerTurn(player) => {
         self.times_rolled = 0;
         self.keep = vec![false; num_dice];
-        self.roll = vec![1; num_dice];
+        self.roll = unrolled(&self.ruleset);
+        self.announced = None;
         self.player_in_turn = *player;
         self.state = State::FirstRoll;
         if self.player_in_turn == 0 {
@@ -206,7 +388,7 @@ impl Game {
       GameMessage::RollResult(roll) => {
         self.roll = roll.clone();
 
-        if self.times_rolled < self.ruleset.rolls {
+        if self.times_rolled < self.rolls() {
           self.state = State::Reroll;
         } else {
           self.state = State::Place;
@@ -230,20 +412,76 @@ impl Game {
     roll_dice_keeping(&self.ruleset.dice, &self.roll, &self.keep)
   }
 
+  /// Returns the score sheet the player in turn would have after placing the current roll on `row` of `column`.
+  /// Fails the same way as `PlayerMessage::PlaceInColumn` would in the current state.
+  pub fn preview_place(
+    &self,
+    column: usize,
+    row: usize,
+  ) -> Result<Vec<Vec<Option<u64>>>, InvalidAction> {
+    if !matches!(self.state, State::Reroll | State::Place) {
+      return Err(InvalidAction::WrongState);
+    }
+    if matches!(self.announced, Some(announced) if announced != row) {
+      return Err(InvalidAction::OutOfOrder);
+    }
+
+    let player = self
+      .players
+      .get(self.player_in_turn)
+      .ok_or(InvalidAction::OutOfBounds)?;
+
+    self.ruleset.place(
+      &player.score_sheet,
+      column,
+      row,
+      &self.roll,
+      &player.handicap,
+    )
+  }
+
+  /// Rolls per turn for the player in turn, including any extra rolls of their handicap.
+  pub fn rolls(&self) -> u64 {
+    let extra_rolls = self
+      .players
+      .get(self.player_in_turn)
+      .map(|player| player.handicap.extra_rolls)
+      .unwrap_or(0);
+    self.ruleset.rolls.saturating_add(extra_rolls)
+  }
+
+  /// Whether a player, team or team member already goes by `name`.
+  pub fn is_name_taken(&self, name: &str) -> bool {
+    self
+      .players
+      .iter()
+      .any(|player| player.name == name || player.members.iter().any(|member| member == name))
+  }
+
+  /// The index of the player or team of the given name.
+  pub fn player_index(&self, name: &str) -> Option<usize> {
+    self.players.iter().position(|player| player.name == name)
+  }
+
+  /// The member of the team in turn taking the turn, if a team is in turn.
+  pub fn member_in_turn(&self) -> Option<&str> {
+    self
+      .players
+      .get(self.player_in_turn)
+      .and_then(|player| player.member_in_turn(self.round))
+  }
+
+  /// Exact odds of each scoring row for the player in turn, should they roll with the current holds.
+  pub fn reroll_odds(&self) -> Vec<RowOdds> {
+    let rolls_left = self.rolls().saturating_sub(self.times_rolled);
+    reroll_odds(&self.ruleset, &self.roll, &self.keep, rolls_left)
+  }
+
   pub fn scoreboard(&self) -> Vec<(u64, &str)> {
     let mut result: Vec<(u64, &str)> = self
       .players
       .iter()
-      .map(|player| {
-        (
-          player
-            .score_sheet
-            .iter()
-            .map(|item| item.or(Some(0)).unwrap())
-            .sum(),
-          player.name.as_str(),
-        )
-      })
+      .map(|player| (player.total(&self.ruleset), player.name.as_str()))
       .collect();
 
     result.sort();
@@ -366,12 +604,12 @@ mod tests {
 
     assert_eq!(
       game.players[0].score_sheet,
-      [Some(24), Some(50), Some(25), Some(0)],
+      [[Some(24), Some(50), Some(25), Some(0)]],
       "player 0 score sheet"
     );
     assert_eq!(
       game.players[1].score_sheet,
-      [Some(18), Some(0), Some(0), Some(0)],
+      [[Some(18), Some(0), Some(0), Some(0)]],
       "player 1 score sheet"
     );
     assert_eq!(
@@ -380,10 +618,7 @@ mod tests {
       "scoreboard"
     );
 
-    let mut game2 = Game::new(mini_rules());
-    for message in game.message_history.iter() {
-      game2.commit(message).unwrap();
-    }
+    let game2 = Game::replay(mini_rules(), &game.message_history).unwrap();
     assert_eq!(
       game, game2,
       "a game produced by replaying another's message history should be equal to the original one"
@@ -397,5 +632,385 @@ mod tests {
       game.message_history, history3,
       "messages should survive a serialize-deserialize cycle"
     );
+
+    let saved =
+      serde_json::from_str::<SavedGame>(&serde_json::to_string(&game.save()).unwrap()).unwrap();
+    assert_eq!(
+      saved.load().unwrap(),
+      game,
+      "a saved game should load back to the original one"
+    );
+
+    let mut json = serde_json::to_value(game.save()).unwrap();
+    json.as_object_mut().unwrap().remove("chat");
+    assert_eq!(
+      serde_json::from_value::<SavedGame>(json).unwrap(),
+      game.save(),
+      "games saved before there was chat should still load"
+    );
+  }
+
+  #[test]
+  fn test_preview_place() {
+    let mut game = Game::new(mini_rules());
+    let (sixes, bonus, full_house) = (0, 1, 2);
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+        GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+        GameMessage::PlayerTurn(0),
+      ],
+    );
+    assert_eq!(
+      game.preview_place(0, sixes),
+      Err(InvalidAction::WrongState),
+      "cannot place before rolling"
+    );
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+        GameMessage::RollResult(vec![6, 6, 6, 6, 5]),
+      ],
+    );
+    assert_eq!(
+      game.preview_place(0, sixes),
+      Ok(vec![vec![Some(24), Some(50), None, None]]),
+      "placing the sixes would also score the bonus"
+    );
+    assert_eq!(
+      game.preview_place(0, full_house),
+      Ok(vec![vec![None, None, Some(0), None]])
+    );
+    assert_eq!(
+      game.preview_place(0, bonus),
+      Err(InvalidAction::NotSelectable)
+    );
+    assert_eq!(
+      game.players[0].score_sheet,
+      [[None, None, None, None]],
+      "previewing does not change the score sheet"
+    );
+  }
+
+  #[test]
+  fn test_columns() {
+    let ruleset = Ruleset {
+      columns: vec![1, 2],
+      ..mini_rules()
+    };
+    let mut game = Game::new(ruleset.clone());
+    let (sixes, full_house, small_straight) = (0, 2, 3);
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+        GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+        GameMessage::PlayerTurn(0),
+      ],
+    );
+    let rows = [
+      (0, sixes),
+      (1, sixes),
+      (1, full_house),
+      (0, full_house),
+      (0, small_straight),
+      (1, small_straight),
+    ];
+    for (turn, (column, row)) in rows.into_iter().enumerate() {
+      let roll = game.prepare(0, &PlayerMessage::Roll).unwrap();
+      game.commit(&roll[0]).unwrap();
+      game
+        .commit(&GameMessage::RollResult(vec![6, 6, 6, 6, 5]))
+        .unwrap();
+      assert_eq!(
+        game.prepare(0, &PlayerMessage::PlaceInColumn(2, row)),
+        Err(InvalidAction::OutOfBounds)
+      );
+
+      let messages = game
+        .prepare(0, &PlayerMessage::PlaceInColumn(column, row))
+        .unwrap();
+      assert_eq!(
+        messages.last() == Some(&GameMessage::GameFinished),
+        turn == rows.len() - 1,
+        "every row is filled in every column before the game ends"
+      );
+      run_game(&mut game, &messages);
+    }
+
+    assert_eq!(
+      game.players[0].score_sheet,
+      [
+        [Some(24), Some(50), Some(0), Some(0)],
+        [Some(24), Some(50), Some(0), Some(0)]
+      ]
+    );
+    assert_eq!(game.scoreboard(), [((24 + 50) * 3, "Henry")]);
+    assert_eq!(Game::replay(ruleset, &game.message_history).unwrap(), game);
+  }
+
+  #[test]
+  fn test_teams() {
+    let mut game = Game::new(mini_rules());
+    let (sixes, full_house, small_straight) = (0, 2, 3);
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::JoinTeam("Red".into(), "Henry".into())),
+        GameMessage::PlayerMessage(1, PlayerMessage::JoinGame("Japsu".into())),
+        GameMessage::PlayerMessage(0, PlayerMessage::JoinTeam("Red".into(), "Bobby".into())),
+      ],
+    );
+    assert_eq!(game.players.len(), 2, "Bobby joined the team of Henry");
+    assert_eq!(game.players[0].members, ["Henry", "Bobby"]);
+    assert_eq!(
+      game.prepare(2, &PlayerMessage::JoinTeam("Japsu".into(), "Hank".into())),
+      Err(InvalidAction::NotATeam)
+    );
+    assert_eq!(
+      game.prepare(2, &PlayerMessage::JoinTeam("Red".into(), "Henry".into())),
+      Err(InvalidAction::NameTaken),
+      "members have different names"
+    );
+    assert_eq!(
+      game.prepare(2, &PlayerMessage::JoinTeam("Blue".into(), "Japsu".into())),
+      Err(InvalidAction::NameTaken)
+    );
+    assert_eq!(
+      game.prepare(2, &PlayerMessage::JoinGame("Red".into())),
+      Err(InvalidAction::NameTaken),
+      "players and teams have different names"
+    );
+    assert_eq!(
+      game.prepare(2, &PlayerMessage::JoinGame("Bobby".into())),
+      Err(InvalidAction::NameTaken)
+    );
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+        GameMessage::PlayerTurn(0),
+      ],
+    );
+    let rows = [
+      sixes,
+      full_house,
+      full_house,
+      small_straight,
+      small_straight,
+    ];
+    let mut members_in_turn = Vec::new();
+    for row in rows {
+      members_in_turn.push(game.member_in_turn().map(String::from));
+      let player = game.player_in_turn;
+      let roll = game.prepare(player, &PlayerMessage::Roll).unwrap();
+      game.commit(&roll[0]).unwrap();
+      game
+        .commit(&GameMessage::RollResult(vec![6, 6, 6, 6, 5]))
+        .unwrap();
+      let messages = game.prepare(player, &PlayerMessage::Place(row)).unwrap();
+      run_game(&mut game, &messages);
+    }
+    assert_eq!(
+      members_in_turn,
+      [
+        Some("Henry".into()),
+        None,
+        Some("Bobby".into()),
+        None,
+        Some("Henry".into())
+      ],
+      "team members take turns alternately"
+    );
+
+    assert_eq!(
+      game.scoreboard(),
+      [(24 + 50, "Red"), (0, "Japsu")],
+      "teams are ranked like players"
+    );
+  }
+
+  #[test]
+  fn test_handicap() {
+    let mut game = Game::new(mini_rules());
+    let handicap = Handicap {
+      starting_points: 10,
+      extra_rolls: 1,
+      bonus_reduction: 0,
+    };
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+        GameMessage::PlayerMessage(1, PlayerMessage::JoinGame("Bobby".into())),
+      ],
+    );
+    assert_eq!(
+      game.prepare(1, &PlayerMessage::SetHandicap(1, handicap.clone())),
+      Err(InvalidAction::NotYourTurn),
+      "only the host sets handicaps"
+    );
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::SetHandicap(2, handicap.clone())),
+      Err(InvalidAction::OutOfBounds)
+    );
+    let huge = Handicap {
+      starting_points: u64::MAX,
+      ..handicap.clone()
+    };
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::SetHandicap(1, huge)),
+      Err(InvalidAction::InvalidHandicap)
+    );
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::SetHandicap(1, handicap.clone())),
+        GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+        GameMessage::PlayerTurn(0),
+      ],
+    );
+    assert_eq!(game.players[1].handicap, handicap);
+    assert_eq!(
+      game.scoreboard(),
+      [(10, "Bobby"), (0, "Henry")],
+      "starting points count from the start"
+    );
+
+    // Henry gets three rolls and Bobby four
+    for player in 0..2 {
+      for _ in 0..game.rolls() {
+        let roll = game.prepare(player, &PlayerMessage::Roll).unwrap();
+        game.commit(&roll[0]).unwrap();
+        game.commit(&GameMessage::RollResult(vec![1; 5])).unwrap();
+      }
+      assert_eq!(game.state, State::Place);
+      assert_eq!(game.times_rolled, 3 + player as u64);
+      let messages = game.prepare(player, &PlayerMessage::Place(2)).unwrap();
+      run_game(&mut game, &messages);
+    }
+  }
+
+  /// A game of Henry alone, rolled once.
+  fn rolled_game(order: PlacementOrder) -> Game {
+    let mut game = Game::new(Ruleset {
+      order,
+      ..mini_rules()
+    });
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+        GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+        GameMessage::PlayerTurn(0),
+        GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+        GameMessage::RollResult(vec![6, 6, 6, 5, 5]),
+      ],
+    );
+    game
+  }
+
+  #[test]
+  fn test_forced_order() {
+    let (sixes, bonus, full_house, small_straight) = (0, 1, 2, 3);
+
+    let mut game = rolled_game(PlacementOrder::TopDown);
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::Place(full_house)),
+      Err(InvalidAction::OutOfOrder)
+    );
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::Place(bonus)),
+      Err(InvalidAction::NotSelectable)
+    );
+    assert_eq!(
+      game.ruleset.forced_row(&game.players[0].score_sheet[0]),
+      Some(sixes)
+    );
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::Place(sixes)),
+        GameMessage::PlayerTurn(0),
+      ],
+    );
+    assert_eq!(
+      game.ruleset.forced_row(&game.players[0].score_sheet[0]),
+      Some(full_house),
+      "the bonus row is skipped"
+    );
+
+    let game = rolled_game(PlacementOrder::BottomUp);
+    assert_eq!(game.preview_place(0, sixes), Err(InvalidAction::OutOfOrder));
+    assert!(game
+      .prepare(0, &PlayerMessage::Place(small_straight))
+      .is_ok());
+  }
+
+  #[test]
+  fn test_announced() {
+    let (sixes, bonus, full_house) = (0, 1, 2);
+
+    let game = rolled_game(PlacementOrder::Free);
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::Announce(full_house)),
+      Err(InvalidAction::WrongState),
+      "only announced rules have announcing"
+    );
+
+    let mut game = rolled_game(PlacementOrder::Announced);
+    assert!(
+      game.prepare(0, &PlayerMessage::Place(sixes)).is_ok(),
+      "the first roll may be placed anywhere without announcing"
+    );
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::Roll),
+      Err(InvalidAction::OutOfOrder),
+      "rerolling requires announcing"
+    );
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::Announce(bonus)),
+      Err(InvalidAction::NotSelectable)
+    );
+
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::Announce(full_house)),
+        GameMessage::PlayerMessage(0, PlayerMessage::ToggleHold(0)),
+        GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+        GameMessage::RollResult(vec![6, 6, 6, 6, 6]),
+      ],
+    );
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::Announce(sixes)),
+      Err(InvalidAction::WrongState),
+      "a row is announced only once"
+    );
+    assert_eq!(
+      game.prepare(0, &PlayerMessage::Place(sixes)),
+      Err(InvalidAction::OutOfOrder)
+    );
+    run_game(
+      &mut game,
+      &[
+        GameMessage::PlayerMessage(0, PlayerMessage::Place(full_house)),
+        GameMessage::PlayerTurn(0),
+      ],
+    );
+    assert_eq!(game.announced, None, "announcing is for one turn");
+    assert_eq!(
+      Game::replay(game.ruleset.clone(), &game.message_history).unwrap(),
+      game
+    );
   }
 }
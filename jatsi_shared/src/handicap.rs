@@ -0,0 +1,100 @@
+use serde::{self, Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// The most starting points a handicap can give.
+pub const MAX_STARTING_POINTS: u64 = 1000;
+/// The most extra rolls per turn a handicap can give.
+pub const MAX_EXTRA_ROLLS: u64 = 10;
+/// The most points sooner a handicap can give the bonus.
+pub const MAX_BONUS_REDUCTION: u64 = 1000;
+
+/// Evens out games between players of different skill. Set for each player before the game starts.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Handicap {
+  /// Points the player starts the game with.
+  pub starting_points: u64,
+  /// Rolls per turn on top of those of the ruleset.
+  pub extra_rolls: u64,
+  /// How many points fewer the player needs for the bonus.
+  pub bonus_reduction: u64,
+}
+
+impl Handicap {
+  pub fn is_none(&self) -> bool {
+    *self == Self::default()
+  }
+
+  /// Whether each part of the handicap is within its limit.
+  pub fn is_valid(&self) -> bool {
+    self.starting_points <= MAX_STARTING_POINTS
+      && self.extra_rolls <= MAX_EXTRA_ROLLS
+      && self.bonus_reduction <= MAX_BONUS_REDUCTION
+  }
+
+  /// A compact form for narrow score cards, like "+10 +1r -13b".
+  pub fn short(&self) -> String {
+    let mut parts = Vec::new();
+    if self.starting_points > 0 {
+      parts.push(format!("+{}", self.starting_points));
+    }
+    if self.extra_rolls > 0 {
+      parts.push(format!("+{}r", self.extra_rolls));
+    }
+    if self.bonus_reduction > 0 {
+      parts.push(format!("-{}b", self.bonus_reduction));
+    }
+    parts.join(" ")
+  }
+}
+
+impl Display for Handicap {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    let mut parts = Vec::new();
+    if self.starting_points > 0 {
+      parts.push(format!("{} starting points", self.starting_points));
+    }
+    match self.extra_rolls {
+      0 => {}
+      1 => parts.push("an extra roll per turn".into()),
+      rolls => parts.push(format!("{} extra rolls per turn", rolls)),
+    }
+    if self.bonus_reduction > 0 {
+      parts.push(format!("the bonus {} points sooner", self.bonus_reduction));
+    }
+
+    match parts.len() {
+      0 => write!(f, "no handicap"),
+      _ => write!(f, "{}", parts.join(", ")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_describe() {
+    let handicap = Handicap {
+      starting_points: 10,
+      extra_rolls: 1,
+      bonus_reduction: 13,
+    };
+    assert_eq!(
+      handicap.to_string(),
+      "10 starting points, an extra roll per turn, the bonus 13 points sooner"
+    );
+    assert_eq!(handicap.short(), "+10 +1r -13b");
+
+    assert!(handicap.is_valid());
+    let too_many_rolls = Handicap {
+      extra_rolls: u64::MAX,
+      ..handicap
+    };
+    assert!(!too_many_rolls.is_valid());
+
+    assert!(Handicap::default().is_none());
+    assert_eq!(Handicap::default().to_string(), "no handicap");
+    assert_eq!(Handicap::default().short(), "");
+  }
+}
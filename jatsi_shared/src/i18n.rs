@@ -0,0 +1,230 @@
+use serde::{self, Deserialize, Serialize};
+
+use crate::errors::InvalidAction;
+use crate::events::{and_list, Event};
+use crate::handicap::Handicap;
+use crate::rules::Scoring;
+
+/// The languages the built-in texts can be shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+  #[default]
+  English,
+  Finnish,
+}
+
+impl Language {
+  pub const ALL: [Language; 2] = [Self::English, Self::Finnish];
+
+  /// The name of the language in the language itself.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::English => "English",
+      Self::Finnish => "Suomi",
+    }
+  }
+}
+
+/// Built-in texts that can be shown in other languages than English.
+pub trait Localize {
+  fn localize(&self, language: Language) -> String;
+}
+
+impl Localize for Scoring {
+  fn localize(&self, language: Language) -> String {
+    use Scoring::*;
+
+    match language {
+      Language::English => self.name(),
+      Language::Finnish => match self {
+        Numbers { num } => match num {
+          1 => "Ykköset".into(),
+          2 => "Kakkoset".into(),
+          3 => "Kolmoset".into(),
+          4 => "Neloset".into(),
+          5 => "Viitoset".into(),
+          6 => "Kuutoset".into(),
+          n => format!("{}:t", n),
+        },
+        Bonus { .. } => "Bonus".into(),
+        SetOf { num } => match num {
+          2 => "Pari".into(),
+          3 => "Kolme samaa".into(),
+          4 => "Neljä samaa".into(),
+          n => format!("{} samaa", n),
+        },
+        Straight { min_length, .. } => match min_length {
+          4 => "Pieni suora".into(),
+          5 => "Suuri suora".into(),
+          n => format!("{} suora", n),
+        },
+        TwoPairs {} => "Kaksi paria".into(),
+        FullHouse { .. } => "Täyskäsi".into(),
+        Yahtzee { .. } => "Jatsi".into(),
+        Chance => "Sattuma".into(),
+        // Named in the ruleset file
+        Custom { .. } => self.name(),
+      },
+    }
+  }
+}
+
+impl Localize for InvalidAction {
+  fn localize(&self, language: Language) -> String {
+    match language {
+      Language::English => self.to_string(),
+      Language::Finnish => match self {
+        Self::NotYourTurn => "ei ole vuorosi".into(),
+        Self::WrongState => "tätä ei voi tehdä nyt".into(),
+        Self::OutOfBounds => "rajojen ulkopuolella (näin ei pitäisi käydä :)".into(),
+        Self::AlreadyOccupied => "valittu rivi on jo täytetty".into(),
+        Self::NotSelectable => "bonusriviä ei voi valita".into(),
+        Self::OutOfOrder => "säännöt vaativat toisen rivin tai rivin ilmoittamisen ensin".into(),
+        Self::NotATeam => "sen niminen pelaaja pelaa yksin".into(),
+        Self::InvalidHandicap => "tasoitus on sallittua suurempi".into(),
+        Self::NameTaken => "sen niminen on jo liittynyt".into(),
+      },
+    }
+  }
+}
+
+impl Localize for Handicap {
+  fn localize(&self, language: Language) -> String {
+    match language {
+      Language::English => self.to_string(),
+      Language::Finnish => {
+        let mut parts = Vec::new();
+        if self.starting_points > 0 {
+          parts.push(format!("{} aloituspistettä", self.starting_points));
+        }
+        match self.extra_rolls {
+          0 => {}
+          1 => parts.push("yksi lisäheitto vuorossa".into()),
+          rolls => parts.push(format!("{} lisäheittoa vuorossa", rolls)),
+        }
+        if self.bonus_reduction > 0 {
+          parts.push(format!("bonus {} pistettä aiemmin", self.bonus_reduction));
+        }
+
+        match parts.len() {
+          0 => "ei tasoitusta".into(),
+          _ => parts.join(", "),
+        }
+      }
+    }
+  }
+}
+
+impl Localize for Event {
+  fn localize(&self, language: Language) -> String {
+    match language {
+      Language::English => self.to_string(),
+      Language::Finnish => match self {
+        Self::Joined { name } => format!("{} liittyi peliin", name),
+        Self::JoinedTeam { name, team } => format!("{} liittyi joukkueeseen {}", name, team),
+        Self::Handicapped { name, handicap } if handicap.is_none() => {
+          format!("{} pelaa ilman tasoitusta", name)
+        }
+        Self::Handicapped { name, handicap } => {
+          format!(
+            "{} pelaa tasoituksella {}",
+            name,
+            handicap.localize(language)
+          )
+        }
+        Self::GameStarted => "Peli alkaa".into(),
+        Self::RoundBegins(round) => format!("Kierros {} alkaa", round),
+        Self::Held { name, dice } => {
+          let dice: Vec<usize> = dice.iter().map(|die| die + 1).collect();
+          let noun = if dice.len() == 1 { "nopan" } else { "nopat" };
+          format!("{} piti {} {}", name, noun, and_list(&dice, language))
+        }
+        Self::Rolled { name, roll } => {
+          let roll: Vec<String> = roll.iter().map(|value| value.to_string()).collect();
+          format!("{} heitti {}", name, roll.join(" "))
+        }
+        Self::Announced { name, row } => format!("{} ilmoitti rivin {}", name, row),
+        Self::Scored {
+          name,
+          points,
+          row,
+          column,
+          reason,
+          wilds,
+          bonus,
+        } => {
+          let mut text = format!("{} sai {} pistettä riviltä {}", name, points, row);
+          if let Some(column) = column {
+            text += &format!(" sarakkeessa {}", column + 1);
+          }
+          text += &format!(" ({})", reason);
+          match wilds.len() {
+            0 => {}
+            1 => text += &format!(" jokerin ollessa {}", wilds[0]),
+            _ => text += &format!(" jokerien ollessa {}", and_list(wilds, language)),
+          }
+          if let Some(bonus) = bonus {
+            text += &format!(" ja {} pisteen bonuksen", bonus);
+          }
+          text
+        }
+        Self::GameFinished { winners, points } => match winners.len() {
+          1 => format!("Peli päättyi! {} voitti {} pisteellä", winners[0], points),
+          _ => format!(
+            "Peli päättyi! {} jakavat voiton {} pisteellä",
+            and_list(winners, language),
+            points
+          ),
+        },
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::ee_rules;
+
+  #[test]
+  fn test_localize() {
+    let names: Vec<String> = ee_rules()
+      .scorings
+      .iter()
+      .map(|scoring| scoring.localize(Language::Finnish))
+      .collect();
+    assert_eq!(
+      names,
+      [
+        "Ykköset",
+        "Kakkoset",
+        "Kolmoset",
+        "Neloset",
+        "Viitoset",
+        "Kuutoset",
+        "Bonus",
+        "Kolme samaa",
+        "Neljä samaa",
+        "Täyskäsi",
+        "Pieni suora",
+        "Suuri suora",
+        "Sattuma",
+        "Jatsi",
+      ]
+    );
+
+    let straight = Scoring::Straight {
+      min_length: 4,
+      value: 30,
+    };
+    assert_eq!(straight.localize(Language::English), "Small Straight");
+    assert_eq!(
+      InvalidAction::NotYourTurn.localize(Language::Finnish),
+      "ei ole vuorosi"
+    );
+    assert_eq!(
+      InvalidAction::NotYourTurn.localize(Language::English),
+      InvalidAction::NotYourTurn.to_string()
+    );
+  }
+}
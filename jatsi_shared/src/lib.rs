@@ -1,4 +1,15 @@
+pub mod analysis;
+pub mod chat;
 pub mod dice;
 pub mod errors;
+pub mod events;
+pub mod expression;
 pub mod game;
+pub mod handicap;
+pub mod i18n;
+pub mod probability;
+pub mod protocol;
+pub mod ratings;
 pub mod rules;
+pub mod stats;
+pub mod tournament;
@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::dice::Die;
+use crate::rules::Ruleset;
+
+/// The odds of a single scoring row after the remaining rerolls of a turn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowOdds {
+  /// Probability of the row scoring more than zero points.
+  pub probability: f64,
+  /// Expected points for the row.
+  pub expected_score: f64,
+}
+
+/// Computes the exact odds of every scoring row of `ruleset`, in order, given the current roll.
+/// The dice marked in `keep` are held for the next roll. Should rolls remain after that, the holds are assumed to be
+/// chosen separately for each row and each of its two figures so as to maximize that figure.
+/// The Bonus row never scores by itself, so it always gets zero odds.
+pub fn reroll_odds(
+  ruleset: &Ruleset,
+  roll: &[u64],
+  keep: &[bool],
+  rolls_left: u64,
+) -> Vec<RowOdds> {
+  let values = expected_values(&ruleset.dice, roll, keep, rolls_left, |roll| {
+    ruleset
+      .scorings
+      .iter()
+      .enumerate()
+      .flat_map(|(row, _)| {
+        let points = ruleset.score(row, roll);
+        [if points > 0 { 1.0 } else { 0.0 }, points as f64]
+      })
+      .collect()
+  });
+
+  values
+    .chunks(2)
+    .map(|chunk| RowOdds {
+      probability: chunk[0],
+      expected_score: chunk[1],
+    })
+    .collect()
+}
+
+/// Expected value of each component returned by `value` after the remaining rerolls, when the dice marked in `keep`
+/// are held for the next roll and every later hold is chosen separately for each component so as to maximize it.
+/// `value` must not depend on the order of identical dice.
+pub fn expected_values<F>(
+  dice: &[Die],
+  roll: &[u64],
+  keep: &[bool],
+  rolls_left: u64,
+  value: F,
+) -> Vec<f64>
+where
+  F: Fn(&[u64]) -> Vec<f64>,
+{
+  TurnSolver::new(dice, value).expected_values(roll, keep, rolls_left)
+}
+
+/// Finds the dice to hold for the next roll that maximize the expected value of `value` at the end of the turn,
+/// assuming all later holds are chosen optimally too. Returns the holds and the expected value they yield.
+/// Holding all dice is the same as not rolling again.
+pub fn best_keep<F>(dice: &[Die], roll: &[u64], rolls_left: u64, value: F) -> (Vec<bool>, f64)
+where
+  F: Fn(&[u64]) -> f64,
+{
+  TurnSolver::new(dice, |roll: &[u64]| vec![value(roll)]).best_keep(roll, rolls_left)
+}
+
+/// Roughly how much work solving each roll of a turn with `dice` takes: the number of distinct rolls times the number of
+/// distinct outcomes of all the ways to reroll one of them. The time taken grows in proportion.
+pub fn solver_work(dice: &[Die]) -> f64 {
+  // Identical dice are interchangeable, so their values count as multisets
+  let multisets = |sides: usize, count: usize| binomial((sides + count).saturating_sub(1), count);
+  let mut groups: Vec<(&Die, usize)> = Vec::new();
+  for die in dice {
+    match groups.iter_mut().find(|(other, _)| *other == die) {
+      Some((_, count)) => *count += 1,
+      None => groups.push((die, 1)),
+    }
+  }
+
+  groups
+    .into_iter()
+    .map(|(die, count)| {
+      let rolls = multisets(die.sides(), count);
+      let rerolls: f64 = (0..=count)
+        .map(|rerolled| binomial(count, rerolled) * multisets(die.sides(), rerolled))
+        .sum();
+      rolls * rerolls
+    })
+    .product()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+  (0..k).fold(1.0, |result, i| result * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Answers `expected_values` and `best_keep` for any number of rolls of a turn, with the same `value`. The expected
+/// values of the rolls solved for one question are remembered for the next ones.
+pub struct TurnSolver<'a, F> {
+  solver: Solver<'a, F>,
+}
+
+impl<'a, F> TurnSolver<'a, F>
+where
+  F: Fn(&[u64]) -> Vec<f64>,
+{
+  pub fn new(dice: &'a [Die], value: F) -> Self {
+    Self {
+      solver: Solver::new(dice, value),
+    }
+  }
+
+  /// Like `expected_values`.
+  pub fn expected_values(&mut self, roll: &[u64], keep: &[bool], rolls_left: u64) -> Vec<f64> {
+    if rolls_left == 0 {
+      return (self.solver.value)(roll);
+    }
+
+    self.solver.reroll(roll, reroll_mask(keep), rolls_left)
+  }
+
+  /// Like `best_keep`, maximizing the first component of the value.
+  pub fn best_keep(&mut self, roll: &[u64], rolls_left: u64) -> (Vec<bool>, f64) {
+    let dice = self.solver.dice.len();
+    if rolls_left == 0 {
+      return (vec![true; dice], (self.solver.value)(roll)[0]);
+    }
+
+    let mut best = (0, f64::NEG_INFINITY);
+    for mask in 0..1usize << dice {
+      let expected = self.solver.reroll(roll, mask, rolls_left)[0];
+      if expected > best.1 {
+        best = (mask, expected);
+      }
+    }
+
+    let keep = (0..dice).map(|i| best.0 & (1 << i) == 0).collect();
+    (keep, best.1)
+  }
+}
+
+/// Bit `i` of the mask is set if die `i` is rerolled.
+fn reroll_mask(keep: &[bool]) -> usize {
+  keep
+    .iter()
+    .enumerate()
+    .filter(|(_, &kept)| !kept)
+    .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+type Outcomes = Rc<Vec<(Vec<u64>, f64)>>;
+
+/// Dynamic programming over the rolls of a turn.
+/// Rolls are stored in a canonical form where the values of identical dice are sorted, which is what keeps the number
+/// of distinct states small.
+struct Solver<'a, F> {
+  dice: &'a [Die],
+  groups: Vec<Vec<usize>>,
+  value: F,
+  outcomes: HashMap<usize, Outcomes>,
+  memo: HashMap<(Vec<u64>, u64), Vec<f64>>,
+}
+
+impl<'a, F> Solver<'a, F>
+where
+  F: Fn(&[u64]) -> Vec<f64>,
+{
+  fn new(dice: &'a [Die], value: F) -> Self {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, die) in dice.iter().enumerate() {
+      match groups.iter_mut().find(|group| dice[group[0]] == *die) {
+        Some(group) => group.push(i),
+        None => groups.push(vec![i]),
+      }
+    }
+
+    Self {
+      dice,
+      groups,
+      value,
+      outcomes: HashMap::new(),
+      memo: HashMap::new(),
+    }
+  }
+
+  /// Sorts the values within each group of identical dice, considering only the dice in `mask`.
+  fn sort_groups(&self, roll: &mut [u64], mask: usize) {
+    for group in self.groups.iter() {
+      let indices: Vec<usize> = group
+        .iter()
+        .copied()
+        .filter(|&i| mask & (1 << i) != 0)
+        .collect();
+      let mut values: Vec<u64> = indices.iter().map(|&i| roll[i]).collect();
+      values.sort_unstable();
+      for (i, value) in indices.into_iter().zip(values) {
+        roll[i] = value;
+      }
+    }
+  }
+
+  /// All distinct results of rolling the dice in `mask` with their probabilities.
+  /// Positions of dice not in `mask` are left at zero.
+  fn outcomes(&mut self, mask: usize) -> Outcomes {
+    if let Some(outcomes) = self.outcomes.get(&mask) {
+      return outcomes.clone();
+    }
+
+    let mut all = vec![(vec![0; self.dice.len()], 1.0)];
+    for (i, die) in self.dice.iter().enumerate() {
+      if mask & (1 << i) == 0 {
+        continue;
+      }
+
+      // Repeated faces add up when the outcomes are merged below
+      let faces = die.faces();
+      let sides = faces.len() as f64;
+      all = all
+        .into_iter()
+        .flat_map(|(roll, probability)| {
+          faces.iter().map(move |&value| {
+            let mut roll = roll.clone();
+            roll[i] = value;
+            (roll, probability / sides)
+          })
+        })
+        .collect();
+    }
+
+    let mut distinct: HashMap<Vec<u64>, f64> = HashMap::new();
+    for (mut roll, probability) in all {
+      self.sort_groups(&mut roll, mask);
+      *distinct.entry(roll).or_insert(0.0) += probability;
+    }
+
+    let outcomes: Outcomes = Rc::new(distinct.into_iter().collect());
+    self.outcomes.insert(mask, outcomes.clone());
+    outcomes
+  }
+
+  /// Expected values when the dice in `mask` are rolled now and `rolls_left - 1` rolls remain after that.
+  fn reroll(&mut self, roll: &[u64], mask: usize, rolls_left: u64) -> Vec<f64> {
+    let mut result: Vec<f64> = Vec::new();
+
+    for (outcome, probability) in self.outcomes(mask).iter() {
+      let mut next_roll: Vec<u64> = roll
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+          if mask & (1 << i) != 0 {
+            outcome[i]
+          } else {
+            value
+          }
+        })
+        .collect();
+      self.sort_groups(&mut next_roll, usize::MAX);
+
+      let values = self.solve(&next_roll, rolls_left - 1);
+      if result.is_empty() {
+        result = vec![0.0; values.len()];
+      }
+      for (sum, value) in result.iter_mut().zip(values) {
+        *sum += probability * value;
+      }
+    }
+
+    result
+  }
+
+  /// Expected values of a canonical roll with `rolls_left` rolls remaining, with each component maximized separately.
+  fn solve(&mut self, roll: &[u64], rolls_left: u64) -> Vec<f64> {
+    let key = (roll.to_vec(), rolls_left);
+    if let Some(values) = self.memo.get(&key) {
+      return values.clone();
+    }
+
+    let best = if rolls_left == 0 {
+      (self.value)(roll)
+    } else {
+      // Rerolling nothing is the same as stopping here
+      let mut best = self.solve(roll, rolls_left - 1);
+      for mask in 1..1usize << self.dice.len() {
+        let values = self.reroll(roll, mask, rolls_left);
+        for (best, value) in best.iter_mut().zip(values) {
+          if value > *best {
+            *best = value;
+          }
+        }
+      }
+      best
+    };
+
+    self.memo.insert(key, best.clone());
+    best
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::{mini_rules, Scoring};
+
+  fn assert_close(actual: f64, expected: f64, message: &str) {
+    assert!(
+      (actual - expected).abs() < 1e-9,
+      "{}: expected {}, got {}",
+      message,
+      expected,
+      actual
+    );
+  }
+
+  #[test]
+  fn test_solver_work() {
+    assert_close(
+      solver_work(&vec![Die::Sides(6); 5]),
+      252.0 * 1683.0,
+      "Classic dice",
+    );
+    assert_close(
+      solver_work(&[Die::Sides(4), Die::Sides(6)]),
+      24.0 * 35.0,
+      "Two different dice",
+    );
+  }
+
+  #[test]
+  fn test_no_rolls_left() {
+    let odds = reroll_odds(&mini_rules(), &[5, 5, 1, 5, 1], &[false; 5], 0);
+    let (sixes, bonus, full_house, small_straight) = (0, 1, 2, 3);
+
+    assert_close(odds[sixes].probability, 0.0, "No sixes");
+    assert_close(
+      odds[bonus].expected_score,
+      0.0,
+      "The bonus row never scores by itself",
+    );
+    assert_close(odds[full_house].probability, 1.0, "Full house already");
+    assert_close(odds[full_house].expected_score, 25.0, "Full house already");
+    assert_close(odds[small_straight].probability, 0.0, "No straight");
+  }
+
+  #[test]
+  fn test_one_reroll() {
+    let odds = reroll_odds(
+      &mini_rules(),
+      &[6, 6, 6, 6, 1],
+      &[true, true, true, true, false],
+      1,
+    );
+    let sixes = 0;
+
+    assert_close(odds[sixes].probability, 1.0, "Sixes are already held");
+    assert_close(
+      odds[sixes].expected_score,
+      25.0,
+      "24 held and one sixth of a six",
+    );
+
+    let yahtzee = expected_values(
+      &vec![Die::Sides(6); 5],
+      &[6, 6, 6, 6, 1],
+      &[true, true, true, true, false],
+      1,
+      |roll| vec![Scoring::Yahtzee { value: 50 }.score(roll) as f64],
+    );
+    assert_close(yahtzee[0], 50.0 / 6.0, "One sixth of a Yahtzee");
+  }
+
+  #[test]
+  fn test_later_holds_are_optimal() {
+    let odds = expected_values(
+      &vec![Die::Sides(6); 5],
+      &[6, 6, 6, 6, 1],
+      &[true, true, true, true, false],
+      2,
+      |roll| vec![Scoring::Yahtzee { value: 50 }.score(roll) as f64 / 50.0],
+    );
+    assert_close(odds[0], 1.0 - 25.0 / 36.0, "Two chances at the last six");
+  }
+
+  #[test]
+  fn test_mixed_dice() {
+    let dice = [Die::Sides(4), Die::Sides(6)];
+    let odds = expected_values(&dice, &[1, 1], &[false, false], 1, |roll| {
+      vec![roll.iter().sum::<u64>() as f64]
+    });
+    assert_close(odds[0], 2.5 + 3.5, "Sum of the averages");
+
+    let dice = [Die::Faces(vec![0, 0, 1, 1, 2, 3]), Die::Sides(6)];
+    let odds = expected_values(&dice, &[0, 1], &[false, false], 1, |roll| {
+      vec![roll.iter().sum::<u64>() as f64]
+    });
+    assert_close(odds[0], 7.0 / 6.0 + 3.5, "Repeated faces are more likely");
+  }
+
+  #[test]
+  fn test_best_keep() {
+    let (keep, expected) = best_keep(&vec![Die::Sides(6); 5], &[6, 2, 6, 6, 3], 1, |roll| {
+      Scoring::Numbers { num: 6 }.score(roll) as f64
+    });
+    assert_eq!(keep, [true, false, true, true, false], "Hold the sixes");
+    assert_close(
+      expected,
+      18.0 + 2.0,
+      "Three sixes held and two sixths of a six",
+    );
+  }
+}
@@ -0,0 +1,107 @@
+use serde::{self, Deserialize, Serialize};
+
+use crate::chat::{ChatMessage, Reaction};
+use crate::errors::{InvalidAction, InvalidChat, InvalidLogin, InvalidRuleset, InvalidTournament};
+use crate::game::{GameMessage, PlayerMessage};
+use crate::rules::Ruleset;
+use crate::tournament::{Tournament, TournamentSettings};
+
+/// A registered player. Games played while logged in are attributed to the account.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+  pub id: i64,
+  pub username: String,
+}
+
+/// Messages sent by a client to the server, which holds the authoritative `Game` of each room.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ClientMessage {
+  /// Creates a room playing with the given rules and joins its game as the host.
+  CreateRoom {
+    name: String,
+    ruleset: Ruleset,
+    /// The team to play in, sharing its score sheet with the others joining it.
+    #[serde(default)]
+    team: Option<String>,
+  },
+  /// Enters the room with the given join code and joins its game, or just watches it if it has already started.
+  JoinRoom {
+    name: String,
+    code: String,
+    #[serde(default)]
+    team: Option<String>,
+  },
+  /// An action in the game of the room the client is in.
+  Player(PlayerMessage),
+  /// A chat message to everyone in the room.
+  Chat(String),
+  React(Reaction),
+  /// Creates an account and logs in to it.
+  Register {
+    username: String,
+    password: String,
+  },
+  LogIn {
+    username: String,
+    password: String,
+  },
+  /// Logs in with the token received when last logging in, so that the password need not be stored.
+  Authenticate(String),
+  /// Logs out and forgets the token.
+  LogOut,
+  /// Creates a tournament and registers to it. The client becomes the organizer.
+  CreateTournament {
+    name: String,
+    ruleset: Ruleset,
+    settings: TournamentSettings,
+  },
+  /// Follows the tournament with the given code, registering to it if it has not started yet.
+  JoinTournament {
+    name: String,
+    code: String,
+  },
+  /// Seats the players of the first round. Later rounds start when the previous one has finished.
+  StartTournament,
+  /// Gives up the unfinished game of the named player of the tournament. Players can forfeit their own games and the
+  /// organizer anyone's, say for a player who has left.
+  Forfeit(String),
+}
+
+/// Messages sent by the server to a client.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ServerMessage {
+  /// The client has entered the room with the given join code. The game in it is played with `ruleset`.
+  /// A client may only be in one room at a time.
+  RoomEntered {
+    code: String,
+    ruleset: Ruleset,
+  },
+  NoSuchRoom(String),
+  RulesetRejected(InvalidRuleset),
+  /// The client has joined the game as the player with the given index.
+  Joined(usize),
+  /// A message committed to the authoritative game. Clients commit these to their own copy of the game, in order.
+  /// A newly connected client first receives the whole message history this way.
+  Game(GameMessage),
+  /// The last `PlayerMessage` sent by the client was rejected.
+  Rejected(InvalidAction),
+  /// Chat and reactions in the room. A newly connected client first receives the chat so far.
+  Chat(ChatMessage),
+  /// The last chat message sent by the client was rejected.
+  ChatRejected(InvalidChat),
+  /// The connection is logged in to `account`. The client may store `token` to log in again later.
+  /// While logged in, games are joined with the username of the account.
+  LoggedIn {
+    account: Account,
+    token: String,
+  },
+  LoggedOut,
+  LoginRejected(InvalidLogin),
+  /// The tournament the client follows, sent when entering it and whenever it changes. Players join the rooms of
+  /// their tables with `JoinRoom`.
+  Tournament {
+    code: String,
+    tournament: Tournament,
+  },
+  TournamentRejected(InvalidTournament),
+}
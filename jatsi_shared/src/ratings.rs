@@ -0,0 +1,79 @@
+use serde::{self, Deserialize, Serialize};
+
+pub const INITIAL_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+/// The expected result of a two-player game against `opponent`: 1 for a sure win, 0 for a sure loss.
+pub fn expected_result(rating: f64, opponent: f64) -> f64 {
+  1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Pairwise Elo: every player is compared with every other one as if they had played a two-player game, with
+/// the changes scaled down so that a game is worth as much as a two-player game no matter the number of players.
+/// Returns the change in the rating of each player, given their ratings and final points.
+pub fn rating_changes(ratings: &[f64], points: &[u64]) -> Vec<f64> {
+  let opponents = ratings.len().saturating_sub(1).max(1) as f64;
+
+  (0..ratings.len())
+    .map(|player| {
+      let change: f64 = (0..ratings.len())
+        .filter(|&opponent| opponent != player)
+        .map(|opponent| {
+          let result = match points[player].cmp(&points[opponent]) {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Less => 0.0,
+          };
+          result - expected_result(ratings[player], ratings[opponent])
+        })
+        .sum();
+      K_FACTOR * change / opponents
+    })
+    .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+  pub username: String,
+  pub rating: f64,
+  pub games: usize,
+}
+
+/// Ratings of the players of one ruleset, the highest first.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Leaderboard {
+  /// Identifies the ruleset in the addresses of the leaderboards.
+  pub ruleset_key: String,
+  pub ruleset_name: String,
+  pub entries: Vec<LeaderboardEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+  }
+
+  #[test]
+  fn test_rating_changes() {
+    let changes = rating_changes(&[INITIAL_RATING, INITIAL_RATING], &[200, 150]);
+    assert_close(changes[0], K_FACTOR / 2.0);
+    assert_close(changes[1], -K_FACTOR / 2.0);
+
+    let changes = rating_changes(&[1500.0, 1500.0, 1500.0], &[180, 180, 120]);
+    assert_close(changes[0], changes[1]);
+    assert!(changes[0] > 0.0 && changes[2] < 0.0);
+    assert_close(changes.iter().sum::<f64>(), 0.0);
+
+    let upset = rating_changes(&[1800.0, 1400.0], &[100, 250]);
+    let expected = rating_changes(&[1800.0, 1400.0], &[250, 100]);
+    assert!(
+      upset[1] > expected[0],
+      "beating a much stronger player is worth more than beating a weaker one"
+    );
+
+    assert_eq!(rating_changes(&[1500.0], &[250]), [0.0]);
+  }
+}
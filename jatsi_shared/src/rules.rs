@@ -1,43 +1,227 @@
-use crate::errors::InvalidAction;
+use serde::{self, Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Clone)]
+use crate::dice::{longest_run, Die, MAX_DICE, MAX_FACE_VALUE, MAX_SIDES};
+use crate::errors::{InvalidAction, InvalidRuleset};
+use crate::expression::Expression;
+use crate::handicap::Handicap;
+use crate::i18n::{Language, Localize};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Scoring {
-  Numbers { num: u64 },
-  Bonus { min_points: u64, value: u64 },
-  SetOf { num: u64 },
-  Straight { min_length: u64, value: u64 },
+  Numbers {
+    num: u64,
+  },
+  Bonus {
+    min_points: u64,
+    value: u64,
+  },
+  SetOf {
+    num: u64,
+  },
+  Straight {
+    min_length: u64,
+    value: u64,
+  },
   TwoPairs {},
-  FullHouse { value: u64 },
-  Yahtzee { value: u64 },
+  FullHouse {
+    value: u64,
+  },
+  Yahtzee {
+    value: u64,
+  },
   Chance,
+  /// A row defined in the ruleset file by an expression of the roll.
+  Custom {
+    name: String,
+    expression: Expression,
+  },
 }
 
 use Scoring::*;
 
-fn is_straight_of_at_least(min_length: u64, roll: &[u64]) -> bool {
-  match roll {
-    [x, rest @ ..] => {
-      let mut y = *x;
+/// Why a roll scores what it does on a row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+  pub points: u64,
+  /// The indices of the dice that count, in the order of the dice.
+  pub dice: Vec<usize>,
+  /// Like "three 4s = 12" or "3-4-5-6 found".
+  pub reason: String,
+}
 
-      // if we have a straight longer than required, this will go negative, hence i64
-      let mut required = min_length as i64 - 1;
+/// A number of dice showing the same value, like "three 4s" or "kolme nelosta".
+fn dice_of(count: usize, value: u64, language: Language) -> String {
+  match language {
+    Language::English => {
+      let count_name = match count {
+        1 => return format!("one {}", value),
+        2 => "two".to_string(),
+        3 => "three".to_string(),
+        4 => "four".to_string(),
+        5 => "five".to_string(),
+        6 => "six".to_string(),
+        n => n.to_string(),
+      };
+      format!("{} {}s", count_name, value)
+    }
+    Language::Finnish => {
+      let count_name = match count {
+        1 => "yksi",
+        2 => "kaksi",
+        3 => "kolme",
+        4 => "neljä",
+        5 => "viisi",
+        6 => "kuusi",
+        _ => return format!("{} × {}", count, value),
+      };
+      // One die is named in the nominative, more in the partitive
+      let value_name = match (value, count) {
+        (1, 1) => "ykkönen",
+        (2, 1) => "kakkonen",
+        (3, 1) => "kolmonen",
+        (4, 1) => "nelonen",
+        (5, 1) => "vitonen",
+        (6, 1) => "kutonen",
+        (1, _) => "ykköstä",
+        (2, _) => "kakkosta",
+        (3, _) => "kolmosta",
+        (4, _) => "nelosta",
+        (5, _) => "vitosta",
+        (6, _) => "kutosta",
+        _ => return format!("{} × {}", count, value),
+      };
+      format!("{} {}", count_name, value_name)
+    }
+  }
+}
+
+/// The word joining the last two items of a list in `language`.
+pub(crate) fn and(language: Language) -> &'static str {
+  match language {
+    Language::English => "and",
+    Language::Finnish => "ja",
+  }
+}
+
+/// The indices of the first `count` dice of `roll` showing `value`.
+fn dice_showing(roll: &[u64], value: u64, count: usize) -> Vec<usize> {
+  (0..roll.len())
+    .filter(|&i| roll[i] == value)
+    .take(count)
+    .collect()
+}
+
+/// The values of `roll` from the most common to the least common, the higher first if as common, with their counts.
+fn value_counts(roll: &[u64]) -> Vec<(u64, usize)> {
+  let mut values = roll.to_vec();
+  values.sort_unstable();
+  values.dedup();
+  let mut counts: Vec<(u64, usize)> = values
+    .into_iter()
+    .map(|value| (value, roll.iter().filter(|&&x| x == value).count()))
+    .collect();
+  counts.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+  counts
+}
+
+/// The most ways of assigning values to the wild dice of a roll a ruleset with custom rows can have.
+pub const MAX_WILD_ASSIGNMENTS: u64 = 1000;
+
+/// All the ways of picking `size` values out of `values`, repeats allowed and order ignored.
+fn multisets(values: &[u64], size: usize) -> Vec<Vec<u64>> {
+  match size {
+    0 => vec![vec![]],
+    _ => (0..values.len())
+      .flat_map(|i| {
+        multisets(&values[i..], size - 1)
+          .into_iter()
+          .map(move |mut rest| {
+            rest.insert(0, values[i]);
+            rest
+          })
+      })
+      .collect(),
+  }
+}
 
-      for &i in rest {
-        if i == y - 1 {
-          y = i;
-          required -= 1;
+/// Ways of assigning values to `wilds` wild dice worth trying on `scoring`, given the values of the other dice.
+/// Every built-in scoring is best served by one of a handful of assignments, so only custom rows have to try them
+/// all.
+fn wild_candidates(
+  scoring: &Scoring,
+  fixed: &[u64],
+  wilds: usize,
+  values: &[u64],
+) -> Vec<Vec<u64>> {
+  let count = |value: u64| fixed.iter().filter(|&&x| x == value).count();
+  // Enough wilds to bring each (value, count) up to count, the rest on `rest`
+  let fill = |targets: &[(u64, usize)], rest: u64| {
+    let mut assignment = Vec::new();
+    for &(value, target) in targets {
+      let needed = target.saturating_sub(count(value));
+      assignment.extend(std::iter::repeat_n(value, needed));
+    }
+    (assignment.len() <= wilds).then(|| {
+      assignment.resize(wilds, rest);
+      assignment
+    })
+  };
+  let mut distinct = fixed.to_vec();
+  distinct.sort_unstable();
+  distinct.dedup();
+  let highest = values.last().copied().unwrap_or_default();
+
+  // Sets, numbers, chance and yahtzees want all the wilds on the same value
+  let mut candidates: Vec<Vec<u64>> = values.iter().map(|&value| vec![value; wilds]).collect();
+  match *scoring {
+    Straight { min_length, .. } => {
+      for &start in values {
+        let run: Vec<u64> = (start..start.saturating_add(min_length)).collect();
+        if run.iter().all(|value| values.binary_search(value).is_ok()) {
+          let targets: Vec<(u64, usize)> = run.iter().map(|&value| (value, 1)).collect();
+          candidates.extend(fill(&targets, start));
+        }
+      }
+    }
+    FullHouse { .. } => {
+      for &three in &distinct {
+        for &two in &distinct {
+          if three != two {
+            candidates.extend(fill(&[(three, 3), (two, 2)], three));
+          }
+        }
+      }
+    }
+    TwoPairs {} => {
+      let mut pair_values = distinct.clone();
+      pair_values.push(highest);
+      for (i, &first) in pair_values.iter().enumerate() {
+        for &second in &pair_values[i..] {
+          let targets = if first == second {
+            vec![(first, 4)]
+          } else {
+            vec![(first, 2), (second, 2)]
+          };
+          candidates.extend(fill(&targets, highest));
         }
       }
-
-      required <= 0
     }
-    [] => false,
+    Custom { .. } => candidates = multisets(values, wilds),
+    _ => {}
   }
+  candidates
+}
+
+/// Whether `roll` has `min_length` consecutive values, in any order and whatever the faces of the dice.
+fn is_straight_of_at_least(min_length: u64, roll: &[u64]) -> bool {
+  !roll.is_empty() && longest_run(roll) >= min_length
 }
 
 impl Scoring {
   pub fn name(&self) -> String {
     match *self {
+      Custom { ref name, .. } => name.clone(),
+
       Numbers { num } => match num {
         1 => "Ones".into(),
         2 => "Twos".into(),
@@ -88,11 +272,7 @@ impl Scoring {
       },
 
       Straight { min_length, value } => {
-        let mut roll = roll.to_vec();
-        roll.sort();
-        roll.reverse();
-
-        if is_straight_of_at_least(min_length, &roll) {
+        if is_straight_of_at_least(min_length, roll) {
           value
         } else {
           0
@@ -134,11 +314,128 @@ impl Scoring {
       }
 
       Chance => roll.iter().sum(),
+
+      Custom { ref expression, .. } => expression.evaluate(roll),
     }
   }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl Scoring {
+  /// The points of `roll` along with the dice that give them and why, explained in `language`.
+  pub fn explain(&self, roll: &[u64], language: Language) -> Explanation {
+    let points = self.score(roll);
+    let all_dice: Vec<usize> = (0..roll.len()).collect();
+
+    let (dice, reason) = match self {
+      Chance => {
+        let values: Vec<String> = roll.iter().map(|value| value.to_string()).collect();
+        (all_dice, format!("{} = {}", values.join(" + "), points))
+      }
+      Bonus { .. } => match language {
+        Language::English => (Vec::new(), "scored from the rows above".into()),
+        Language::Finnish => (Vec::new(), "lasketaan yllä olevista riveistä".into()),
+      },
+      _ if points == 0 => {
+        let name = self.localize(language).to_lowercase();
+        match language {
+          Language::English => (Vec::new(), format!("no {}", name)),
+          Language::Finnish => (Vec::new(), format!("ei {}", name)),
+        }
+      }
+
+      Numbers { num } => {
+        let dice = dice_showing(roll, *num, roll.len());
+        let reason = format!("{} = {}", dice_of(dice.len(), *num, language), points);
+        (dice, reason)
+      }
+
+      // The first value in the order of the dice, as scored
+      SetOf { num } => {
+        let num = *num as usize;
+        let value = *roll
+          .iter()
+          .find(|&&value| dice_showing(roll, value, num).len() == num)
+          .expect("a set scores");
+        (
+          dice_showing(roll, value, num),
+          format!("{} = {}", dice_of(num, value, language), points),
+        )
+      }
+
+      Straight { .. } => {
+        let mut values = roll.to_vec();
+        values.sort_unstable();
+        values.dedup();
+        let mut runs: Vec<Vec<u64>> = Vec::new();
+        for value in values {
+          match runs.last_mut() {
+            Some(run) if run.last().map(|last| last + 1) == Some(value) => run.push(value),
+            _ => runs.push(vec![value]),
+          }
+        }
+        // The highest of the longest runs
+        let run = runs
+          .into_iter()
+          .max_by_key(|run| run.len())
+          .unwrap_or_default();
+
+        let dice = (0..roll.len())
+          .filter(|&i| run.contains(&roll[i]) && !roll[..i].contains(&roll[i]))
+          .collect();
+        let values: Vec<String> = run.iter().map(|value| value.to_string()).collect();
+        let reason = match language {
+          Language::English => format!("{} found", values.join("-")),
+          Language::Finnish => format!("{} löytyi", values.join("-")),
+        };
+        (dice, reason)
+      }
+
+      TwoPairs {} => {
+        let pairs: Vec<u64> = value_counts(roll)
+          .into_iter()
+          .flat_map(|(value, count)| vec![value; count / 2])
+          .take(2)
+          .collect();
+        let mut dice = match pairs[..] {
+          [a, b] if a == b => dice_showing(roll, a, 4),
+          [a, b] => [dice_showing(roll, a, 2), dice_showing(roll, b, 2)].concat(),
+          _ => Vec::new(),
+        };
+        dice.sort_unstable();
+        let reason = match pairs[..] {
+          [a, b] if a == b => format!("{} = {}", dice_of(4, a, language), points),
+          [a, b] => format!(
+            "{} {} {} = {}",
+            dice_of(2, a, language),
+            and(language),
+            dice_of(2, b, language),
+            points
+          ),
+          _ => String::new(),
+        };
+        (dice, reason)
+      }
+
+      FullHouse { .. } | Yahtzee { .. } => {
+        let counts: Vec<String> = value_counts(roll)
+          .into_iter()
+          .map(|(value, count)| dice_of(count, value, language))
+          .collect();
+        (all_dice, counts.join(&format!(" {} ", and(language))))
+      }
+
+      Custom { expression, .. } => (all_dice, format!("{} = {}", expression, points)),
+    };
+
+    Explanation {
+      points,
+      dice,
+      reason,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum JokerRule {
   Forced,
   FreeChoice,
@@ -146,18 +443,83 @@ pub enum JokerRule {
   NoJoker,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// The order in which the rows of a column have to be filled.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum PlacementOrder {
+  /// Any free row.
+  #[default]
+  Free,
+  /// The topmost free row.
+  TopDown,
+  /// The bottommost free row.
+  BottomUp,
+  /// Any free row straight after the first roll. Rerolling requires announcing the row first, and the roll must
+  /// then be placed on it.
+  Announced,
+}
+
+fn single_column() -> Vec<u64> {
+  vec![1]
+}
+
+/// Who made a ruleset and what it is about. Built-in rulesets have none.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+  pub name: String,
+  pub description: String,
+  pub author: String,
+}
+
+impl Metadata {
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+}
+
+/// Rulesets are stored in ruleset files as JSON.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Ruleset {
-  pub dice: Vec<u64>,
+  #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+  pub metadata: Metadata,
+  pub dice: Vec<Die>,
   pub scorings: Vec<Scoring>,
+  /// The multiplier of each column of the score sheet. Every row is filled once in every column.
+  #[serde(default = "single_column")]
+  pub columns: Vec<u64>,
+  #[serde(default)]
+  pub order: PlacementOrder,
+  /// Faces showing this value are wild and count as whichever value of the other faces scores the most.
+  #[serde(default)]
+  pub wild: Option<u64>,
+  /// Names of the rows to show instead of the built-in ones, in every language. Parallel to the scorings, and may be
+  /// shorter than them.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub row_names: Vec<Option<String>>,
   pub joker_rule: JokerRule, // not implemented yet
   pub rolls: u64,            // 3
 }
 
+/// The parts of a ruleset that decide how its games play, leaving out its metadata and row names. Rulesets that only
+/// differ in how they are named are the same game.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Gameplay<'a> {
+  dice: &'a [Die],
+  scorings: &'a [Scoring],
+  columns: &'a [u64],
+  order: PlacementOrder,
+  // Left out when unset so that rulesets from before wilds serialize as they did
+  #[serde(skip_serializing_if = "Option::is_none")]
+  wild: Option<u64>,
+  joker_rule: &'a JokerRule,
+  rolls: u64,
+}
+
 /// Mini ruleset. Mainly useful for testing.
 pub fn mini_rules() -> Ruleset {
   Ruleset {
-    dice: vec![6; 5],
+    metadata: Metadata::default(),
+    dice: vec![Die::Sides(6); 5],
     scorings: vec![
       Numbers { num: 6 },
       Bonus {
@@ -170,6 +532,10 @@ pub fn mini_rules() -> Ruleset {
         value: 30,
       },
     ],
+    columns: single_column(),
+    order: PlacementOrder::Free,
+    wild: None,
+    row_names: Vec::new(),
     joker_rule: JokerRule::Forced,
     rolls: 3,
   }
@@ -177,7 +543,8 @@ pub fn mini_rules() -> Ruleset {
 
 pub fn ee_rules() -> Ruleset {
   Ruleset {
-    dice: vec![6; 5],
+    metadata: Metadata::default(),
+    dice: vec![Die::Sides(6); 5],
     scorings: vec![
       Numbers { num: 1 },
       Numbers { num: 2 },
@@ -203,6 +570,10 @@ pub fn ee_rules() -> Ruleset {
       Chance {},
       Yahtzee { value: 50 },
     ],
+    columns: single_column(),
+    order: PlacementOrder::Free,
+    wild: None,
+    row_names: Vec::new(),
     joker_rule: JokerRule::Forced,
     rolls: 3,
   }
@@ -210,7 +581,8 @@ pub fn ee_rules() -> Ruleset {
 
 pub fn roleplayers_rules() -> Ruleset {
   Ruleset {
-    dice: vec![4, 6, 8, 10, 10],
+    metadata: Metadata::default(),
+    dice: [4, 6, 8, 10, 10].map(Die::Sides).to_vec(),
     scorings: vec![
       Numbers { num: 1 },
       Numbers { num: 2 },
@@ -243,26 +615,345 @@ pub fn roleplayers_rules() -> Ruleset {
       Chance {},
       Yahtzee { value: 50 },
     ],
+    columns: single_column(),
+    order: PlacementOrder::Free,
+    wild: None,
+    row_names: Vec::new(),
     joker_rule: JokerRule::Forced,
     rolls: 3,
   }
 }
 
+/// The classic rules played in three columns worth one, two and three times their points.
+pub fn triple_rules() -> Ruleset {
+  Ruleset {
+    columns: vec![1, 2, 3],
+    ..ee_rules()
+  }
+}
+
+/// The classic rules with the rows filled from top to bottom.
+pub fn top_down_rules() -> Ruleset {
+  Ruleset {
+    order: PlacementOrder::TopDown,
+    ..ee_rules()
+  }
+}
+
+/// The classic rules where the row has to be announced before rerolling.
+pub fn announced_rules() -> Ruleset {
+  Ruleset {
+    order: PlacementOrder::Announced,
+    ..ee_rules()
+  }
+}
+
+/// The built-in rulesets with their display names.
+pub fn builtin_rulesets() -> Vec<(&'static str, Ruleset)> {
+  vec![
+    ("Classic", ee_rules()),
+    ("Roleplayer's", roleplayers_rules()),
+    ("Triple Yahtzee", triple_rules()),
+    ("Top-down", top_down_rules()),
+    ("Announced", announced_rules()),
+    ("Mini", mini_rules()),
+  ]
+}
+
 impl Ruleset {
+  /// The display name of the built-in ruleset this plays like, if any.
+  pub fn builtin_name(&self) -> Option<&'static str> {
+    builtin_rulesets()
+      .into_iter()
+      .find(|(_, builtin)| builtin.gameplay() == self.gameplay())
+      .map(|(name, _)| name)
+  }
+
+  /// What decides how the ruleset plays, to compare and tell rulesets apart by.
+  pub fn gameplay(&self) -> Gameplay<'_> {
+    Gameplay {
+      dice: &self.dice,
+      scorings: &self.scorings,
+      columns: &self.columns,
+      order: self.order,
+      wild: self.wild,
+      joker_rule: &self.joker_rule,
+      rolls: self.rolls,
+    }
+  }
+
+  /// The name of the ruleset to show: that of its metadata, or that of a built-in ruleset.
+  pub fn display_name(&self) -> Option<String> {
+    match self.metadata.name.trim() {
+      "" => self.builtin_name().map(|name| name.to_string()),
+      name => Some(name.to_string()),
+    }
+  }
+
+  /// The name of a row in `language`, or the name the ruleset gives it.
+  pub fn row_name(&self, row: usize, language: Language) -> String {
+    match self.row_names.get(row) {
+      Some(Some(name)) if !name.trim().is_empty() => name.clone(),
+      _ => self
+        .scorings
+        .get(row)
+        .map(|scoring| scoring.localize(language))
+        .unwrap_or_default(),
+    }
+  }
+
+  /// One round for every row besides the bonus in every column.
   pub fn rounds(&self) -> usize {
-    self
+    let rows = self
       .scorings
       .iter()
       .filter(|item| if let Bonus { .. } = item { false } else { true })
-      .count()
+      .count();
+    rows * self.columns.len()
+  }
+
+  pub fn empty_score_sheet(&self) -> Vec<Vec<Option<u64>>> {
+    vec![vec![None; self.scorings.len()]; self.columns.len()]
+  }
+
+  /// The points of a score sheet, each column weighted by its multiplier.
+  pub fn total(&self, score_sheet: &[Vec<Option<u64>>]) -> u64 {
+    self
+      .columns
+      .iter()
+      .zip(score_sheet)
+      .map(|(multiplier, column)| {
+        let points = column.iter().fold(0, |sum: u64, points| {
+          sum.saturating_add(points.unwrap_or(0))
+        });
+        multiplier.saturating_mul(points)
+      })
+      .fold(0, u64::saturating_add)
+  }
+
+  /// The row that has to be filled next in a column of a score sheet, if the placement order decides it.
+  pub fn forced_row(&self, column: &[Option<u64>]) -> Option<usize> {
+    let mut free_rows = self
+      .scorings
+      .iter()
+      .zip(column)
+      .enumerate()
+      .filter(|(_, (scoring, points))| !matches!(scoring, Bonus { .. }) && points.is_none())
+      .map(|(row, _)| row);
+
+    match self.order {
+      PlacementOrder::TopDown => free_rows.next(),
+      PlacementOrder::BottomUp => free_rows.next_back(),
+      PlacementOrder::Free | PlacementOrder::Announced => None,
+    }
+  }
+
+  pub fn is_wild(&self, value: u64) -> bool {
+    self.wild == Some(value)
+  }
+
+  /// How many ways there are at most of assigning values to the wild dice of a roll, up to a bit over
+  /// `MAX_WILD_ASSIGNMENTS`.
+  fn wild_assignments(&self) -> u64 {
+    let wild_dice = self
+      .dice
+      .iter()
+      .filter(|die| die.faces().into_iter().any(|value| self.is_wild(value)))
+      .count() as u64;
+    let mut values: Vec<u64> = self
+      .dice
+      .iter()
+      .flat_map(|die| die.faces())
+      .filter(|&value| !self.is_wild(value))
+      .collect();
+    values.sort_unstable();
+    values.dedup();
+
+    // Multisets of k values out of n: (n + k - 1)! / (k! (n - 1)!), one k at a time to stay exact
+    let n = values.len() as u64;
+    let mut assignments = 1;
+    for k in 1..=wild_dice {
+      assignments = assignments * (n + k - 1) / k;
+      if assignments > MAX_WILD_ASSIGNMENTS {
+        break;
+      }
+    }
+    assignments
+  }
+
+  /// `roll` with each wild face counting as the value that scores the most on `row`, the lowest such values if
+  /// several do.
+  pub fn assign_wilds(&self, row: usize, roll: &[u64]) -> Vec<u64> {
+    let wild_dice: Vec<usize> = (0..roll.len()).filter(|&i| self.is_wild(roll[i])).collect();
+    let scoring = match self.scorings.get(row) {
+      Some(scoring) if !wild_dice.is_empty() => scoring,
+      _ => return roll.to_vec(),
+    };
+
+    let mut values: Vec<u64> = self
+      .dice
+      .iter()
+      .flat_map(|die| die.faces())
+      .filter(|&value| !self.is_wild(value))
+      .collect();
+    values.sort_unstable();
+    values.dedup();
+
+    let fixed: Vec<u64> = roll
+      .iter()
+      .copied()
+      .filter(|&value| !self.is_wild(value))
+      .collect();
+    let mut best: Option<(u64, Vec<u64>, Vec<u64>)> = None;
+    for mut assignment in wild_candidates(scoring, &fixed, wild_dice.len(), &values) {
+      assignment.sort_unstable();
+      let mut assigned = roll.to_vec();
+      for (&i, &value) in wild_dice.iter().zip(&assignment) {
+        assigned[i] = value;
+      }
+      let points = scoring.score(&assigned);
+      if best
+        .as_ref()
+        .is_none_or(|(best_points, best_assignment, _)| {
+          points > *best_points || (points == *best_points && assignment < *best_assignment)
+        })
+      {
+        best = Some((points, assignment, assigned));
+      }
+    }
+    best.map_or_else(|| roll.to_vec(), |(_, _, assigned)| assigned)
+  }
+
+  /// The values the wild faces of `roll` count as on `row`, in the order of the dice.
+  pub fn assigned_wilds(&self, row: usize, roll: &[u64]) -> Vec<u64> {
+    roll
+      .iter()
+      .zip(self.assign_wilds(row, roll))
+      .filter(|(&value, _)| self.is_wild(value))
+      .map(|(_, assigned)| assigned)
+      .collect()
+  }
+
+  /// Points of `roll` on `row`, with the wild faces counting as their best values.
+  pub fn score(&self, row: usize, roll: &[u64]) -> u64 {
+    self
+      .scorings
+      .get(row)
+      .map_or(0, |scoring| scoring.score(&self.assign_wilds(row, roll)))
+  }
+
+  /// Why `roll` scores what it does on `row`, in `language`, with the wild faces counting as their best values.
+  pub fn explain(&self, row: usize, roll: &[u64], language: Language) -> Option<Explanation> {
+    self
+      .scorings
+      .get(row)
+      .map(|scoring| scoring.explain(&self.assign_wilds(row, roll), language))
+  }
+
+  /// Places `roll` on `row` of `column` for a player with `handicap`, returning the new score sheet. Wild faces count
+  /// as their best values for the row. Announcing is up to the game.
+  pub fn place(
+    &self,
+    score_sheet: &[Vec<Option<u64>>],
+    column: usize,
+    row: usize,
+    roll: &[u64],
+    handicap: &Handicap,
+  ) -> Result<Vec<Vec<Option<u64>>>, InvalidAction> {
+    let current = score_sheet.get(column).ok_or(InvalidAction::OutOfBounds)?;
+    let roll = self.assign_wilds(row, roll);
+    let mut new_score_sheet = score_sheet.to_vec();
+    new_score_sheet[column] = update_score_sheet(
+      current,
+      &self.scorings,
+      row,
+      &roll,
+      handicap.bonus_reduction,
+    )?;
+    if matches!(self.forced_row(current), Some(forced) if forced != row) {
+      return Err(InvalidAction::OutOfOrder);
+    }
+    Ok(new_score_sheet)
+  }
+
+  /// Checks that a game can be played with these rules.
+  pub fn validate(&self) -> Result<(), InvalidRuleset> {
+    if self.dice.is_empty() {
+      return Err(InvalidRuleset::NoDice);
+    }
+    if self.dice.len() > MAX_DICE {
+      return Err(InvalidRuleset::TooManyDice(self.dice.len()));
+    }
+    if let Some(index) = self.dice.iter().position(|die| die.sides() == 0) {
+      return Err(InvalidRuleset::NoSides(index));
+    }
+    if let Some(index) = self.dice.iter().position(|die| die.sides() > MAX_SIDES) {
+      return Err(InvalidRuleset::TooManySides(index));
+    }
+    if let Some(index) = self
+      .dice
+      .iter()
+      .position(|die| die.faces().into_iter().any(|face| face > MAX_FACE_VALUE))
+    {
+      return Err(InvalidRuleset::FaceTooHigh(index));
+    }
+    if self.rolls == 0 {
+      return Err(InvalidRuleset::NoRolls);
+    }
+    if self.columns.is_empty() {
+      return Err(InvalidRuleset::NoColumns);
+    }
+    if self.rounds() == 0 {
+      return Err(InvalidRuleset::NoScorings);
+    }
+    // update_score_sheet only knows of one bonus row
+    let bonuses = self
+      .scorings
+      .iter()
+      .filter(|scoring| matches!(scoring, Bonus { .. }))
+      .count();
+    if bonuses > 1 {
+      return Err(InvalidRuleset::MultipleBonuses);
+    }
+    // Custom rows try every way of assigning the wilds, which has to stay cheap enough to do on each placement
+    let custom = self
+      .scorings
+      .iter()
+      .any(|scoring| matches!(scoring, Custom { .. }));
+    if custom && self.wild_assignments() > MAX_WILD_ASSIGNMENTS {
+      return Err(InvalidRuleset::TooManyWildAssignments);
+    }
+
+    Ok(())
+  }
+
+  /// Reads and validates the contents of a ruleset file.
+  pub fn from_json(json: &str) -> Result<Self, InvalidRuleset> {
+    let ruleset: Self =
+      serde_json::from_str(json).map_err(|err| InvalidRuleset::Unreadable(err.to_string()))?;
+    ruleset.validate()?;
+    Ok(ruleset)
+  }
+
+  /// Writes the contents of a ruleset file. Rows at the end that keep their built-in names are left out of the row
+  /// names, so that a ruleset reads back the same however many of them it had.
+  pub fn to_json(&self) -> String {
+    let mut ruleset = self.clone();
+    while ruleset.row_names.last() == Some(&None) {
+      ruleset.row_names.pop();
+    }
+    serde_json::to_string_pretty(&ruleset).expect("rulesets should serialize")
   }
 }
 
+/// Places `roll` on a row of one column of a score sheet. The bonus of the column is scored from that column alone,
+/// needing `bonus_reduction` points fewer than the ruleset says.
 pub fn update_score_sheet(
   score_sheet: &[Option<u64>],
   scorings: &[Scoring],
   selected_index: usize,
   roll: &[u64],
+  bonus_reduction: u64,
 ) -> Result<Vec<Option<u64>>, InvalidAction> {
   let scoring = scorings
     .get(selected_index)
@@ -312,7 +1003,7 @@ pub fn update_score_sheet(
       .iter()
       .map(|&row| if let Some(pts) = row { pts } else { 0 })
       .sum::<u64>()
-      >= *min_points;
+      >= min_points.saturating_sub(bonus_reduction);
 
     if all_bonus_affecting_rows_filled || bonus_threshold_crossed {
       // Bonus will be scored now
@@ -404,7 +1095,17 @@ mod tests {
       small_straight.score(&[6, 5, 4, 3, 2]),
       30,
       "A large straight is also a small straight"
-    )
+    );
+    assert_eq!(
+      small_straight.score(&[6, 4, 3, 2, 1]),
+      30,
+      "Lower small straight below a higher die"
+    );
+    assert_eq!(
+      small_straight.score(&[2, 0, 3, 1, 0]),
+      30,
+      "Small straight of custom faces from zero up"
+    );
   }
 
   #[test]
@@ -501,6 +1202,83 @@ mod tests {
     assert_eq!(chance.score(&[6, 5, 4, 4, 2]), 21);
   }
 
+  #[test]
+  fn test_custom() {
+    let json = r#"{"Custom": {"name": "Low Set", "expression": "if count_max >= 3 and max <= 3 then sum else 0"}}"#;
+    let low_set: Scoring = serde_json::from_str(json).unwrap();
+    assert_eq!(low_set.name(), "Low Set");
+    assert_eq!(low_set.score(&[2, 2, 2, 3, 1]), 10);
+    assert_eq!(low_set.score(&[2, 2, 2, 3, 6]), 0);
+
+    let mut ruleset = serde_json::to_value(mini_rules()).unwrap();
+    ruleset["scorings"][0] =
+      serde_json::json!({"Custom": {"name": "Broken", "expression": "sum >"}});
+    match Ruleset::from_json(&ruleset.to_string()) {
+      Err(InvalidRuleset::Unreadable(reason)) => assert!(
+        reason.starts_with("the expression ends too soon at position 6"),
+        "{}",
+        reason
+      ),
+      other => panic!("read a broken expression: {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_explain() {
+    let explain = |scoring: Scoring, roll: &[u64]| {
+      let explanation = scoring.explain(roll, Language::English);
+      (explanation.dice, explanation.reason)
+    };
+
+    assert_eq!(
+      explain(SetOf { num: 3 }, &[4, 1, 4, 4, 2]),
+      (vec![0, 2, 3], "three 4s = 12".into())
+    );
+    assert_eq!(
+      explain(
+        Straight {
+          min_length: 4,
+          value: 30
+        },
+        &[5, 3, 6, 4, 5]
+      ),
+      (vec![0, 1, 2, 3], "3-4-5-6 found".into())
+    );
+    assert_eq!(
+      explain(Numbers { num: 1 }, &[1, 5, 5, 5, 5]),
+      (vec![0], "one 1 = 1".into())
+    );
+    assert_eq!(
+      explain(TwoPairs {}, &[2, 5, 2, 5, 5]),
+      (vec![0, 1, 2, 3], "two 5s and two 2s = 14".into())
+    );
+    assert_eq!(
+      explain(FullHouse { value: 25 }, &[2, 3, 2, 3, 3]).1,
+      "three 3s and two 2s"
+    );
+    assert_eq!(explain(Chance {}, &[1, 2, 3]).1, "1 + 2 + 3 = 6");
+    assert_eq!(
+      explain(Yahtzee { value: 50 }, &[1, 2, 3]),
+      (vec![], "no yahtzee".into())
+    );
+
+    let explanation = Scoring::Numbers { num: 4 }.explain(&[4, 4, 1, 2, 3], Language::Finnish);
+    assert_eq!(explanation.points, 8);
+    assert_eq!(explanation.reason, "kaksi nelosta = 8");
+    assert_eq!(
+      TwoPairs {}
+        .explain(&[2, 5, 2, 5, 5], Language::Finnish)
+        .reason,
+      "kaksi vitosta ja kaksi kakkosta = 14"
+    );
+    assert_eq!(
+      Yahtzee { value: 50 }
+        .explain(&[1, 2, 3], Language::Finnish)
+        .reason,
+      "ei jatsi"
+    );
+  }
+
   #[test]
   fn test_bonus() {
     let value = 10;
@@ -516,7 +1294,7 @@ mod tests {
 
     let before: Vec<Option<u64>> = vec![Some(3), None, None, None];
     let expected: Vec<Option<u64>> = vec![Some(3), Some(6), Some(value), None];
-    let actual = update_score_sheet(&before, &scorings, 1, &[2, 2, 2, 3, 4]).unwrap();
+    let actual = update_score_sheet(&before, &scorings, 1, &[2, 2, 2, 3, 4], 0).unwrap();
     assert_eq!(actual, expected);
   }
 
@@ -549,21 +1327,21 @@ mod tests {
     let mut score_sheet: Vec<Option<u64>> = vec![None; scorings.len()];
 
     // 8 - Yahtzee
-    score_sheet = update_score_sheet(&score_sheet, &scorings, 8, &[6, 6, 6, 6, 6]).unwrap();
+    score_sheet = update_score_sheet(&score_sheet, &scorings, 8, &[6, 6, 6, 6, 6], 0).unwrap();
     assert_eq!(
       score_sheet,
       [None, None, None, None, None, None, None, None, Some(50)]
     );
 
     // 0 - Ones
-    score_sheet = update_score_sheet(&score_sheet, &scorings, 0, &[6, 6, 1, 1, 1]).unwrap();
+    score_sheet = update_score_sheet(&score_sheet, &scorings, 0, &[6, 6, 1, 1, 1], 0).unwrap();
     assert_eq!(
       score_sheet,
       [Some(3), None, None, None, None, None, None, None, Some(50)]
     );
 
     // 3 - Set of 3
-    score_sheet = update_score_sheet(&score_sheet, &scorings, 3, &[6, 6, 5, 5, 5]).unwrap();
+    score_sheet = update_score_sheet(&score_sheet, &scorings, 3, &[6, 6, 5, 5, 5], 0).unwrap();
     assert_eq!(
       score_sheet,
       [
@@ -580,7 +1358,7 @@ mod tests {
     );
 
     // 5 - Straight
-    score_sheet = update_score_sheet(&score_sheet, &scorings, 5, &[5, 5, 4, 3, 2]).unwrap();
+    score_sheet = update_score_sheet(&score_sheet, &scorings, 5, &[5, 5, 4, 3, 2], 0).unwrap();
     assert_eq!(
       score_sheet,
       [
@@ -597,7 +1375,7 @@ mod tests {
     );
 
     // 8 - OMG! YAHTZEE AGAIN!
-    score_sheet = update_score_sheet(&score_sheet, &scorings, 8, &[1, 1, 1, 1, 1]).unwrap();
+    score_sheet = update_score_sheet(&score_sheet, &scorings, 8, &[1, 1, 1, 1, 1], 0).unwrap();
     assert_eq!(
       score_sheet,
       [
@@ -614,7 +1392,7 @@ mod tests {
     );
 
     // 1 - Twos
-    score_sheet = update_score_sheet(&score_sheet, &scorings, 1, &[1, 2, 2, 2, 2]).unwrap();
+    score_sheet = update_score_sheet(&score_sheet, &scorings, 1, &[1, 2, 2, 2, 2], 0).unwrap();
 
     // Bonus should be scored at this point!
     assert_eq!(
@@ -634,4 +1412,270 @@ mod tests {
 
     // TODO finish game :)
   }
+
+  #[test]
+  fn test_columns() {
+    let ruleset = triple_rules();
+    let none = Handicap::default();
+    assert_eq!(ruleset.rounds(), 3 * 13);
+
+    let mut score_sheet = ruleset.empty_score_sheet();
+    let sixes = 5;
+    for column in 0..3 {
+      score_sheet = ruleset
+        .place(&score_sheet, column, sixes, &[6, 6, 6, 6, 1], &none)
+        .unwrap();
+    }
+    assert_eq!(
+      ruleset.place(&score_sheet, 1, sixes, &[6, 6, 6, 6, 6], &none),
+      Err(InvalidAction::AlreadyOccupied)
+    );
+    assert_eq!(
+      ruleset.place(&score_sheet, 3, sixes, &[6, 6, 6, 6, 6], &none),
+      Err(InvalidAction::OutOfBounds)
+    );
+    assert_eq!(ruleset.total(&score_sheet), 24 + 2 * 24 + 3 * 24);
+
+    // Scoring the bonus in one column does not score it in the others
+    score_sheet = ruleset
+      .place(&score_sheet, 2, 4, &[5, 5, 5, 5, 5], &none)
+      .unwrap();
+    score_sheet = ruleset
+      .place(&score_sheet, 2, 3, &[4, 4, 4, 4, 1], &none)
+      .unwrap();
+    let bonus = 6;
+    assert_eq!(score_sheet[2][bonus], Some(50));
+    assert_eq!(score_sheet[1][bonus], None);
+    assert_eq!(
+      ruleset.total(&score_sheet),
+      24 + 2 * 24 + 3 * (24 + 25 + 16 + 50)
+    );
+  }
+
+  #[test]
+  fn test_bonus_reduction() {
+    let ruleset = mini_rules();
+    let (sixes, bonus) = (0, 1);
+    let handicap = Handicap {
+      bonus_reduction: 6,
+      ..Handicap::default()
+    };
+    let score_sheet = ruleset.empty_score_sheet();
+
+    let without = ruleset
+      .place(
+        &score_sheet,
+        0,
+        sixes,
+        &[6, 6, 6, 1, 1],
+        &Handicap::default(),
+      )
+      .unwrap();
+    assert_eq!(
+      without[0][bonus],
+      Some(0),
+      "18 points are not enough without the handicap"
+    );
+    let with = ruleset
+      .place(&score_sheet, 0, sixes, &[6, 6, 6, 1, 1], &handicap)
+      .unwrap();
+    assert_eq!(
+      with[0][bonus],
+      Some(50),
+      "18 points are enough with the handicap"
+    );
+  }
+
+  #[test]
+  fn test_wilds() {
+    let ruleset = Ruleset {
+      dice: vec![Die::Faces(vec![0, 2, 3, 4, 5, 6]); 5],
+      wild: Some(0),
+      ..ee_rules()
+    };
+    let (sixes, large_straight, chance, yahtzee) = (5, 11, 12, 13);
+
+    assert_eq!(ruleset.score(large_straight, &[0, 2, 3, 4, 6]), 40);
+    assert_eq!(
+      ruleset.assigned_wilds(large_straight, &[0, 2, 3, 4, 6]),
+      [5],
+      "the wild completes the straight"
+    );
+    assert_eq!(ruleset.assigned_wilds(yahtzee, &[0, 4, 0, 4, 4]), [4, 4]);
+    assert_eq!(ruleset.score(chance, &[0, 2, 3, 4, 6]), 21);
+    assert_eq!(ruleset.score(sixes, &[0, 0, 3, 4, 6]), 18);
+    assert_eq!(
+      ruleset.score(sixes, &[2, 2, 3, 4, 6]),
+      6,
+      "rolls without wilds score as usual"
+    );
+
+    let score_sheet = ruleset.empty_score_sheet();
+    let placed = ruleset
+      .place(
+        &score_sheet,
+        0,
+        yahtzee,
+        &[0, 4, 0, 4, 4],
+        &Handicap::default(),
+      )
+      .unwrap();
+    assert_eq!(placed[0][yahtzee], Some(50));
+  }
+
+  #[test]
+  fn test_wild_candidates() {
+    let ruleset = Ruleset {
+      dice: vec![Die::Faces(vec![0, 1, 2, 4, 5, 6]); 5],
+      wild: Some(0),
+      scorings: vec![
+        Numbers { num: 3 },
+        SetOf { num: 3 },
+        Straight {
+          min_length: 4,
+          value: 30,
+        },
+        TwoPairs {},
+        FullHouse { value: 25 },
+        Yahtzee { value: 50 },
+        Chance,
+      ],
+      ..ee_rules()
+    };
+    let values = [1, 2, 4, 5, 6];
+
+    // Every roll with wilds scores as well as trying all the assignments would
+    for roll in multisets(&[0, 1, 2, 4, 5, 6], 5) {
+      let wilds = roll.iter().filter(|&&value| value == 0).count();
+      for (row, scoring) in ruleset.scorings.iter().enumerate() {
+        let best = multisets(&values, wilds)
+          .into_iter()
+          .map(|assignment| {
+            let mut assigned: Vec<u64> = roll.iter().copied().filter(|&value| value != 0).collect();
+            assigned.extend(assignment);
+            scoring.score(&assigned)
+          })
+          .max()
+          .unwrap();
+        assert_eq!(
+          ruleset.score(row, &roll),
+          best,
+          "{:?} on {}",
+          roll,
+          scoring.name()
+        );
+      }
+    }
+
+    let huge = Ruleset {
+      dice: vec![Die::Faces((0..=200).collect()); 10],
+      wild: Some(0),
+      ..ee_rules()
+    };
+    let started = std::time::Instant::now();
+    assert_eq!(huge.score(13, &[0; 10]), 50);
+    assert!(started.elapsed().as_secs() < 1);
+    assert_eq!(huge.validate(), Ok(()));
+
+    let custom = Ruleset {
+      scorings: vec![Custom {
+        name: "Sum".into(),
+        expression: "sum".parse().unwrap(),
+      }],
+      ..huge
+    };
+    assert_eq!(
+      custom.validate(),
+      Err(InvalidRuleset::TooManyWildAssignments)
+    );
+  }
+
+  #[test]
+  fn test_row_names() {
+    let ruleset = Ruleset {
+      row_names: vec![None, Some("Ässät".into())],
+      ..ee_rules()
+    };
+    assert_eq!(ruleset.row_name(0, Language::Finnish), "Ykköset");
+    assert_eq!(ruleset.row_name(1, Language::English), "Ässät");
+    assert_eq!(ruleset.row_name(10, Language::English), "Small Straight");
+    assert_eq!(
+      ruleset.display_name().as_deref(),
+      Some("Classic"),
+      "row names do not change the game"
+    );
+
+    let json = r#"{"metadata": {"name": "Kesämökki", "author": "Japsu"}, "dice": [6, 6], "scorings": ["Chance"],
+      "joker_rule": "Forced", "rolls": 3}"#;
+    let ruleset = Ruleset::from_json(json).unwrap();
+    assert_eq!(ruleset.display_name().as_deref(), Some("Kesämökki"));
+    assert_eq!(ruleset.metadata.description, "");
+    assert_eq!(ee_rules().display_name().as_deref(), Some("Classic"));
+
+    let unnamed = Ruleset {
+      row_names: vec![None; ee_rules().scorings.len()],
+      ..ee_rules()
+    };
+    let json = unnamed.to_json();
+    assert!(!json.contains("row_names") && !json.contains("metadata"));
+    assert_eq!(
+      Ruleset::from_json(&json),
+      Ok(ee_rules()),
+      "rows without names are not written"
+    );
+  }
+
+  #[test]
+  fn test_ruleset_file() {
+    for (name, ruleset) in builtin_rulesets() {
+      assert_eq!(ruleset.validate(), Ok(()), "{} is valid", name);
+      assert_eq!(
+        Ruleset::from_json(&ruleset.to_json()),
+        Ok(ruleset),
+        "{} survives a write-read cycle",
+        name
+      );
+    }
+
+    assert!(matches!(
+      Ruleset::from_json("{\"dice\": [6, 6]"),
+      Err(InvalidRuleset::Unreadable(..))
+    ));
+
+    let mut ruleset = mini_rules();
+    ruleset.scorings = vec![Bonus {
+      min_points: 1,
+      value: 1,
+    }];
+    assert_eq!(
+      Ruleset::from_json(&ruleset.to_json()),
+      Err(InvalidRuleset::NoScorings),
+      "the bonus alone does not make a game"
+    );
+
+    let ruleset = Ruleset {
+      dice: vec![Die::Sides(6); 64],
+      ..mini_rules()
+    };
+    assert_eq!(ruleset.validate(), Err(InvalidRuleset::TooManyDice(64)));
+    let ruleset = Ruleset {
+      dice: vec![Die::Sides(6), Die::Sides(1_000_000_000)],
+      ..mini_rules()
+    };
+    assert_eq!(ruleset.validate(), Err(InvalidRuleset::TooManySides(1)));
+    let ruleset = Ruleset {
+      dice: vec![Die::Faces(vec![1, u64::MAX]), Die::Sides(6)],
+      ..mini_rules()
+    };
+    assert_eq!(ruleset.validate(), Err(InvalidRuleset::FaceTooHigh(0)));
+
+    let json = mini_rules()
+      .to_json()
+      .replace("\"columns\": [\n    1\n  ],", "");
+    assert_eq!(
+      Ruleset::from_json(&json),
+      Ok(mini_rules()),
+      "ruleset files from before columns have one column"
+    );
+  }
 }
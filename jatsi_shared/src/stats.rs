@@ -0,0 +1,247 @@
+use serde::{self, Deserialize, Serialize};
+
+use crate::errors::InvalidAction;
+use crate::game::{GameMessage, SavedGame};
+use crate::i18n::Language;
+use crate::rules::{Ruleset, Scoring};
+
+/// Stats of a row over all the columns of the score sheet, in points before the multipliers of the columns.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RowStats {
+  pub name: String,
+  pub average: f64,
+  /// How often the row was scratched by placing a roll worth zero points on it.
+  pub zero_rate: f64,
+}
+
+/// Stats of the games of one player played with one ruleset.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RulesetStats {
+  pub ruleset_name: String,
+  pub games: usize,
+  pub average_score: f64,
+  pub best_score: u64,
+  /// Rolls of all dice showing the same face that were placed on the score card.
+  pub yahtzees: usize,
+  /// How often a column scored the bonus. None if the ruleset has no bonus.
+  pub bonus_hit_rate: Option<f64>,
+  pub zero_rate: f64,
+  /// Every row except the bonus.
+  pub rows: Vec<RowStats>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+  pub username: String,
+  pub games_played: usize,
+  /// Games that no longer replay, left out of the stats of the rulesets.
+  #[serde(default)]
+  pub unreadable_games: usize,
+  /// The most played ruleset first.
+  pub rulesets: Vec<RulesetStats>,
+}
+
+fn rate(count: usize, total: usize) -> f64 {
+  if total == 0 {
+    0.0
+  } else {
+    count as f64 / total as f64
+  }
+}
+
+/// What one player did in one finished game.
+struct PlayerGame {
+  score_sheet: Vec<Vec<Option<u64>>>,
+  yahtzees: usize,
+}
+
+fn player_game(saved_game: &SavedGame, player: usize) -> Result<PlayerGame, InvalidAction> {
+  let game = saved_game.load()?;
+  let score_sheet = game
+    .players
+    .get(player)
+    .ok_or(InvalidAction::OutOfBounds)?
+    .score_sheet
+    .clone();
+
+  let mut roll: &[u64] = &[];
+  let mut yahtzees = 0;
+  for msg in game.message_history.iter() {
+    match msg {
+      GameMessage::RollResult(result) => roll = result,
+      GameMessage::PlayerMessage(from_player, player_msg)
+        if *from_player == player
+          && player_msg.placement().is_some()
+          && roll.len() > 1
+          && roll.iter().all(|&value| value == roll[0]) =>
+      {
+        yahtzees += 1;
+      }
+      _ => {}
+    }
+  }
+
+  Ok(PlayerGame {
+    score_sheet,
+    yahtzees,
+  })
+}
+
+fn ruleset_stats(ruleset: &Ruleset, games: &[PlayerGame]) -> RulesetStats {
+  let bonus_index = ruleset
+    .scorings
+    .iter()
+    .position(|scoring| matches!(scoring, Scoring::Bonus { .. }));
+  let totals: Vec<u64> = games
+    .iter()
+    .map(|game| ruleset.total(&game.score_sheet))
+    .collect();
+  // Every column of every game
+  let columns: Vec<&Vec<Option<u64>>> = games
+    .iter()
+    .flat_map(|game| game.score_sheet.iter())
+    .collect();
+
+  let rows: Vec<RowStats> = ruleset
+    .scorings
+    .iter()
+    .enumerate()
+    .filter(|&(index, _)| Some(index) != bonus_index)
+    .map(|(index, _)| {
+      let scored: Vec<u64> = columns.iter().filter_map(|column| column[index]).collect();
+      let zeros = scored.iter().filter(|&&points| points == 0).count();
+      RowStats {
+        name: ruleset.row_name(index, Language::English),
+        average: rate(scored.iter().sum::<u64>() as usize, scored.len()),
+        zero_rate: rate(zeros, scored.len()),
+      }
+    })
+    .collect();
+
+  let (zeros, scored) = columns.iter().fold((0, 0), |(zeros, scored), column| {
+    let rows = column
+      .iter()
+      .enumerate()
+      .filter(|&(index, _)| Some(index) != bonus_index)
+      .filter_map(|(_, points)| *points);
+    rows.fold((zeros, scored), |(zeros, scored), points| {
+      (zeros + (points == 0) as usize, scored + 1)
+    })
+  });
+
+  RulesetStats {
+    ruleset_name: ruleset.builtin_name().unwrap_or("Custom").to_string(),
+    games: games.len(),
+    average_score: rate(totals.iter().sum::<u64>() as usize, totals.len()),
+    best_score: totals.iter().copied().max().unwrap_or(0),
+    yahtzees: games.iter().map(|game| game.yahtzees).sum(),
+    bonus_hit_rate: bonus_index.map(|bonus_index| {
+      let hits = columns
+        .iter()
+        .filter(|column| column[bonus_index].unwrap_or(0) > 0)
+        .count();
+      rate(hits, columns.len())
+    }),
+    zero_rate: rate(zeros, scored),
+    rows,
+  }
+}
+
+/// Computes the stats of a player from the games they have played, each given with the index of the player in it.
+/// Games that do not replay are counted but otherwise skipped, so that one broken game does not hide the rest.
+pub fn player_stats(username: &str, games: &[(SavedGame, usize)]) -> PlayerStats {
+  // Rulesets have no identity besides their contents
+  let mut by_ruleset: Vec<(&Ruleset, Vec<PlayerGame>)> = Vec::new();
+  let mut unreadable_games = 0;
+  for (saved_game, player) in games.iter() {
+    let game = match player_game(saved_game, *player) {
+      Ok(game) => game,
+      Err(_) => {
+        unreadable_games += 1;
+        continue;
+      }
+    };
+    match by_ruleset
+      .iter_mut()
+      .find(|(ruleset, _)| **ruleset == saved_game.ruleset)
+    {
+      Some((_, ruleset_games)) => ruleset_games.push(game),
+      None => by_ruleset.push((&saved_game.ruleset, vec![game])),
+    }
+  }
+
+  let mut rulesets: Vec<RulesetStats> = by_ruleset
+    .iter()
+    .map(|(ruleset, ruleset_games)| ruleset_stats(ruleset, ruleset_games))
+    .collect();
+  rulesets.sort_by_key(|stats| std::cmp::Reverse(stats.games));
+
+  PlayerStats {
+    username: username.to_string(),
+    games_played: games.len(),
+    unreadable_games,
+    rulesets,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::game::{Game, PlayerMessage};
+  use crate::rules::mini_rules;
+
+  fn mini_game(rolls: [[u64; 5]; 3]) -> SavedGame {
+    let mut messages = vec![
+      GameMessage::PlayerMessage(0, PlayerMessage::JoinGame("Henry".into())),
+      GameMessage::PlayerMessage(0, PlayerMessage::StartGame),
+    ];
+    for (row, roll) in [0, 2, 3].into_iter().zip(rolls) {
+      messages.extend([
+        GameMessage::PlayerTurn(0),
+        GameMessage::PlayerMessage(0, PlayerMessage::Roll),
+        GameMessage::RollResult(roll.to_vec()),
+        GameMessage::PlayerMessage(0, PlayerMessage::Place(row)),
+      ]);
+    }
+    messages.push(GameMessage::GameFinished);
+
+    Game::replay(mini_rules(), &messages).unwrap().save()
+  }
+
+  #[test]
+  fn test_player_stats() {
+    // Sixes, bonus, full house and small straight
+    let games = [
+      (
+        mini_game([[6, 6, 6, 6, 6], [2, 2, 3, 3, 3], [1, 1, 1, 1, 1]]),
+        0,
+      ),
+      (
+        mini_game([[1, 2, 3, 4, 5], [1, 2, 3, 4, 5], [4, 1, 2, 3, 4]]),
+        0,
+      ),
+      (
+        mini_game([[1, 2, 3, 4, 5], [1, 2, 3, 4, 5], [4, 1, 2, 3, 4]]),
+        5,
+      ),
+    ];
+    let stats = player_stats("Henry", &games);
+    assert_eq!(stats.games_played, 3);
+    assert_eq!(stats.unreadable_games, 1, "there is no player 5");
+    assert_eq!(stats.rulesets.len(), 1);
+
+    let mini = &stats.rulesets[0];
+    assert_eq!(mini.ruleset_name, "Mini");
+    assert_eq!(mini.games, 2);
+    assert_eq!(mini.best_score, 30 + 50 + 25);
+    assert_eq!(mini.average_score, (105.0 + 30.0) / 2.0);
+    assert_eq!(mini.yahtzees, 2, "rolled sixes and ones");
+    assert_eq!(mini.bonus_hit_rate, Some(0.5));
+    assert_eq!(mini.zero_rate, 3.0 / 6.0);
+
+    let names: Vec<&str> = mini.rows.iter().map(|row| row.name.as_str()).collect();
+    assert_eq!(names, ["Sixes", "Full House", "Small Straight"]);
+    assert_eq!(mini.rows[0].average, 15.0);
+    assert_eq!(mini.rows[1].zero_rate, 0.5);
+  }
+}
@@ -0,0 +1,587 @@
+use serde::{self, Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::errors::InvalidTournament;
+use crate::rules::Ruleset;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Format {
+  /// The top finishers of each table advance to the next round until only one table is left.
+  Bracket { advancing: usize },
+  /// Everyone plays every round, seated with players of similar cumulative points.
+  Swiss { rounds: usize },
+}
+
+/// Decides between players with equal results, in the order given in `TournamentSettings::tie_breaks`.
+/// If all of them are equal, the player who registered first ranks higher.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TieBreak {
+  /// Points over all the games of the tournament.
+  TotalPoints,
+  /// Tables finished with the most points.
+  Wins,
+  /// Points of the best game of the tournament.
+  BestGame,
+}
+
+impl TieBreak {
+  pub fn all() -> [TieBreak; 3] {
+    [TieBreak::TotalPoints, TieBreak::Wins, TieBreak::BestGame]
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      TieBreak::TotalPoints => "Total points",
+      TieBreak::Wins => "Wins",
+      TieBreak::BestGame => "Best game",
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TournamentSettings {
+  /// The most players seated at one table. Tables of a round are filled evenly.
+  pub table_size: usize,
+  pub format: Format,
+  pub tie_breaks: Vec<TieBreak>,
+}
+
+impl TournamentSettings {
+  pub fn validate(&self) -> Result<(), InvalidTournament> {
+    if self.table_size < 2 {
+      return Err(InvalidTournament::TableTooSmall);
+    }
+    match self.format {
+      Format::Bracket { advancing } if advancing == 0 || advancing * 2 > self.table_size => {
+        Err(InvalidTournament::TooManyAdvancing)
+      }
+      Format::Swiss { rounds: 0 } => Err(InvalidTournament::NoRounds),
+      _ => Ok(()),
+    }
+  }
+}
+
+/// One game of a round. Players are given by their index in `Tournament::players`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+  pub players: Vec<usize>,
+  /// The join code of the room the table is played in, once the server has created it.
+  pub room: Option<String>,
+  /// The final points of each player, in the order of `players`, once the game has finished.
+  pub points: Option<Vec<u64>>,
+  /// Players who gave up the game, ranking last at the table with no points.
+  #[serde(default)]
+  pub forfeited: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+  pub player: usize,
+  pub name: String,
+  pub games: usize,
+  pub wins: usize,
+  pub total_points: u64,
+  pub best_game: u64,
+  /// The latest round the player has been seated in.
+  pub last_round: Option<usize>,
+  /// Points of the latest finished game of the player.
+  pub last_points: u64,
+  /// Knocked out of a bracket.
+  pub eliminated: bool,
+  /// Gave up the latest game.
+  #[serde(default)]
+  pub forfeited: bool,
+}
+
+/// Players registered to play a series of games with one ruleset, and the tables of each round.
+/// The tournament only seats players and keeps score: the games themselves are played elsewhere.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tournament {
+  pub ruleset: Ruleset,
+  pub settings: TournamentSettings,
+  pub players: Vec<String>,
+  pub rounds: Vec<Vec<Table>>,
+}
+
+impl Tournament {
+  pub fn new(ruleset: Ruleset, settings: TournamentSettings) -> Result<Self, InvalidTournament> {
+    settings.validate()?;
+    Ok(Self {
+      ruleset,
+      settings,
+      players: Vec::new(),
+      rounds: Vec::new(),
+    })
+  }
+
+  /// Players can only register before the first round. Returns the index of the player.
+  pub fn register(&mut self, name: &str) -> Result<usize, InvalidTournament> {
+    let name = name.trim();
+    if name.is_empty() {
+      return Err(InvalidTournament::EmptyName);
+    }
+    if self.has_started() {
+      return Err(InvalidTournament::AlreadyStarted);
+    }
+    if self.player_index(name).is_some() {
+      return Err(InvalidTournament::NameTaken);
+    }
+
+    self.players.push(name.to_string());
+    Ok(self.players.len() - 1)
+  }
+
+  pub fn player_index(&self, name: &str) -> Option<usize> {
+    self
+      .players
+      .iter()
+      .position(|player| player.eq_ignore_ascii_case(name))
+  }
+
+  pub fn has_started(&self) -> bool {
+    !self.rounds.is_empty()
+  }
+
+  fn round_finished(&self) -> bool {
+    self
+      .rounds
+      .last()
+      .map(|tables| tables.iter().all(|table| table.points.is_some()))
+      .unwrap_or(true)
+  }
+
+  pub fn is_finished(&self) -> bool {
+    if !self.has_started() || !self.round_finished() {
+      return false;
+    }
+    match self.settings.format {
+      Format::Bracket { .. } => self.rounds.last().map(Vec::len) == Some(1),
+      Format::Swiss { rounds } => self.rounds.len() >= rounds,
+    }
+  }
+
+  /// Seats the players of the next round and returns its tables.
+  pub fn start_round(&mut self) -> Result<&mut [Table], InvalidTournament> {
+    if self.players.len() < 2 {
+      return Err(InvalidTournament::NotEnoughPlayers);
+    }
+    if !self.round_finished() {
+      return Err(InvalidTournament::RoundInProgress);
+    }
+    if self.is_finished() {
+      return Err(InvalidTournament::Finished);
+    }
+
+    let standings = self.standings();
+    let tables = match (&self.settings.format, self.rounds.last()) {
+      (_, None) => self.deal((0..self.players.len()).collect()),
+      (Format::Bracket { advancing }, Some(tables)) => {
+        let mut advancers: Vec<usize> = tables
+          .iter()
+          .flat_map(|table| {
+            self
+              .table_ranking(table, &standings)
+              .into_iter()
+              .take(*advancing)
+          })
+          .collect();
+        advancers.sort_by_key(|player| {
+          standings
+            .iter()
+            .position(|standing| standing.player == *player)
+        });
+        self.deal(advancers)
+      }
+      (Format::Swiss { .. }, Some(_)) => {
+        let players = standings.iter().map(|standing| standing.player).collect();
+        self.chunk(players)
+      }
+    };
+
+    self.rounds.push(tables);
+    Ok(self.rounds.last_mut().expect("a round was just added"))
+  }
+
+  /// Sizes of the tables needed to seat `players` evenly.
+  fn table_sizes(&self, players: usize) -> Vec<usize> {
+    let tables = players.div_ceil(self.settings.table_size);
+    (0..tables)
+      .map(|table| players / tables + (table < players % tables) as usize)
+      .collect()
+  }
+
+  /// Seats players one at a time around the tables, so that the best seeds play at different tables.
+  fn deal(&self, players: Vec<usize>) -> Vec<Table> {
+    let mut tables: Vec<Table> = self
+      .table_sizes(players.len())
+      .into_iter()
+      .map(|size| Table {
+        players: Vec::with_capacity(size),
+        room: None,
+        points: None,
+        forfeited: Vec::new(),
+      })
+      .collect();
+    let count = tables.len();
+    for (seat, player) in players.into_iter().enumerate() {
+      tables[seat % count].players.push(player);
+    }
+    tables
+  }
+
+  /// Seats players in order, so that players next to each other in the standings play together.
+  fn chunk(&self, players: Vec<usize>) -> Vec<Table> {
+    let mut players = players.into_iter();
+    self
+      .table_sizes(players.len())
+      .into_iter()
+      .map(|size| Table {
+        players: players.by_ref().take(size).collect(),
+        room: None,
+        points: None,
+        forfeited: Vec::new(),
+      })
+      .collect()
+  }
+
+  /// Records the result of a table from the scoreboard of its game, matching the players by name.
+  pub fn record_result(
+    &mut self,
+    round: usize,
+    table: usize,
+    scoreboard: &[(u64, &str)],
+  ) -> Result<(), InvalidTournament> {
+    let names: Vec<String> = self
+      .rounds
+      .get(round)
+      .and_then(|tables| tables.get(table))
+      .ok_or(InvalidTournament::NoSuchTable)?
+      .players
+      .iter()
+      .map(|&player| self.players[player].clone())
+      .collect();
+
+    let table = &mut self.rounds[round][table];
+    if table.points.is_some() {
+      return Err(InvalidTournament::AlreadyRecorded);
+    }
+    if scoreboard.len() != names.len() {
+      return Err(InvalidTournament::WrongPlayers);
+    }
+    let points = names
+      .iter()
+      .map(|name| {
+        scoreboard
+          .iter()
+          .find(|(_, player)| player.eq_ignore_ascii_case(name))
+          .map(|&(points, _)| points)
+          .ok_or(InvalidTournament::WrongPlayers)
+      })
+      .collect::<Result<Vec<u64>, InvalidTournament>>()?;
+
+    table.points = Some(points);
+    Ok(())
+  }
+
+  /// Ends the unfinished table of `player`, who gives up the game. The others keep the points they have in
+  /// `scoreboard`, the scoreboard of the game so far.
+  pub fn forfeit(
+    &mut self,
+    player: usize,
+    scoreboard: &[(u64, &str)],
+  ) -> Result<(), InvalidTournament> {
+    let (round, table) = self
+      .current_table(player)
+      .ok_or(InvalidTournament::NotPlaying)?;
+    self.record_result(round, table, scoreboard)?;
+
+    let table = &mut self.rounds[round][table];
+    if let (Some(points), Some(seat)) = (
+      table.points.as_mut(),
+      table.players.iter().position(|&seated| seated == player),
+    ) {
+      points[seat] = 0;
+    }
+    table.forfeited.push(player);
+    Ok(())
+  }
+
+  /// The round and table of the unfinished game of a player, if they have one.
+  pub fn current_table(&self, player: usize) -> Option<(usize, usize)> {
+    let round = self.rounds.len().checked_sub(1)?;
+    let table = self.rounds[round]
+      .iter()
+      .position(|table| table.points.is_none() && table.players.contains(&player))?;
+    Some((round, table))
+  }
+
+  /// Compares players by the configured tie-breaks and then by registration. `Less` means that `a` ranks higher.
+  fn break_tie(&self, a: &Standing, b: &Standing) -> Ordering {
+    self
+      .settings
+      .tie_breaks
+      .iter()
+      .map(|tie_break| match tie_break {
+        TieBreak::TotalPoints => b.total_points.cmp(&a.total_points),
+        TieBreak::Wins => b.wins.cmp(&a.wins),
+        TieBreak::BestGame => b.best_game.cmp(&a.best_game),
+      })
+      .find(|ordering| ordering.is_ne())
+      .unwrap_or_else(|| a.player.cmp(&b.player))
+  }
+
+  /// The players of a finished table, the winner first.
+  fn table_ranking(&self, table: &Table, standings: &[Standing]) -> Vec<usize> {
+    let points = table.points.clone().unwrap_or_default();
+    let mut seats: Vec<(u64, &Standing)> = table
+      .players
+      .iter()
+      .enumerate()
+      .filter_map(|(seat, player)| {
+        let standing = standings
+          .iter()
+          .find(|standing| standing.player == *player)?;
+        Some((points.get(seat).copied().unwrap_or(0), standing))
+      })
+      .collect();
+    let forfeited = |standing: &Standing| table.forfeited.contains(&standing.player);
+    seats.sort_by(|(a_points, a), (b_points, b)| {
+      forfeited(a)
+        .cmp(&forfeited(b))
+        .then(b_points.cmp(a_points))
+        .then_with(|| self.break_tie(a, b))
+    });
+    seats
+      .into_iter()
+      .map(|(_, standing)| standing.player)
+      .collect()
+  }
+
+  /// All players, the leader first. Brackets rank by the furthest round reached, Swiss by cumulative points.
+  pub fn standings(&self) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = self
+      .players
+      .iter()
+      .enumerate()
+      .map(|(player, name)| Standing {
+        player,
+        name: name.clone(),
+        games: 0,
+        wins: 0,
+        total_points: 0,
+        best_game: 0,
+        last_round: None,
+        last_points: 0,
+        eliminated: false,
+        forfeited: false,
+      })
+      .collect();
+
+    for (round, tables) in self.rounds.iter().enumerate() {
+      for table in tables.iter() {
+        // Players who forfeit cannot win, even if everyone is still at zero
+        let top = table.points.as_ref().and_then(|points| {
+          table
+            .players
+            .iter()
+            .zip(points)
+            .filter(|(player, _)| !table.forfeited.contains(player))
+            .map(|(_, &points)| points)
+            .max()
+        });
+        for (seat, &player) in table.players.iter().enumerate() {
+          let standing = &mut standings[player];
+          standing.last_round = Some(round);
+          standing.forfeited = table.forfeited.contains(&player);
+          if let Some(points) = table.points.as_ref().map(|points| points[seat]) {
+            standing.games += 1;
+            standing.wins += (!standing.forfeited && Some(points) == top) as usize;
+            standing.total_points += points;
+            standing.best_game = standing.best_game.max(points);
+            standing.last_points = points;
+          }
+        }
+      }
+    }
+
+    if let Format::Bracket { .. } = self.settings.format {
+      let latest = self.rounds.len().checked_sub(1);
+      for standing in standings.iter_mut() {
+        standing.eliminated = latest.is_some() && standing.last_round != latest;
+      }
+    }
+
+    standings.sort_by(|a, b| {
+      let primary = match self.settings.format {
+        Format::Bracket { .. } => b
+          .last_round
+          .cmp(&a.last_round)
+          .then(a.forfeited.cmp(&b.forfeited))
+          .then(b.last_points.cmp(&a.last_points)),
+        Format::Swiss { .. } => b.total_points.cmp(&a.total_points),
+      };
+      primary.then_with(|| self.break_tie(a, b))
+    });
+    standings
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::rules::mini_rules;
+
+  fn tournament(format: Format, names: &[&str]) -> Tournament {
+    let settings = TournamentSettings {
+      table_size: 3,
+      format,
+      tie_breaks: vec![TieBreak::Wins, TieBreak::TotalPoints],
+    };
+    let mut tournament = Tournament::new(mini_rules(), settings).unwrap();
+    for name in names {
+      tournament.register(name).unwrap();
+    }
+    tournament
+  }
+
+  /// Plays out a round where the player who registered last scores the most.
+  fn play_round(tournament: &mut Tournament) {
+    let round = tournament.rounds.len() - 1;
+    for table in 0..tournament.rounds[round].len() {
+      let players = tournament.rounds[round][table].players.clone();
+      let names: Vec<String> = players
+        .iter()
+        .map(|&player| tournament.players[player].clone())
+        .collect();
+      let scoreboard: Vec<(u64, &str)> = players
+        .iter()
+        .zip(names.iter())
+        .map(|(&player, name)| (10 * player as u64, name.as_str()))
+        .collect();
+      tournament.record_result(round, table, &scoreboard).unwrap();
+    }
+  }
+
+  #[test]
+  fn test_settings() {
+    let mut settings = TournamentSettings {
+      table_size: 4,
+      format: Format::Bracket { advancing: 3 },
+      tie_breaks: Vec::new(),
+    };
+    assert_eq!(
+      settings.validate(),
+      Err(InvalidTournament::TooManyAdvancing)
+    );
+    settings.format = Format::Swiss { rounds: 0 };
+    assert_eq!(settings.validate(), Err(InvalidTournament::NoRounds));
+    settings.table_size = 1;
+    assert_eq!(settings.validate(), Err(InvalidTournament::TableTooSmall));
+  }
+
+  #[test]
+  fn test_bracket() {
+    let names = ["A", "B", "C", "D", "E", "F", "G"];
+    let mut tournament = tournament(Format::Bracket { advancing: 1 }, &names);
+    assert_eq!(tournament.register("a"), Err(InvalidTournament::NameTaken));
+
+    let sizes: Vec<usize> = tournament
+      .start_round()
+      .unwrap()
+      .iter()
+      .map(|table| table.players.len())
+      .collect();
+    assert_eq!(sizes, [3, 2, 2], "tables are filled evenly");
+    assert_eq!(
+      tournament.start_round().map(|_| ()),
+      Err(InvalidTournament::RoundInProgress)
+    );
+    assert_eq!(
+      tournament.record_result(0, 1, &[(1, "B")]),
+      Err(InvalidTournament::WrongPlayers)
+    );
+    assert_eq!(
+      tournament.register("H"),
+      Err(InvalidTournament::AlreadyStarted)
+    );
+
+    play_round(&mut tournament);
+    let finalists: Vec<usize> = tournament.start_round().unwrap()[0].players.clone();
+    assert_eq!(
+      finalists,
+      [6, 5, 4],
+      "table winners advance, seeded by points"
+    );
+
+    play_round(&mut tournament);
+    assert!(tournament.is_finished());
+    let standings = tournament.standings();
+    let ranking: Vec<&str> = standings
+      .iter()
+      .map(|standing| standing.name.as_str())
+      .collect();
+    assert_eq!(ranking, ["G", "F", "E", "D", "C", "B", "A"]);
+    assert_eq!(standings[0].wins, 2);
+    assert!(!standings[2].eliminated);
+    assert!(standings[3].eliminated);
+  }
+
+  #[test]
+  fn test_swiss() {
+    let names = ["A", "B", "C", "D", "E", "F"];
+    let mut tournament = tournament(Format::Swiss { rounds: 2 }, &names);
+    tournament.start_round().unwrap();
+    play_round(&mut tournament);
+
+    let tables: Vec<Vec<usize>> = tournament
+      .start_round()
+      .unwrap()
+      .iter()
+      .map(|table| table.players.clone())
+      .collect();
+    assert_eq!(
+      tables,
+      [vec![5, 4, 3], vec![2, 1, 0]],
+      "players with similar points play together"
+    );
+
+    play_round(&mut tournament);
+    assert!(tournament.is_finished());
+    assert_eq!(
+      tournament.start_round().map(|_| ()),
+      Err(InvalidTournament::Finished)
+    );
+    assert_eq!(tournament.standings()[0].total_points, 100);
+  }
+
+  #[test]
+  fn test_forfeit() {
+    let mut tournament = tournament(Format::Bracket { advancing: 1 }, &["A", "B", "C"]);
+    assert_eq!(
+      tournament.forfeit(0, &[]),
+      Err(InvalidTournament::NotPlaying)
+    );
+    tournament.start_round().unwrap();
+    assert_eq!(tournament.current_table(1), Some((0, 0)));
+
+    // C leads but gives up before anyone has scored much
+    tournament
+      .forfeit(2, &[(12, "C"), (0, "A"), (0, "B")])
+      .unwrap();
+    let table = &tournament.rounds[0][0];
+    assert_eq!(table.points, Some(vec![0, 0, 0]));
+    assert_eq!(table.forfeited, [2]);
+    assert_eq!(tournament.current_table(2), None);
+    assert_eq!(
+      tournament.forfeit(2, &[]),
+      Err(InvalidTournament::NotPlaying),
+      "the table has ended"
+    );
+
+    let standings = tournament.standings();
+    assert!(standings[2].forfeited);
+    assert_eq!(standings[2].name, "C", "forfeiting ranks last");
+    assert_eq!(standings[2].wins, 0);
+    assert_eq!(standings[0].wins, 1, "the others tie for the win");
+  }
+}
@@ -0,0 +1,271 @@
+use jatsi_shared::chat::ChatMessage;
+use jatsi_shared::errors::InvalidAction;
+use jatsi_shared::game::{Game, PlayerMessage, State};
+use jatsi_shared::protocol::ServerMessage;
+use jatsi_shared::rules::{PlacementOrder, Ruleset};
+use ratatui::crossterm::event::KeyCode;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+  /// Everyone plays on this terminal, taking turns at the keyboard.
+  HotSeat,
+  /// Playing with others through the server.
+  Online,
+}
+
+pub struct App {
+  pub mode: Mode,
+  pub game: Game,
+  /// Index of the player using this client, once joined. Unused in hot-seat games.
+  pub me: Option<usize>,
+  /// Join code of the room, once entered.
+  pub code: Option<String>,
+  /// The row of the score card to place the roll on.
+  pub selected_row: usize,
+  /// The column of the score sheet to place the roll on, for rulesets with several.
+  pub selected_column: usize,
+  pub status: Option<String>,
+  /// Chat in the room, once entered.
+  pub chat: Vec<ChatMessage>,
+  pub quit: bool,
+}
+
+impl App {
+  pub fn new(mode: Mode, ruleset: Ruleset) -> Self {
+    Self {
+      mode,
+      game: Game::new(ruleset),
+      me: None,
+      code: None,
+      selected_row: 0,
+      selected_column: 0,
+      status: None,
+      chat: Vec::new(),
+      quit: false,
+    }
+  }
+
+  /// A hot-seat game that has already been started with the given players.
+  pub fn hot_seat(ruleset: Ruleset, names: &[String]) -> Result<Self, InvalidAction> {
+    let mut app = Self::new(Mode::HotSeat, ruleset);
+    for (player, name) in names.iter().enumerate() {
+      app.play(player, &PlayerMessage::JoinGame(name.clone()))?;
+    }
+    app.play(0, &PlayerMessage::StartGame)?;
+    Ok(app)
+  }
+
+  /// The player whose actions the keyboard makes.
+  pub fn acting_player(&self) -> Option<usize> {
+    match self.mode {
+      Mode::HotSeat => Some(self.game.player_in_turn),
+      Mode::Online => self.me,
+    }
+  }
+
+  pub fn my_turn(&self) -> bool {
+    self.acting_player() == Some(self.game.player_in_turn)
+  }
+
+  /// Acts as the leader: prepares the action and commits the resulting messages.
+  pub fn play(&mut self, from_player: usize, msg: &PlayerMessage) -> Result<(), InvalidAction> {
+    for message in self.game.prepare(from_player, msg)? {
+      self.game.commit(&message)?;
+    }
+    Ok(())
+  }
+
+  /// Handles a key press. Returns the action of the player, if the key made one.
+  pub fn on_key(&mut self, key: KeyCode) -> Option<PlayerMessage> {
+    let num_rows = self.game.ruleset.scorings.len();
+    let num_columns = self.game.ruleset.columns.len();
+    let num_dice = self.game.ruleset.dice.len();
+
+    match key {
+      KeyCode::Char('q') | KeyCode::Esc => {
+        self.quit = true;
+        None
+      }
+      KeyCode::Up | KeyCode::Char('k') => {
+        self.selected_row = (self.selected_row + num_rows - 1) % num_rows;
+        None
+      }
+      KeyCode::Down | KeyCode::Char('j') => {
+        self.selected_row = (self.selected_row + 1) % num_rows;
+        None
+      }
+      KeyCode::Left | KeyCode::Char('h') => {
+        self.selected_column = (self.selected_column + num_columns - 1) % num_columns;
+        None
+      }
+      KeyCode::Right | KeyCode::Char('l') => {
+        self.selected_column = (self.selected_column + 1) % num_columns;
+        None
+      }
+      KeyCode::Char(' ') => Some(PlayerMessage::Roll),
+      KeyCode::Enter => Some(PlayerMessage::PlaceInColumn(
+        self.selected_column,
+        self.selected_row,
+      )),
+      KeyCode::Char('s') if self.game.state == State::Start => Some(PlayerMessage::StartGame),
+      KeyCode::Char('a') if self.game.ruleset.order == PlacementOrder::Announced => {
+        Some(PlayerMessage::Announce(self.selected_row))
+      }
+      KeyCode::Char(c) => match c.to_digit(10) {
+        Some(die) if die >= 1 && die as usize <= num_dice => {
+          Some(PlayerMessage::ToggleHold(die as usize - 1))
+        }
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  pub fn on_server_message(&mut self, msg: ServerMessage) {
+    match msg {
+      ServerMessage::RoomEntered { code, ruleset } => {
+        self.game = Game::new(ruleset);
+        self.code = Some(code);
+        self.me = None;
+        self.selected_row = 0;
+        self.selected_column = 0;
+        self.status = None;
+        self.chat.clear();
+      }
+      ServerMessage::NoSuchRoom(code) => {
+        self.status = Some(format!("There is no game with the code {}", code))
+      }
+      ServerMessage::RulesetRejected(err) => {
+        self.status = Some(format!("Invalid ruleset: {}", err))
+      }
+      ServerMessage::Joined(player) => self.me = Some(player),
+      ServerMessage::Game(game_msg) => {
+        self.status = None;
+        if let Err(err) = self.game.commit(&game_msg) {
+          self.status = Some(format!("Out of sync with the server: {}", err));
+        }
+      }
+      ServerMessage::Rejected(err) => self.status = Some(err.to_string()),
+      ServerMessage::Chat(chat) => self.chat.push(chat),
+      ServerMessage::ChatRejected(err) => self.status = Some(err.to_string()),
+      // The terminal client does not log in
+      ServerMessage::LoggedIn { .. } | ServerMessage::LoggedOut => {}
+      ServerMessage::LoginRejected(err) => self.status = Some(err.to_string()),
+      // Nor does it follow tournaments, but tables can be joined with their codes
+      ServerMessage::Tournament { .. } => {}
+      ServerMessage::TournamentRejected(err) => self.status = Some(err.to_string()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use jatsi_shared::game::GameMessage;
+  use jatsi_shared::rules::{announced_rules, mini_rules, triple_rules};
+
+  #[test]
+  fn test_keys() {
+    let mut app = App::hot_seat(mini_rules(), &["Henry".into(), "Bobby".into()]).unwrap();
+    let num_rows = app.game.ruleset.scorings.len();
+
+    assert_eq!(app.on_key(KeyCode::Char(' ')), Some(PlayerMessage::Roll));
+    assert_eq!(
+      app.on_key(KeyCode::Char('1')),
+      Some(PlayerMessage::ToggleHold(0))
+    );
+    assert_eq!(
+      app.on_key(KeyCode::Char('5')),
+      Some(PlayerMessage::ToggleHold(4))
+    );
+    assert_eq!(
+      app.on_key(KeyCode::Char('6')),
+      None,
+      "there are only five dice"
+    );
+    assert_eq!(
+      app.on_key(KeyCode::Char('s')),
+      None,
+      "the game is already on"
+    );
+
+    assert_eq!(app.on_key(KeyCode::Up), None);
+    assert_eq!(app.selected_row, num_rows - 1);
+    app.on_key(KeyCode::Down);
+    app.on_key(KeyCode::Char('j'));
+    assert_eq!(
+      app.on_key(KeyCode::Enter),
+      Some(PlayerMessage::PlaceInColumn(0, 1))
+    );
+    assert_eq!(
+      app.on_key(KeyCode::Right),
+      None,
+      "there is only one column to pick"
+    );
+    assert_eq!(app.selected_column, 0);
+
+    assert!(!app.quit);
+    app.on_key(KeyCode::Char('q'));
+    assert!(app.quit);
+  }
+
+  #[test]
+  fn test_columns() {
+    let mut app = App::hot_seat(triple_rules(), &["Henry".into()]).unwrap();
+    app.on_key(KeyCode::Left);
+    assert_eq!(app.selected_column, 2);
+    app.on_key(KeyCode::Char('l'));
+    app.on_key(KeyCode::Right);
+    assert_eq!(
+      app.on_key(KeyCode::Enter),
+      Some(PlayerMessage::PlaceInColumn(1, 0))
+    );
+  }
+
+  #[test]
+  fn test_hot_seat() {
+    let mut app = App::hot_seat(mini_rules(), &["Henry".into(), "Bobby".into()]).unwrap();
+    assert_eq!(app.game.state, State::FirstRoll);
+    assert_eq!(app.acting_player(), Some(0));
+
+    app.play(0, &PlayerMessage::Roll).unwrap();
+    app.play(0, &PlayerMessage::Place(0)).unwrap();
+    assert_eq!(app.acting_player(), Some(1));
+    assert!(app.my_turn());
+  }
+
+  #[test]
+  fn test_announce() {
+    let mut app = App::hot_seat(mini_rules(), &["Henry".into()]).unwrap();
+    assert_eq!(app.on_key(KeyCode::Char('a')), None);
+
+    let mut app = App::hot_seat(announced_rules(), &["Henry".into()]).unwrap();
+    app.on_key(KeyCode::Down);
+    assert_eq!(
+      app.on_key(KeyCode::Char('a')),
+      Some(PlayerMessage::Announce(1))
+    );
+  }
+
+  #[test]
+  fn test_online() {
+    let mut app = App::new(Mode::Online, mini_rules());
+    app.on_server_message(ServerMessage::RoomEntered {
+      code: "ABCDE".into(),
+      ruleset: mini_rules(),
+    });
+    assert_eq!(app.code.as_deref(), Some("ABCDE"));
+    assert!(!app.my_turn());
+
+    app.on_server_message(ServerMessage::Game(GameMessage::PlayerMessage(
+      0,
+      PlayerMessage::JoinGame("Henry".into()),
+    )));
+    app.on_server_message(ServerMessage::Joined(0));
+    assert_eq!(app.game.players.len(), 1);
+    assert!(app.my_turn());
+
+    app.on_server_message(ServerMessage::Rejected(InvalidAction::WrongState));
+    assert!(app.status.is_some());
+  }
+}
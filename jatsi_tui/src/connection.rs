@@ -0,0 +1,70 @@
+use std::io::ErrorKind;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use jatsi_shared::protocol::{ClientMessage, ServerMessage};
+use tungstenite::error::UrlError;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Error, Message};
+
+/// How long reading waits for the server before checking for messages to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A connection to `jatsi_server`, serviced by a thread of its own.
+pub struct Connection {
+  sender: Sender<ClientMessage>,
+  pub receiver: Receiver<ServerMessage>,
+}
+
+impl Connection {
+  pub fn connect(url: &str) -> Result<Self, Box<Error>> {
+    let (mut socket, _) = tungstenite::connect(url)?;
+    // Servers behind TLS are read through the same TCP stream
+    let stream = match socket.get_ref() {
+      MaybeTlsStream::Plain(stream) => stream,
+      MaybeTlsStream::Rustls(stream) => &stream.sock,
+      _ => return Err(Box::new(Error::Url(UrlError::UnsupportedUrlScheme))),
+    };
+    stream
+      .set_read_timeout(Some(POLL_INTERVAL))
+      .map_err(Error::Io)?;
+
+    let (sender, outgoing) = channel::<ClientMessage>();
+    let (incoming, receiver) = channel::<ServerMessage>();
+
+    thread::spawn(move || loop {
+      match socket.read() {
+        Ok(msg) if msg.is_text() => {
+          let parsed = msg
+            .to_text()
+            .ok()
+            .and_then(|text| serde_json::from_str::<ServerMessage>(text).ok());
+          if let Some(server_msg) = parsed {
+            if incoming.send(server_msg).is_err() {
+              break;
+            }
+          }
+        }
+        Ok(_) => {}
+        Err(Error::Io(err))
+          if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+        Err(_) => break,
+      }
+
+      while let Ok(client_msg) = outgoing.try_recv() {
+        let json = serde_json::to_string(&client_msg).expect("messages should serialize");
+        if socket.send(Message::text(json)).is_err() {
+          return;
+        }
+      }
+    });
+
+    Ok(Self { sender, receiver })
+  }
+
+  pub fn send(&self, msg: ClientMessage) {
+    // A closed connection shows up as the receiver disconnecting
+    let _ = self.sender.send(msg);
+  }
+}
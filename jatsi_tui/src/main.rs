@@ -0,0 +1,114 @@
+mod app;
+mod connection;
+mod ui;
+
+use std::{env, fs, io, process, time::Duration};
+
+use jatsi_shared::protocol::ClientMessage;
+use jatsi_shared::rules::{ee_rules, Ruleset};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+
+use crate::app::{App, Mode};
+use crate::connection::Connection;
+
+const DEFAULT_SERVER_URL: &str = "ws://localhost:8088";
+const USAGE: &str = "Usage:
+  jatsi_tui [--rules FILE] --hot-seat NAME...      play on this terminal
+  jatsi_tui [--rules FILE] NAME                    create a game on the server
+  jatsi_tui NAME CODE                              join a game on the server
+
+The server is at ws://localhost:8088 unless JATSI_SERVER_URL says otherwise.";
+
+/// How long to wait for key presses before checking for messages from the server.
+const TICK: Duration = Duration::from_millis(50);
+
+fn exit_with(message: &str) -> ! {
+  eprintln!("{}", message);
+  process::exit(1)
+}
+
+fn main() -> io::Result<()> {
+  let mut args: Vec<String> = env::args().skip(1).collect();
+
+  let ruleset = match args.iter().position(|arg| arg == "--rules") {
+    Some(index) => {
+      let path = args
+        .get(index + 1)
+        .cloned()
+        .unwrap_or_else(|| exit_with(USAGE));
+      args.drain(index..index + 2);
+      let json = fs::read_to_string(&path)?;
+      Ruleset::from_json(&json).unwrap_or_else(|err| exit_with(&format!("{}: {}", path, err)))
+    }
+    None => ee_rules(),
+  };
+
+  let (mut app, connection) = if args.first().map(String::as_str) == Some("--hot-seat") {
+    if args.len() < 2 {
+      exit_with(USAGE);
+    }
+    let app = App::hot_seat(ruleset, &args[1..]).unwrap_or_else(|err| exit_with(&err.to_string()));
+    (app, None)
+  } else {
+    let msg = match args.as_slice() {
+      [name] => ClientMessage::CreateRoom {
+        name: name.clone(),
+        ruleset: ruleset.clone(),
+        team: None,
+      },
+      [name, code] => ClientMessage::JoinRoom {
+        name: name.clone(),
+        code: code.clone(),
+        team: None,
+      },
+      _ => exit_with(USAGE),
+    };
+
+    let url = env::var("JATSI_SERVER_URL").unwrap_or_else(|_| DEFAULT_SERVER_URL.to_string());
+    let connection = Connection::connect(&url)
+      .unwrap_or_else(|err| exit_with(&format!("Could not connect to {}: {}", url, err)));
+    connection.send(msg);
+    (App::new(Mode::Online, ruleset), Some(connection))
+  };
+
+  let mut terminal = ratatui::init();
+  let result = run(&mut terminal, &mut app, connection.as_ref());
+  ratatui::restore();
+  result
+}
+
+fn run(
+  terminal: &mut ratatui::DefaultTerminal,
+  app: &mut App,
+  connection: Option<&Connection>,
+) -> io::Result<()> {
+  while !app.quit {
+    if let Some(connection) = connection {
+      while let Ok(msg) = connection.receiver.try_recv() {
+        app.on_server_message(msg);
+      }
+    }
+
+    terminal.draw(|frame| ui::draw(frame, app))?;
+
+    if !event::poll(TICK)? {
+      continue;
+    }
+    let key = match event::read()? {
+      Event::Key(key) if key.kind == KeyEventKind::Press => key,
+      _ => continue,
+    };
+
+    let (Some(msg), Some(player)) = (app.on_key(key.code), app.acting_player()) else {
+      continue;
+    };
+    match connection {
+      Some(connection) => connection.send(ClientMessage::Player(msg)),
+      None => {
+        app.status = app.play(player, &msg).err().map(|err| err.to_string());
+      }
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,288 @@
+use jatsi_shared::events::events;
+use jatsi_shared::game::State;
+use jatsi_shared::i18n::Language;
+use jatsi_shared::rules::PlacementOrder;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+use crate::app::{App, Mode};
+
+pub fn draw(frame: &mut Frame, app: &App) {
+  let [status_area, main_area, dice_area, help_area] = Layout::vertical([
+    Constraint::Length(1),
+    Constraint::Min(5),
+    Constraint::Length(3),
+    Constraint::Length(1),
+  ])
+  .areas(frame.area());
+  let [score_card_area, log_area] =
+    Layout::horizontal([Constraint::Min(30), Constraint::Length(40)]).areas(main_area);
+
+  frame.render_widget(Paragraph::new(status_line(app)), status_area);
+  draw_score_card(frame, app, score_card_area);
+  if app.mode == Mode::Online {
+    let [log_area, chat_area] =
+      Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(log_area);
+    draw_event_log(frame, app, log_area);
+    draw_chat(frame, app, chat_area);
+  } else {
+    draw_event_log(frame, app, log_area);
+  }
+  draw_dice(frame, app, dice_area);
+  frame.render_widget(Paragraph::new(help_line(app)).dim(), help_area);
+}
+
+fn status_line(app: &App) -> Line<'static> {
+  let game = &app.game;
+  let room = match &app.code {
+    Some(code) => format!("Join code {} · ", code),
+    None => String::new(),
+  };
+
+  let state = match game.state {
+    State::Start => format!(
+      "{} player(s) waiting for the game to start",
+      game.players.len()
+    ),
+    State::End => "The game is over".into(),
+    _ => {
+      let name = &game.players[game.player_in_turn].name;
+      let rolls_left = game.rolls().saturating_sub(game.times_rolled);
+      if app.my_turn() && app.mode == Mode::Online {
+        format!("Your turn, {} roll(s) left", rolls_left)
+      } else {
+        format!("{}'s turn, {} roll(s) left", name, rolls_left)
+      }
+    }
+  };
+
+  let mut spans = vec![Span::raw(format!("{}{}", room, state)).bold()];
+  if let Some(status) = &app.status {
+    spans.push(Span::raw(format!(" · {}", status)).red());
+  }
+  Line::from(spans)
+}
+
+fn draw_score_card(frame: &mut Frame, app: &App, area: Rect) {
+  let game = &app.game;
+  let in_turn = game.player_in_turn;
+  let acting = app.my_turn();
+  let columns = &game.ruleset.columns;
+
+  // A column for each column of each player's score sheet, marked with its multiplier if there are several
+  let header = Row::new(std::iter::once(Cell::from("")).chain(
+    game.players.iter().enumerate().flat_map(|(p, player)| {
+      columns.iter().enumerate().map(move |(column, multiplier)| {
+        let title = match columns.len() {
+          1 => player.name.clone(),
+          _ => format!("{} ×{}", player.name, multiplier),
+        };
+        if acting && p == in_turn && column == app.selected_column && columns.len() > 1 {
+          Cell::from(title).underlined()
+        } else {
+          Cell::from(title)
+        }
+      })
+    }),
+  ))
+  .bold();
+
+  let rows = game
+    .ruleset
+    .scorings
+    .iter()
+    .enumerate()
+    .map(|(i, _)| {
+      // What the player in turn would get by placing the current roll here, if they can
+      let hint = if acting {
+        game
+          .preview_place(app.selected_column, i)
+          .ok()
+          .and_then(|score_sheet| score_sheet[app.selected_column][i])
+      } else {
+        None
+      };
+
+      let cells = game.players.iter().enumerate().flat_map(|(p, player)| {
+        player
+          .score_sheet
+          .iter()
+          .enumerate()
+          .map(
+            move |(column, sheet_column)| match (hint, sheet_column[i]) {
+              (Some(points), _) if p == in_turn && column == app.selected_column => {
+                Cell::from(points.to_string()).dark_gray()
+              }
+              (_, Some(points)) => Cell::from(points.to_string()),
+              _ => Cell::from(""),
+            },
+          )
+      });
+
+      Row::new(
+        std::iter::once(Cell::from(game.ruleset.row_name(i, Language::English))).chain(cells),
+      )
+    });
+
+  // Handicaps below the scoring rows, so the selected row stays a scoring row
+  let handicaps = game
+    .players
+    .iter()
+    .any(|player| !player.handicap.is_none())
+    .then(|| {
+      let cells = game.players.iter().flat_map(|player| {
+        (0..columns.len()).map(move |column| match column {
+          0 => Cell::from(player.handicap.short()),
+          _ => Cell::from(""),
+        })
+      });
+      Row::new(std::iter::once(Cell::from("Handicap")).chain(cells)).italic()
+    });
+  let rows = rows.chain(handicaps);
+
+  let totals = game.players.iter().flat_map(|player| {
+    // The total of a player under their last column, including the starting points of their handicap
+    let total = player.total(&game.ruleset);
+    (0..columns.len()).map(move |column| match column + 1 == columns.len() {
+      true => Cell::from(total.to_string()),
+      false => Cell::from(""),
+    })
+  });
+  let footer = Row::new(std::iter::once(Cell::from("Total")).chain(totals)).bold();
+
+  let widths = std::iter::once(Constraint::Length(16))
+    .chain(
+      game
+        .players
+        .iter()
+        .flat_map(|_| columns.iter().map(|_| Constraint::Length(10))),
+    )
+    .collect::<Vec<Constraint>>();
+
+  let table = Table::new(rows, widths)
+    .header(header)
+    .footer(footer)
+    .block(Block::default().borders(Borders::ALL).title("Score card"))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+  let mut state = TableState::default();
+  if acting && matches!(game.state, State::Reroll | State::Place) {
+    state.select(Some(app.selected_row));
+  }
+  frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_event_log(frame: &mut Frame, app: &App, area: Rect) {
+  let lines = area.height.saturating_sub(2) as usize;
+  let descriptions: Vec<String> = match events(&app.game, Language::English) {
+    Ok(events) => events.iter().map(|event| event.to_string()).collect(),
+    Err(err) => vec![err.to_string()],
+  };
+  let items = descriptions
+    .into_iter()
+    .rev()
+    .take(lines)
+    .rev()
+    .map(ListItem::new)
+    .collect::<Vec<ListItem>>();
+
+  frame.render_widget(
+    List::new(items).block(Block::default().borders(Borders::ALL).title("Events")),
+    area,
+  );
+}
+
+fn draw_chat(frame: &mut Frame, app: &App, area: Rect) {
+  let lines = area.height.saturating_sub(2) as usize;
+  let items = app
+    .chat
+    .iter()
+    .rev()
+    .take(lines)
+    .rev()
+    .map(|msg| ListItem::new(format!("{}: {}", msg.sender, msg.body)))
+    .collect::<Vec<ListItem>>();
+
+  frame.render_widget(
+    List::new(items).block(Block::default().borders(Borders::ALL).title("Chat")),
+    area,
+  );
+}
+
+fn draw_dice(frame: &mut Frame, app: &App, area: Rect) {
+  let game = &app.game;
+  let rolled = matches!(game.state, State::Reroll | State::Place);
+
+  // The dice that count on the picked row are underlined and the reason is told, wilds counting differently on
+  // every row
+  let explanation = rolled
+    .then(|| game.ruleset.explain(app.selected_row, &game.roll, Language::English))
+    .flatten();
+  let counting = explanation
+    .as_ref()
+    .map(|explanation| explanation.dice.clone())
+    .unwrap_or_default();
+
+  let mut spans = Vec::new();
+  for (i, (&value, &kept)) in game.roll.iter().zip(game.keep.iter()).enumerate() {
+    let face = if rolled && game.ruleset.is_wild(value) {
+      "*".into()
+    } else if rolled {
+      value.to_string()
+    } else {
+      "?".into()
+    };
+    let mut die = Span::raw(format!(" {}:[{}] ", i + 1, face));
+    if kept {
+      die = die.yellow().bold();
+    }
+    if counting.contains(&i) {
+      die = die.underlined();
+    }
+    spans.push(die);
+  }
+
+  let mut title = "Dice (held dice in yellow)".to_string();
+  if let Some(explanation) = explanation {
+    let row = game.ruleset.row_name(app.selected_row, Language::English);
+    title = format!("{} · {}: {}", title, row, explanation.reason);
+  }
+  let wilds = match rolled {
+    true => game.ruleset.assigned_wilds(app.selected_row, &game.roll),
+    false => Vec::new(),
+  };
+  if !wilds.is_empty() {
+    let wilds: Vec<String> = wilds.iter().map(|value| value.to_string()).collect();
+    title = format!("{}, wilds * as {}", title, wilds.join(" "));
+  }
+
+  frame.render_widget(
+    Paragraph::new(Line::from(spans)).block(Block::default().borders(Borders::ALL).title(title)),
+    area,
+  );
+}
+
+fn help_line(app: &App) -> String {
+  let ruleset = &app.game.ruleset;
+  match app.game.state {
+    State::Start if app.mode == Mode::Online && app.me == Some(0) => {
+      "s start the game · q quit".into()
+    }
+    State::Start | State::End => "q quit".into(),
+    _ if app.my_turn() => {
+      let mut keys = vec!["space roll", "1–9 hold", "↑/↓ pick a row"];
+      if ruleset.columns.len() > 1 {
+        keys.push("←/→ pick a column");
+      }
+      if ruleset.order == PlacementOrder::Announced {
+        keys.push("a announce");
+      }
+      keys.extend(["enter place", "q quit"]);
+      keys.join(" · ")
+    }
+    _ => "Waiting for the other players · q quit".into(),
+  }
+}
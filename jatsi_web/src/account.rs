@@ -0,0 +1,105 @@
+use dioxus::prelude::*;
+use dioxus_websocket_hooks::use_ws_context;
+use jatsi_shared::protocol::{Account, ClientMessage};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const STORAGE_KEY: &str = "jatsi.login";
+
+/// A login remembered by the browser. The token stands in for the password.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredLogin {
+  pub account: Account,
+  pub token: String,
+}
+
+pub fn stored_login() -> Option<StoredLogin> {
+  storage::load(STORAGE_KEY)
+}
+
+pub fn store_login(login: &StoredLogin) {
+  storage::store(STORAGE_KEY, login);
+}
+
+pub fn forget_login() {
+  storage::remove(STORAGE_KEY);
+}
+
+/// The message logging the connection in with the remembered login, if any. The server replies with `LoggedIn`
+/// or `LoginRejected`, and processes messages in order, so this can be sent right before entering a room.
+pub fn authentication() -> Option<ClientMessage> {
+  stored_login().map(|login| ClientMessage::Authenticate(login.token))
+}
+
+#[derive(Props)]
+pub struct AccountBoxProps<'a> {
+  pub account: &'a UseRef<Option<Account>>,
+}
+
+// Logging in, registering or logging out.
+#[allow(non_snake_case)]
+pub fn AccountBox<'a>(cx: Scope<'a, AccountBoxProps<'a>>) -> Element {
+  let ws = use_ws_context(&cx);
+  let username = use_state(&cx, || String::new());
+  let password = use_state(&cx, || String::new());
+
+  if let Some(account) = &*cx.props.account.read() {
+    let name = account.username.clone();
+    return cx.render(rsx!(
+      p { "Logged in as {name}. Your games count towards your stats." }
+      button {
+        onclick: move |_| {
+          // The token is revoked by logging out on a connection that is logged in with it
+          if let Some(msg) = authentication() {
+            ws.send_json(&msg);
+          }
+          ws.send_json(&ClientMessage::LogOut);
+        },
+        prevent_default: "onclick",
+        "Log out"
+      }
+    ));
+  }
+
+  let can_submit = !username.get().trim().is_empty() && !password.get().is_empty();
+
+  cx.render(rsx!(
+    p { "Log in to keep track of your games across devices, or play as a guest." }
+    input {
+      placeholder: "Username",
+      value: "{username}",
+      oninput: move |evt| username.set(evt.value.clone()),
+    }
+    input {
+      r#type: "password",
+      placeholder: "Password",
+      value: "{password}",
+      oninput: move |evt| password.set(evt.value.clone()),
+    }
+    button {
+      disabled: "{!can_submit}",
+      onclick: move |_| {
+        ws.send_json(&ClientMessage::LogIn {
+          username: username.get().trim().to_string(),
+          password: password.get().clone(),
+        });
+        password.set(String::new());
+      },
+      prevent_default: "onclick",
+      "Log in"
+    }
+    button {
+      disabled: "{!can_submit}",
+      onclick: move |_| {
+        ws.send_json(&ClientMessage::Register {
+          username: username.get().trim().to_string(),
+          password: password.get().clone(),
+        });
+        password.set(String::new());
+      },
+      prevent_default: "onclick",
+      "Create an account"
+    }
+  ))
+}
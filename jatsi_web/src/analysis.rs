@@ -0,0 +1,214 @@
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+use jatsi_shared::analysis::{AnalysisReport, Analyzer, Decision, DecisionKind};
+use jatsi_shared::errors::InvalidAction;
+use jatsi_shared::game::Game;
+use jatsi_shared::i18n::Language;
+use jatsi_shared::rules::Ruleset;
+
+#[derive(Props)]
+pub struct AnalysisProps<'a> {
+  pub game: &'a UseRef<Game>,
+  /// The language of row names and descriptions.
+  pub language: Language,
+}
+
+fn describe_held(keep: &[bool], language: Language) -> String {
+  let held = keep
+    .iter()
+    .enumerate()
+    .filter(|(_, &kept)| kept)
+    .map(|(i, _)| (i + 1).to_string())
+    .collect::<Vec<String>>();
+
+  match (held.is_empty(), language) {
+    (true, Language::English) => "nothing".into(),
+    (true, Language::Finnish) => "ei mitään".into(),
+    (false, Language::English) => format!("dice {}", held.join(", ")),
+    (false, Language::Finnish) => format!("nopat {}", held.join(", ")),
+  }
+}
+
+fn describe_roll(roll: &[u64]) -> String {
+  roll
+    .iter()
+    .map(|value| value.to_string())
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+/// The name of a row, with the column if the score sheet has several.
+fn describe_cell(ruleset: &Ruleset, column: usize, row: usize, language: Language) -> String {
+  let row_name = ruleset.row_name(row, language);
+  match (ruleset.columns.len(), language) {
+    (1, _) => row_name,
+    (_, Language::English) => format!("{} of column {}", row_name, column + 1),
+    (_, Language::Finnish) => format!("{} sarakkeessa {}", row_name, column + 1),
+  }
+}
+
+fn describe(ruleset: &Ruleset, decision: &Decision, language: Language) -> String {
+  match &decision.kind {
+    DecisionKind::Hold {
+      roll,
+      keep,
+      best_keep,
+    } => {
+      let (roll, keep, best_keep) = (
+        describe_roll(roll),
+        describe_held(keep, language),
+        describe_held(best_keep, language),
+      );
+      match language {
+        Language::English => format!(
+          "With {} you held {}, but holding {} was better.",
+          roll, keep, best_keep
+        ),
+        Language::Finnish => format!(
+          "Heitolla {} pidit {}, mutta parempi olisi ollut pitää {}.",
+          roll, keep, best_keep
+        ),
+      }
+    }
+    DecisionKind::Place {
+      roll,
+      column,
+      row,
+      best_keep: Some(best_keep),
+      ..
+    } => {
+      let (roll, cell, best_keep) = (
+        describe_roll(roll),
+        describe_cell(ruleset, *column, *row, language),
+        describe_held(best_keep, language),
+      );
+      match language {
+        Language::English => format!(
+          "With {} you placed on {}, but rolling again holding {} was better.",
+          roll, cell, best_keep
+        ),
+        Language::Finnish => format!(
+          "Heitolla {} sijoitit riville {}, mutta parempi olisi ollut heittää uudelleen pitäen {}.",
+          roll, cell, best_keep
+        ),
+      }
+    }
+    DecisionKind::Place {
+      roll,
+      column,
+      row,
+      best_column,
+      best_row,
+      best_keep: None,
+    } => {
+      let (roll, cell, best_cell) = (
+        describe_roll(roll),
+        describe_cell(ruleset, *column, *row, language),
+        describe_cell(ruleset, *best_column, *best_row, language),
+      );
+      match language {
+        Language::English => format!(
+          "With {} you placed on {}, but {} gave more points.",
+          roll, cell, best_cell
+        ),
+        Language::Finnish => format!(
+          "Heitolla {} sijoitit riville {}, mutta {} olisi antanut enemmän pisteitä.",
+          roll, cell, best_cell
+        ),
+      }
+    }
+  }
+}
+
+// Post-game report of the expected points each player lost by their decisions.
+#[allow(non_snake_case)]
+pub fn Analysis<'a>(cx: Scope<'a, AnalysisProps<'a>>) -> Element {
+  let report = use_ref(&cx, || None::<Result<AnalysisReport, InvalidAction>>);
+  let progress = use_state(&cx, || 0usize);
+
+  // The analysis is expensive, so it is only run once when the game has finished, a turn at a time to let the page
+  // render in between. Rulesets whose turns would take too long to solve are analyzed approximately.
+  use_ref(&cx, || {
+    let game = cx.props.game.read().clone();
+    let report = report.clone();
+    let progress = progress.clone();
+    cx.push_future(async move {
+      let mut analyzer = Analyzer::new(&game);
+      let result = loop {
+        TimeoutFuture::new(0).await;
+        match analyzer.step() {
+          Ok(true) => {
+            let (analyzed, total) = analyzer.progress();
+            progress.set(100 * analyzed / total.max(1));
+          }
+          Ok(false) => break Ok(analyzer.report()),
+          Err(err) => break Err(err),
+        }
+      };
+      report.set(Some(result));
+    });
+  });
+
+  let game = cx.props.game.read();
+  let report = report.read();
+  let report = match &*report {
+    None => {
+      let percent = *progress.get();
+      return cx.render(rsx!(p { "Analyzing the game… {percent}%" }));
+    }
+    Some(Ok(report)) => report,
+    Some(Err(err)) => return cx.render(rsx!(p { "Could not analyze the game: {err}" })),
+  };
+
+  let explanation = match report.approximate {
+    false => {
+      rsx!(p { "Each decision is compared with the best one, counting what the row it uses could still score in a later turn." })
+    }
+    true => {
+      rsx!(p { "These dice have too many outcomes to weigh every hold, so only the rows chosen are compared, by the points they gave right away." })
+    }
+  };
+
+  let players = report.players.iter().map(|player| {
+    let loss = format!("{:.1}", player.loss());
+    let costliest = match player.costliest() {
+      Some((round, decision)) => format!(
+        "Costliest decision, round {}: {} (−{:.1})",
+        round,
+        describe(&game.ruleset, decision, cx.props.language),
+        decision.loss()
+      ),
+      None => "Flawless!".into(),
+    };
+
+    let turns = player.turns.iter().map(|turn| {
+      let round = turn.round;
+      let loss = format!("{:.1}", turn.loss());
+      rsx!(tr {
+        th { "Round {round}" }
+        td { "{loss}" }
+      })
+    });
+
+    rsx!(div {
+      h3 { [player.name.clone()] }
+      p { "Expected points lost: {loss}" }
+      p { "{costliest}" }
+      table {
+        tbody {
+          turns
+        }
+      }
+    })
+  });
+
+  cx.render(rsx!(
+    div {
+      class: "analysis",
+
+      h2 { "How well did you play?" }
+      explanation
+      players
+    }
+  ))
+}
@@ -1,66 +1,90 @@
-use crate::die::Die;
-use crate::score_card::ScoreCard;
-use dioxus::events::MouseEvent;
+use crate::hot_seat::{load_saved_game, HotSeat};
+use crate::language::{load_language, LanguagePicker};
+use crate::leaderboard::Leaderboards;
+use crate::online::Online;
+use crate::profile::Profile;
+use crate::replay::Replay;
+use crate::ruleset_editor::RulesetEditor;
 use dioxus::prelude::*;
-use dioxus_websocket_hooks::use_ws_context_provider;
-use jatsi_shared::dice::roll_dice_keeping;
-use jatsi_shared::game::Game;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+  Choose,
+  Online,
+  HotSeat,
+  Replay,
+  Profile,
+  Leaderboards,
+  RulesetEditor,
+}
 
 #[allow(non_snake_case)]
 pub fn App(cx: Scope) -> Element {
-  let game = use_ref(&cx, || Game::dummy());
-  let num_dice = game.read().ruleset.dice.len();
-  let keep = use_ref(&cx, || vec![false; num_dice]);
-
-  use_ws_context_provider(&cx, "ws://localhost:8088", move |msg| {
-    println!("{:?}", msg);
+  // A hot-seat game in progress is resumed right away
+  let mode = use_state(&cx, || {
+    if load_saved_game().is_some() {
+      Mode::HotSeat
+    } else {
+      Mode::Choose
+    }
   });
 
-  let dice = game
-    .read()
-    .roll
-    .iter()
-    .zip(keep.read().iter())
-    .enumerate()
-    .map(|(ind, (&value, &kept))| {
-      rsx!(Die {
-        value: value,
-        keep: kept,
-        onclick: move |_: MouseEvent| {
-          let mut keep = keep.write();
-          keep[ind] = !keep[ind];
-        },
-      })
-    })
-    .collect::<Vec<LazyNodes>>();
-
-  let toss = move |_| {
-    let mut game = game.write();
-    let keep = keep.read();
-    game.roll = roll_dice_keeping(&game.ruleset.dice, &game.roll, &keep);
-  };
-
-  rsx!(cx,
-    div {
-      ScoreCard {
-        game: &game
-      }
-
-      div {
-        class: "dice",
-
-        dice
-      }
+  let language = use_state(&cx, load_language);
 
+  match *mode.get() {
+    Mode::Choose => cx.render(rsx!(
       div {
         class: "container",
 
+        h2 { "How do you want to play?" }
+        button {
+          onclick: move |_| mode.set(Mode::Online),
+          prevent_default: "onclick",
+          "Online with friends"
+        }
         button {
-          onclick: toss,
+          onclick: move |_| mode.set(Mode::HotSeat),
           prevent_default: "onclick",
-          "Toss a die for your witcher!"
+          "On this device, passing it around"
+        }
+        button {
+          onclick: move |_| mode.set(Mode::Replay),
+          prevent_default: "onclick",
+          "Watch a replay"
+        }
+        button {
+          onclick: move |_| mode.set(Mode::Profile),
+          prevent_default: "onclick",
+          "Player profiles"
+        }
+        button {
+          onclick: move |_| mode.set(Mode::Leaderboards),
+          prevent_default: "onclick",
+          "Leaderboards"
+        }
+        button {
+          onclick: move |_| mode.set(Mode::RulesetEditor),
+          prevent_default: "onclick",
+          "Make a ruleset"
+        }
+        p {
+          LanguagePicker { language: language }
         }
       }
-    }
-  )
+    )),
+    Mode::Online => cx.render(rsx!(Online {
+      language: *language.get()
+    })),
+    Mode::HotSeat => cx.render(rsx!(HotSeat {
+      language: *language.get()
+    })),
+    Mode::Replay => cx.render(rsx!(Replay {
+      language: *language.get()
+    })),
+    Mode::Profile => cx.render(rsx!(Profile {})),
+    Mode::Leaderboards => cx.render(rsx!(Leaderboards {})),
+    Mode::RulesetEditor => cx.render(rsx!(RulesetEditor {
+      language: *language.get()
+    })),
+  }
 }
@@ -0,0 +1,90 @@
+use dioxus::prelude::*;
+use dioxus_websocket_hooks::use_ws_context;
+use jatsi_shared::chat::{validate_chat, ChatMessage, Reaction, MAX_CHAT_LENGTH};
+use jatsi_shared::protocol::ClientMessage;
+
+/// Local time of day of a timestamp, like "18:05".
+fn time_of_day(timestamp: u64) -> String {
+  let date = js_sys::Date::new(&(timestamp as f64).into());
+  format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+}
+
+#[derive(Props)]
+pub struct ChatLogProps<'a> {
+  pub messages: &'a [ChatMessage],
+}
+
+// Chat messages and reactions, oldest first.
+#[allow(non_snake_case)]
+pub fn ChatLog<'a>(cx: Scope<'a, ChatLogProps<'a>>) -> Element {
+  let messages = cx.props.messages.iter().map(|msg| {
+    let time = time_of_day(msg.timestamp);
+    let sender = &msg.sender;
+    let body = msg.body.to_string();
+    rsx!(li {
+      span { class: "time", "{time} " }
+      strong { "{sender}: " }
+      "{body}"
+    })
+  });
+
+  cx.render(rsx!(
+    div {
+      class: "chat-log",
+
+      ol { messages }
+    }
+  ))
+}
+
+#[derive(Props)]
+pub struct ChatBoxProps<'a> {
+  pub chat: &'a UseRef<Vec<ChatMessage>>,
+}
+
+// Chatting with the others in the room.
+#[allow(non_snake_case)]
+pub fn ChatBox<'a>(cx: Scope<'a, ChatBoxProps<'a>>) -> Element {
+  let ws = use_ws_context(&cx);
+  let text = use_state(&cx, || String::new());
+  let chat = cx.props.chat.read();
+
+  let can_send = validate_chat(text.get()).is_ok();
+
+  let reactions = Reaction::all().into_iter().map(|reaction| {
+    let label = reaction.to_string();
+    rsx!(button {
+      class: "reaction",
+      onclick: move |_| ws.send_json(&ClientMessage::React(reaction)),
+      prevent_default: "onclick",
+      "{label}"
+    })
+  });
+
+  cx.render(rsx!(
+    div {
+      class: "container chat",
+
+      ChatLog { messages: &chat }
+      form {
+        onsubmit: move |_| if can_send {
+          ws.send_json(&ClientMessage::Chat(text.get().clone()));
+          text.set(String::new());
+        },
+        prevent_default: "onsubmit",
+
+        input {
+          placeholder: "Say something",
+          maxlength: "{MAX_CHAT_LENGTH}",
+          value: "{text}",
+          oninput: move |evt| text.set(evt.value.clone()),
+        }
+      }
+      div {
+        class: "reactions",
+
+        reactions
+      }
+    }
+  ))
+}
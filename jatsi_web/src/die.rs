@@ -1,9 +1,17 @@
 use dioxus::{events::MouseEvent, prelude::*};
+use jatsi_shared::dice;
 
 #[derive(Props)]
 pub struct DieProps<'a> {
+  pub die: dice::Die,
   pub value: u64,
+  /// Whether the face showing is wild, in which case it is drawn as a star.
+  #[props(default)]
+  pub wild: bool,
   pub keep: bool,
+  /// Whether the die counts on the row the pointer is on.
+  #[props(default)]
+  pub highlight: bool,
   pub onclick: EventHandler<'a, MouseEvent>,
   // pub onclick: Callback<MouseEvent>,
 }
@@ -21,36 +29,122 @@ const OFFSET: i64 = 600;
 const DOT_RADIUS: u64 = 200;
 const HELD_COLOR: &str = "#aaa";
 const UNHELD_COLOR: &str = "#ddd";
+const INK_COLOR: &str = "#333";
 
-// A six-sided die (D6) with dots.
+// Outlines of the dice that are not drawn as squares, in the same -1000..1000 coordinates as the D6.
+const D4_OUTLINE: &str = "0,-950 950,700 -950,700";
+const D8_OUTLINE: &str = "0,-950 823,-475 823,475 0,950 -823,475 -823,-475";
+// The face of a D8 facing the viewer
+const D8_FACE: &str = "0,-950 823,475 -823,475";
+const D10_OUTLINE: &str = "0,-950 900,-150 0,950 -900,-150";
+
+// A die of a ruleset. An ordinary D6 shows dots, other dice show their value as a number. Dice with custom faces are
+// drawn as squares.
 #[allow(non_snake_case)]
 pub fn Die<'a>(cx: Scope<'a, DieProps<'a>>) -> Element {
   let DieProps {
+    die,
     value,
+    wild,
     keep,
+    highlight,
     onclick,
   } = cx.props;
+  let sides = die.ordinary_sides().unwrap_or(0);
 
-  let active_dots = &DOTS_FOR_VALUE[(value - 1) as usize];
   let fill = if *keep { HELD_COLOR } else { UNHELD_COLOR };
-  let dots = DOTS
-    .iter()
-    .zip(active_dots.iter())
-    .filter(|(_, &active)| active)
-    .map(|(&(x, y), _)| {
-      let cx = x * OFFSET;
-      let cy = y * OFFSET;
-      rsx!(circle {
-        cx: "{cx}",
-        cy: "{cy}",
-        r: "{DOT_RADIUS}",
-        fill: "#333"
+
+  let shape = match sides {
+    4 => rsx!(polygon {
+      points: "{D4_OUTLINE}",
+      fill: "{fill}",
+    }),
+    8 => rsx!(g {
+      polygon {
+        points: "{D8_OUTLINE}",
+        fill: "{fill}",
+      }
+      polygon {
+        points: "{D8_FACE}",
+        fill: "none",
+        stroke: "{INK_COLOR}",
+        stroke_width: "30",
+      }
+    }),
+    10 => rsx!(polygon {
+      points: "{D10_OUTLINE}",
+      fill: "{fill}",
+    }),
+    _ => rsx!(rect {
+      x: "-1000",
+      y: "-1000",
+      width: "2000",
+      height: "2000",
+      rx: "{DOT_RADIUS}",
+      fill: "{fill}",
+    }),
+  };
+
+  let face = match (sides, value) {
+    _ if *wild => rsx!(text {
+      x: "0",
+      y: "0",
+      text_anchor: "middle",
+      dominant_baseline: "central",
+      font_size: "1000",
+      fill: "{INK_COLOR}",
+      "★"
+    }),
+    (6, 1..=6) => {
+      let active_dots = &DOTS_FOR_VALUE[(value - 1) as usize];
+      let dots = DOTS
+        .iter()
+        .zip(active_dots.iter())
+        .filter(|(_, &active)| active)
+        .map(|(&(x, y), _)| {
+          let cx = x * OFFSET;
+          let cy = y * OFFSET;
+          rsx!(circle {
+            cx: "{cx}",
+            cy: "{cy}",
+            r: "{DOT_RADIUS}",
+            fill: "{INK_COLOR}"
+          })
+        });
+      rsx!(g { dots })
+    }
+    _ => {
+      // Triangles have their visual center lower than their bounding box
+      let y = if sides == 4 { 250 } else { 0 };
+      let font_size = if *value >= 10 { 600 } else { 800 };
+      rsx!(text {
+        x: "0",
+        y: "{y}",
+        text_anchor: "middle",
+        dominant_baseline: "central",
+        font_size: "{font_size}",
+        font_weight: "bold",
+        fill: "{INK_COLOR}",
+        "{value}"
       })
-    });
+    }
+  };
 
+  let name = match die.ordinary_sides() {
+    Some(sides) => format!("D{}", sides),
+    None => {
+      let faces: Vec<String> = die.faces().iter().map(|face| face.to_string()).collect();
+      format!("Die with faces {}", faces.join(", "))
+    }
+  };
+  let value_text = match wild {
+    true => "wild".to_string(),
+    false => format!("value {}", value),
+  };
   let sr_text = format!(
-    "Die, value {}, {}",
-    value,
+    "{}, {}, {}",
+    name,
+    value_text,
     if *keep {
       "held. Click to release."
     } else {
@@ -58,9 +152,11 @@ pub fn Die<'a>(cx: Scope<'a, DieProps<'a>>) -> Element {
     }
   );
 
+  let highlighted = if *highlight { "highlighted" } else { "" };
+
   rsx!(cx,
     button {
-      class: "die",
+      class: "die {highlighted}",
       onclick: |e| onclick.call(e),
       prevent_default: "onclick",
       title: "{sr_text}",
@@ -68,16 +164,8 @@ pub fn Die<'a>(cx: Scope<'a, DieProps<'a>>) -> Element {
       svg {
         view_box: "-1000 -1000 2000 2000",
 
-        rect {
-          x: "-1000",
-          y: "-1000",
-          width: "2000",
-          height: "2000",
-          rx: "{DOT_RADIUS}",
-          fill: "{fill}",
-        }
-
-        dots
+        shape
+        face
       }
     }
   )
@@ -0,0 +1,34 @@
+use dioxus::prelude::*;
+use jatsi_shared::events::events;
+use jatsi_shared::game::Game;
+use jatsi_shared::i18n::{Language, Localize};
+
+#[derive(Props)]
+pub struct EventLogProps<'a> {
+  pub game: &'a UseRef<Game>,
+  /// The language of row names and explanations.
+  pub language: Language,
+}
+
+// What has happened in the game so far, newest at the bottom.
+#[allow(non_snake_case)]
+pub fn EventLog<'a>(cx: Scope<'a, EventLogProps<'a>>) -> Element {
+  let events = match events(&cx.props.game.read(), cx.props.language) {
+    Ok(events) => events,
+    Err(err) => return rsx!(cx, div { class: "container error", "{err}" }),
+  };
+
+  // The list is laid out in reverse so that it stays scrolled to the newest event
+  let items = events.into_iter().rev().map(|event| {
+    let text = event.localize(cx.props.language);
+    rsx!(li { "{text}" })
+  });
+
+  cx.render(rsx!(
+    div {
+      class: "container event-log",
+
+      ol { items }
+    }
+  ))
+}
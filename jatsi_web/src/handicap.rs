@@ -0,0 +1,72 @@
+use dioxus::prelude::*;
+use jatsi_shared::handicap::{Handicap, MAX_BONUS_REDUCTION, MAX_EXTRA_ROLLS, MAX_STARTING_POINTS};
+
+#[derive(Props)]
+pub struct HandicapFormProps<'a> {
+  pub handicap: Handicap,
+  /// Called with the whole handicap whenever a part of it changes.
+  pub onchange: EventHandler<'a, Handicap>,
+}
+
+// The parts of the handicap of one player.
+#[allow(non_snake_case)]
+pub fn HandicapForm<'a>(cx: Scope<'a, HandicapFormProps<'a>>) -> Element {
+  let handicap = &cx.props.handicap;
+  let onchange = &cx.props.onchange;
+  // Browsers let any number be typed despite the `max` attributes
+  let parse = |value: &str, max: u64| {
+    let value = value.trim();
+    match value.parse::<u64>() {
+      Ok(number) => number.min(max),
+      // Too many digits to be a number
+      Err(_) if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) => max,
+      Err(_) => 0,
+    }
+  };
+
+  cx.render(rsx!(
+    span {
+      class: "handicap",
+
+      label {
+        "Starting points "
+        input {
+          r#type: "number",
+          min: "0",
+          max: "{MAX_STARTING_POINTS}",
+          value: "{handicap.starting_points}",
+          oninput: move |evt| onchange.call(Handicap {
+            starting_points: parse(&evt.value, MAX_STARTING_POINTS),
+            ..handicap.clone()
+          }),
+        }
+      }
+      label {
+        "Extra rolls "
+        input {
+          r#type: "number",
+          min: "0",
+          max: "{MAX_EXTRA_ROLLS}",
+          value: "{handicap.extra_rolls}",
+          oninput: move |evt| onchange.call(Handicap {
+            extra_rolls: parse(&evt.value, MAX_EXTRA_ROLLS),
+            ..handicap.clone()
+          }),
+        }
+      }
+      label {
+        "Bonus sooner by "
+        input {
+          r#type: "number",
+          min: "0",
+          max: "{MAX_BONUS_REDUCTION}",
+          value: "{handicap.bonus_reduction}",
+          oninput: move |evt| onchange.call(Handicap {
+            bonus_reduction: parse(&evt.value, MAX_BONUS_REDUCTION),
+            ..handicap.clone()
+          }),
+        }
+      }
+    }
+  ))
+}
@@ -0,0 +1,238 @@
+use dioxus::prelude::*;
+use jatsi_shared::errors::InvalidAction;
+use jatsi_shared::game::{Game, PlayerMessage, SavedGame, State};
+use jatsi_shared::handicap::Handicap;
+use jatsi_shared::i18n::{Language, Localize};
+use jatsi_shared::rules::{ee_rules, Ruleset};
+
+use crate::handicap::HandicapForm;
+use crate::ruleset_picker::{picked_ruleset, RulesetPicker};
+use crate::storage;
+use crate::table::Table;
+
+const STORAGE_KEY: &str = "jatsi.hot_seat";
+
+/// The hot-seat game in progress, if the page was closed in the middle of one.
+pub fn load_saved_game() -> Option<Game> {
+  storage::load::<SavedGame>(STORAGE_KEY)?.load().ok()
+}
+
+fn save_game(game: &Game) {
+  storage::store(STORAGE_KEY, &game.save());
+}
+
+fn clear_saved_game() {
+  storage::remove(STORAGE_KEY);
+}
+
+/// Acts as the leader: prepares the action and commits the resulting messages.
+fn play(game: &mut Game, from_player: usize, msg: &PlayerMessage) -> Result<(), InvalidAction> {
+  for message in game.prepare(from_player, msg)? {
+    game.commit(&message)?;
+  }
+  Ok(())
+}
+
+#[derive(Props, PartialEq)]
+pub struct HotSeatProps {
+  /// The language of row names and error messages.
+  pub language: Language,
+}
+
+/// A new game of `names` with `handicaps`, started.
+fn new_game(
+  ruleset: Ruleset,
+  names: &[String],
+  handicaps: &[Handicap],
+) -> Result<Game, InvalidAction> {
+  let mut game = Game::new(ruleset);
+  for (player, player_name) in names.iter().enumerate() {
+    play(
+      &mut game,
+      player,
+      &PlayerMessage::JoinGame(player_name.clone()),
+    )?;
+  }
+  for (player, handicap) in handicaps.iter().enumerate() {
+    if !handicap.is_none() {
+      play(
+        &mut game,
+        0,
+        &PlayerMessage::SetHandicap(player, handicap.clone()),
+      )?;
+    }
+  }
+  play(&mut game, 0, &PlayerMessage::StartGame)?;
+  Ok(game)
+}
+
+// Playing on a single device passed around between the players, without a server.
+#[allow(non_snake_case)]
+pub fn HotSeat(cx: Scope<HotSeatProps>) -> Element {
+  let language = cx.props.language;
+  let saved_game = use_ref(&cx, || load_saved_game());
+  let started = use_state(&cx, || saved_game.read().is_some());
+  let game = use_ref(&cx, || {
+    saved_game
+      .read()
+      .clone()
+      .unwrap_or_else(|| Game::new(ee_rules()))
+  });
+  let names = use_ref(&cx, || Vec::<String>::new());
+  let handicaps = use_ref(&cx, || Vec::<Handicap>::new());
+  let name = use_state(&cx, || String::new());
+  let choice = use_state(&cx, || "0".to_string());
+  let ruleset_file = use_state(&cx, || String::new());
+  // The player who has taken the device. Others should look away.
+  let ready = use_state(&cx, || None::<usize>);
+  let error = use_state(&cx, || None::<String>);
+
+  let error_box = error.get().clone().map(|error| {
+    rsx!(div {
+      class: "container error",
+      "{error}"
+    })
+  });
+
+  if !*started.get() {
+    let ruleset = picked_ruleset(choice.get(), ruleset_file.get());
+    let can_start = !names.read().is_empty() && ruleset.is_ok();
+
+    let players = names.read().iter().enumerate().map(|(i, player_name)| {
+      rsx!(li {
+        "{player_name} "
+        a {
+          href: "#",
+          onclick: move |_| {
+            names.write().remove(i);
+            handicaps.write().remove(i);
+          },
+          prevent_default: "onclick",
+          "remove"
+        }
+        " "
+        HandicapForm {
+          handicap: handicaps.read()[i].clone(),
+          onchange: move |handicap| handicaps.write()[i] = handicap,
+        }
+      })
+    });
+
+    return cx.render(rsx!(
+      div {
+        class: "container",
+
+        h2 { "Players" }
+        ul { players }
+        input {
+          placeholder: "Player name",
+          value: "{name}",
+          oninput: move |evt| name.set(evt.value.clone()),
+        }
+        button {
+          disabled: "{name.get().trim().is_empty()}",
+          onclick: move |_| {
+            let player_name = name.get().trim().to_string();
+            if names.read().contains(&player_name) {
+              error.set(Some(InvalidAction::NameTaken.localize(language)));
+              return;
+            }
+            names.write().push(player_name);
+            handicaps.write().push(Handicap::default());
+            name.set(String::new());
+            error.set(None);
+          },
+          prevent_default: "onclick",
+          "Add player"
+        }
+
+        h2 { "Rules" }
+        RulesetPicker {
+          choice: choice,
+          ruleset_file: ruleset_file,
+        }
+        button {
+          disabled: "{!can_start}",
+          onclick: move |_| if let Ok(ruleset) = &ruleset {
+            match new_game(ruleset.clone(), &names.read(), &handicaps.read()) {
+              Ok(new_game) => {
+                save_game(&new_game);
+                game.set(new_game);
+                ready.set(None);
+                error.set(None);
+                started.set(true);
+              }
+              Err(err) => error.set(Some(err.localize(language))),
+            }
+          },
+          prevent_default: "onclick",
+          "Start the game"
+        }
+      }
+      error_box
+    ));
+  }
+
+  let in_turn = game.read().player_in_turn;
+  let in_progress = matches!(
+    game.read().state,
+    State::FirstRoll | State::Reroll | State::Place
+  );
+
+  if in_progress && *ready.get() != Some(in_turn) {
+    let player_name = game.read().players[in_turn].name.clone();
+    return cx.render(rsx!(
+      div {
+        class: "container curtain",
+
+        h2 { "Pass the device to {player_name}" }
+        button {
+          onclick: move |_| ready.set(Some(in_turn)),
+          prevent_default: "onclick",
+          "I'm {player_name}, let's go!"
+        }
+      }
+    ));
+  }
+
+  let new_game = if game.read().state == State::End {
+    Some(rsx!(div {
+      class: "container",
+
+      button {
+        onclick: move |_| {
+          clear_saved_game();
+          started.set(false);
+        },
+        prevent_default: "onclick",
+        "New game"
+      }
+    }))
+  } else {
+    None
+  };
+
+  cx.render(rsx!(
+    div {
+      Table {
+        game: game,
+        // Whoever holds the device plays for the player in turn
+        me: Some(in_turn),
+        language: language,
+        onaction: move |msg| {
+          let mut game = game.write();
+          match play(&mut game, in_turn, &msg) {
+            Ok(()) => {
+              save_game(&game);
+              error.set(None);
+            }
+            Err(err) => error.set(Some(err.localize(language))),
+          }
+        },
+      }
+
+      error_box
+      new_game
+    }
+  ))
+}
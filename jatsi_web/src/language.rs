@@ -0,0 +1,47 @@
+use dioxus::prelude::*;
+use jatsi_shared::i18n::Language;
+
+use crate::storage;
+
+const STORAGE_KEY: &str = "jatsi.language";
+
+/// The language picked on this device, English until one is.
+pub fn load_language() -> Language {
+  storage::load(STORAGE_KEY).unwrap_or_default()
+}
+
+#[derive(Props)]
+pub struct LanguagePickerProps<'a> {
+  pub language: &'a UseState<Language>,
+}
+
+// Choosing the language of the row names and error messages. The choice is remembered on this device.
+#[allow(non_snake_case)]
+pub fn LanguagePicker<'a>(cx: Scope<'a, LanguagePickerProps<'a>>) -> Element {
+  let language = cx.props.language;
+
+  let options = Language::ALL.iter().enumerate().map(|(index, option)| {
+    rsx!(option {
+      value: "{index}",
+      [option.name()]
+    })
+  });
+  let chosen = Language::ALL
+    .iter()
+    .position(|option| option == language.get())
+    .unwrap_or(0);
+
+  cx.render(rsx!(
+    label {
+      "Language "
+      select {
+        value: "{chosen}",
+        onchange: move |evt| if let Some(&chosen) = evt.value.parse::<usize>().ok().and_then(|index| Language::ALL.get(index)) {
+          storage::store(STORAGE_KEY, &chosen);
+          language.set(chosen);
+        },
+        options
+      }
+    }
+  ))
+}
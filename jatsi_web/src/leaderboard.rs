@@ -0,0 +1,100 @@
+use dioxus::prelude::*;
+use gloo_net::http::Request;
+use jatsi_shared::ratings::Leaderboard;
+
+use crate::online::api_url;
+
+async fn fetch_leaderboards() -> Result<Vec<Leaderboard>, String> {
+  let url = format!("{}/leaderboards", api_url());
+  let response = Request::get(&url)
+    .send()
+    .await
+    .map_err(|err| err.to_string())?;
+
+  match response.status() {
+    200 => response.json().await.map_err(|err| err.to_string()),
+    status => Err(format!("The server responded with {}", status)),
+  }
+}
+
+// Ratings of the players with accounts, per ruleset. Only games between at least two of them are rated.
+#[allow(non_snake_case)]
+pub fn Leaderboards(cx: Scope) -> Element {
+  let leaderboards = use_ref(&cx, || None::<Result<Vec<Leaderboard>, String>>);
+  let choice = use_state(&cx, || 0usize);
+
+  let refresh = {
+    let leaderboards = leaderboards.clone();
+    move |_| {
+      let leaderboards = leaderboards.clone();
+      cx.push_future(async move {
+        leaderboards.set(Some(fetch_leaderboards().await));
+      });
+    }
+  };
+
+  let content = match &*leaderboards.read() {
+    None => None,
+    Some(Err(err)) => Some(rsx!(p { class: "error", "{err}" })),
+    Some(Ok(leaderboards)) if leaderboards.is_empty() => {
+      Some(rsx!(p { "No rated games have been played yet." }))
+    }
+    Some(Ok(leaderboards)) => {
+      let options = leaderboards.iter().enumerate().map(|(index, leaderboard)| {
+        rsx!(option {
+          value: "{index}",
+          [leaderboard.ruleset_name.clone()]
+        })
+      });
+      let leaderboard = &leaderboards[(*choice.get()).min(leaderboards.len() - 1)];
+      let entries = leaderboard
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+          let rank = index + 1;
+          let rating = format!("{:.0}", entry.rating);
+          rsx!(tr {
+            td { "{rank}" }
+            th { [entry.username.clone()] }
+            td { "{rating}" }
+            td { [entry.games.to_string()] }
+          })
+        });
+
+      Some(rsx!(
+        select {
+          value: "{choice}",
+          onchange: move |evt| choice.set(evt.value.parse().unwrap_or(0)),
+          options
+        }
+        table {
+          thead {
+            tr {
+              th { "#" }
+              th { "Player" }
+              th { "Rating" }
+              th { "Games" }
+            }
+          }
+          tbody { entries }
+        }
+      ))
+    }
+  };
+
+  cx.render(rsx!(
+    div {
+      class: "container leaderboards",
+
+      h2 { "Leaderboards" }
+      button {
+        onclick: refresh,
+        prevent_default: "onclick",
+        "Show ratings"
+      }
+
+      content
+    }
+  ))
+}
@@ -0,0 +1,242 @@
+use dioxus::prelude::*;
+use dioxus_websocket_hooks::use_ws_context;
+use jatsi_shared::game::{Game, PlayerMessage};
+use jatsi_shared::protocol::{Account, ClientMessage};
+
+use crate::account::{authentication, AccountBox};
+use crate::handicap::HandicapForm;
+use crate::ruleset_picker::{picked_ruleset, RulesetPicker};
+use crate::tournament::{default_settings, SettingsForm};
+
+const ROOM_PARAM: &str = "room=";
+
+/// The join code in the address of the page, if the player arrived through a join link.
+fn room_code_from_url() -> Option<String> {
+  let search = web_sys::window()?.location().search().ok()?;
+  search
+    .trim_start_matches('?')
+    .split('&')
+    .find_map(|param| param.strip_prefix(ROOM_PARAM))
+    .map(|code| code.to_uppercase())
+}
+
+fn join_link(code: &str) -> String {
+  let location = web_sys::window().map(|window| window.location());
+  let origin = location
+    .as_ref()
+    .and_then(|location| location.origin().ok())
+    .unwrap_or_default();
+  let pathname = location
+    .as_ref()
+    .and_then(|location| location.pathname().ok())
+    .unwrap_or_default();
+  format!("{}{}?{}{}", origin, pathname, ROOM_PARAM, code)
+}
+
+#[derive(Props)]
+pub struct SetupProps<'a> {
+  pub account: &'a UseRef<Option<Account>>,
+  /// Kept by the parent, as tournament tables are joined with it later.
+  pub name: &'a UseState<String>,
+}
+
+// Entering a name and either creating a new game or tournament or joining an existing one.
+#[allow(non_snake_case)]
+pub fn Setup<'a>(cx: Scope<'a, SetupProps<'a>>) -> Element {
+  let ws = use_ws_context(&cx);
+  let account = cx.props.account;
+  let name = cx.props.name;
+  let code = use_state(&cx, || room_code_from_url().unwrap_or_default());
+  let team = use_state(&cx, || String::new());
+  let choice = use_state(&cx, || "0".to_string());
+  let ruleset_file = use_state(&cx, || String::new());
+  let settings = use_ref(&cx, default_settings);
+
+  let ruleset = picked_ruleset(choice.get(), ruleset_file.get());
+
+  // Logged in players play under their username
+  let logged_in = account.read().is_some();
+  let has_name = logged_in || !name.get().trim().is_empty();
+  let can_create = has_name && ruleset.is_ok();
+  let can_join = has_name && !code.get().trim().is_empty();
+  let can_organize = can_create && settings.read().validate().is_ok();
+
+  // Players naming the same team share its score sheet
+  let team_name = move || Some(team.get().trim().to_string()).filter(|team| !team.is_empty());
+
+  let enter = move |msg: ClientMessage| {
+    if let Some(auth) = authentication() {
+      ws.send_json(&auth);
+    }
+    ws.send_json(&msg);
+  };
+
+  let name_input = if logged_in {
+    None
+  } else {
+    Some(rsx!(
+      h2 { "Your name" }
+      input {
+        placeholder: "Your name",
+        value: "{name}",
+        oninput: move |evt| name.set(evt.value.clone()),
+      }
+    ))
+  };
+
+  cx.render(rsx!(
+    div {
+      class: "container",
+
+      h2 { "Account" }
+      AccountBox { account: account }
+
+      name_input
+
+      h2 { "Team" }
+      p { "To play in a team, name it. Everyone joining the same team takes turns on its score sheet." }
+      input {
+        placeholder: "Play alone",
+        value: "{team}",
+        oninput: move |evt| team.set(evt.value.clone()),
+      }
+
+      h2 { "Join a game" }
+      input {
+        placeholder: "Join code",
+        value: "{code}",
+        oninput: move |evt| code.set(evt.value.clone()),
+      }
+      button {
+        disabled: "{!can_join}",
+        onclick: move |_| enter(ClientMessage::JoinRoom {
+          name: name.get().trim().to_string(),
+          code: code.get().clone(),
+          team: team_name(),
+        }),
+        prevent_default: "onclick",
+        "Join"
+      }
+
+      h2 { "Create a game" }
+      RulesetPicker {
+        choice: choice,
+        ruleset_file: ruleset_file,
+      }
+      button {
+        disabled: "{!can_create}",
+        onclick: move |_| if let Ok(ruleset) = &ruleset {
+          enter(ClientMessage::CreateRoom {
+            name: name.get().trim().to_string(),
+            ruleset: ruleset.clone(),
+            team: team_name(),
+          })
+        },
+        prevent_default: "onclick",
+        "Create"
+      }
+
+      h2 { "Tournaments" }
+      p { "Join a tournament with its code, or organize one with the ruleset chosen above." }
+      button {
+        disabled: "{!can_join}",
+        onclick: move |_| enter(ClientMessage::JoinTournament {
+          name: name.get().trim().to_string(),
+          code: code.get().clone(),
+        }),
+        prevent_default: "onclick",
+        "Join the tournament"
+      }
+      SettingsForm { settings: settings }
+      button {
+        disabled: "{!can_organize}",
+        onclick: move |_| if let Ok(ruleset) = &ruleset {
+          enter(ClientMessage::CreateTournament {
+            name: name.get().trim().to_string(),
+            ruleset: ruleset.clone(),
+            settings: settings.read().clone(),
+          })
+        },
+        prevent_default: "onclick",
+        "Organize a tournament"
+      }
+    }
+  ))
+}
+
+#[derive(Props)]
+pub struct WaitingRoomProps<'a> {
+  pub game: &'a UseRef<Game>,
+  pub me: Option<usize>,
+  pub code: String,
+}
+
+// Waiting for players to join before the game starts.
+#[allow(non_snake_case)]
+pub fn WaitingRoom<'a>(cx: Scope<'a, WaitingRoomProps<'a>>) -> Element {
+  let ws = use_ws_context(&cx);
+  let game = cx.props.game.read();
+  let code = &cx.props.code;
+  let link = join_link(code);
+
+  let is_host = cx.props.me == Some(0);
+
+  let players = game.players.iter().enumerate().map(|(i, player)| {
+    let host = if i == 0 { " (host)" } else { "" };
+    let you = if Some(i) == cx.props.me { " (you)" } else { "" };
+    let members = if player.is_team() {
+      format!(": {}", player.members.join(", "))
+    } else {
+      String::new()
+    };
+    // The host evens out the game, others see the result
+    let handicap = if !is_host && !player.handicap.is_none() {
+      format!(" with {}", player.handicap)
+    } else {
+      String::new()
+    };
+    let handicap_form = is_host.then(|| {
+      rsx!(HandicapForm {
+        handicap: player.handicap.clone(),
+        onchange: move |handicap| {
+          ws.send_json(&ClientMessage::Player(PlayerMessage::SetHandicap(
+            i, handicap,
+          )))
+        },
+      })
+    });
+    rsx!(li {
+      "{player.name}{host}{you}{members}{handicap} "
+      handicap_form
+    })
+  });
+
+  // The first player to join is the host, and the game only accepts StartGame from the player in turn.
+  // In a team, the server only accepts it from the first member.
+  let start = if is_host {
+    rsx!(button {
+      onclick: move |_| ws.send_json(&ClientMessage::Player(PlayerMessage::StartGame)),
+      prevent_default: "onclick",
+      "Start the game"
+    })
+  } else {
+    rsx!(p { "Waiting for the host to start the game…" })
+  };
+
+  cx.render(rsx!(
+    div {
+      class: "container",
+
+      h2 { "Join code: {code}" }
+      p {
+        "Share this link to invite players: "
+        a { href: "{link}", "{link}" }
+      }
+
+      h2 { "Players" }
+      ul { players }
+
+      start
+    }
+  ))
+}
@@ -1,6 +1,23 @@
+mod account;
+mod analysis;
 mod app;
+mod chat;
 mod die;
+mod event_log;
+mod handicap;
+mod hot_seat;
+mod language;
+mod leaderboard;
+mod lobby;
+mod online;
+mod profile;
+mod replay;
+mod ruleset_editor;
+mod ruleset_picker;
 mod score_card;
+mod storage;
+mod table;
+mod tournament;
 
 use crate::app::App;
 
@@ -1,4 +1,21 @@
+pub mod account;
+pub mod analysis;
 pub mod app;
+pub mod chat;
 pub mod die;
+pub mod event_log;
+pub mod handicap;
+pub mod hot_seat;
+pub mod language;
+pub mod leaderboard;
+pub mod lobby;
+pub mod online;
+pub mod profile;
+pub mod replay;
 // pub mod regular_polygon;
+pub mod ruleset_editor;
+pub mod ruleset_picker;
 pub mod score_card;
+pub mod storage;
+pub mod table;
+pub mod tournament;
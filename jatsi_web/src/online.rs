@@ -0,0 +1,206 @@
+use crate::account::{forget_login, store_login, stored_login, StoredLogin};
+use crate::chat::ChatBox;
+use crate::lobby::{Setup, WaitingRoom};
+use crate::table::Table;
+use crate::tournament::TournamentView;
+use dioxus::prelude::*;
+use dioxus_websocket_hooks::{use_ws_context, use_ws_context_provider_json};
+use jatsi_shared::chat::ChatMessage;
+use jatsi_shared::errors::InvalidLogin;
+use jatsi_shared::game::{Game, State};
+use jatsi_shared::i18n::{Language, Localize};
+use jatsi_shared::protocol::{ClientMessage, ServerMessage};
+use jatsi_shared::rules::ee_rules;
+use jatsi_shared::tournament::Tournament;
+
+/// Override at build time with eg. `JATSI_SERVER_URL=wss://example.com/jatsi trunk build`.
+const DEFAULT_SERVER_URL: &str = "ws://localhost:8088";
+
+pub fn server_url() -> &'static str {
+  option_env!("JATSI_SERVER_URL").unwrap_or(DEFAULT_SERVER_URL)
+}
+
+/// The HTTP API of the server. Override at build time with `JATSI_API_URL`.
+const DEFAULT_API_URL: &str = "http://localhost:8089";
+
+pub fn api_url() -> &'static str {
+  option_env!("JATSI_API_URL").unwrap_or(DEFAULT_API_URL)
+}
+
+#[derive(Props)]
+pub struct OnlineTableProps<'a> {
+  pub game: &'a UseRef<Game>,
+  pub me: Option<usize>,
+  pub name: String,
+  pub chat: &'a UseRef<Vec<ChatMessage>>,
+  pub language: Language,
+}
+
+// A table whose actions are sent to the server.
+#[allow(non_snake_case)]
+pub fn OnlineTable<'a>(cx: Scope<'a, OnlineTableProps<'a>>) -> Element {
+  let ws = use_ws_context(&cx);
+
+  cx.render(rsx!(Table {
+    game: cx.props.game,
+    me: cx.props.me,
+    name: cx.props.name.clone(),
+    onaction: move |msg| ws.send_json(&ClientMessage::Player(msg)),
+    chat: cx.props.chat,
+    language: cx.props.language,
+  }))
+}
+
+#[derive(Props, PartialEq)]
+pub struct OnlineProps {
+  /// The language of row names and error messages.
+  pub language: Language,
+}
+
+// Playing with others through the server.
+#[allow(non_snake_case)]
+pub fn Online(cx: Scope<OnlineProps>) -> Element {
+  let language = cx.props.language;
+  // Only state confirmed by the server ends up here. Replaced when entering a room.
+  let game = use_ref(&cx, || Game::new(ee_rules()));
+  let code = use_ref(&cx, || None::<String>);
+  let me = use_ref(&cx, || None::<usize>);
+  let error = use_ref(&cx, || None::<String>);
+  // Kept apart from the game, as chat is not part of its message history
+  let chat = use_ref(&cx, || Vec::<ChatMessage>::new());
+  // Remembered from an earlier visit until the server says otherwise
+  let account = use_ref(&cx, || stored_login().map(|login| login.account));
+  let name = use_state(&cx, || String::new());
+  // The tournament being followed and its code, shown whenever not at a table
+  let tournament = use_ref(&cx, || None::<(String, Tournament)>);
+
+  use_ws_context_provider_json(&cx, server_url(), {
+    let game = game.clone();
+    let code = code.clone();
+    let me = me.clone();
+    let error = error.clone();
+    let chat = chat.clone();
+    let account = account.clone();
+    let tournament = tournament.clone();
+
+    move |msg: ServerMessage| match msg {
+      ServerMessage::RoomEntered {
+        code: room_code,
+        ruleset,
+      } => {
+        error.set(None);
+        game.set(Game::new(ruleset));
+        code.set(Some(room_code));
+        me.set(None);
+        chat.set(Vec::new());
+      }
+      ServerMessage::NoSuchRoom(room_code) => error.set(Some(format!(
+        "There is no game with the code {}",
+        room_code
+      ))),
+      ServerMessage::RulesetRejected(err) => error.set(Some(format!("Invalid ruleset: {}", err))),
+      ServerMessage::Joined(player) => me.set(Some(player)),
+      ServerMessage::Game(game_msg) => {
+        error.set(None);
+        if let Err(err) = game.write().commit(&game_msg) {
+          error.set(Some(format!("Out of sync with the server: {}", err)));
+        }
+      }
+      ServerMessage::Rejected(err) => error.set(Some(err.localize(language))),
+      ServerMessage::Chat(chat_msg) => chat.write().push(chat_msg),
+      ServerMessage::ChatRejected(err) => error.set(Some(err.to_string())),
+      ServerMessage::LoggedIn {
+        account: logged_in,
+        token,
+      } => {
+        error.set(None);
+        store_login(&StoredLogin {
+          account: logged_in.clone(),
+          token,
+        });
+        account.set(Some(logged_in));
+      }
+      ServerMessage::LoggedOut => {
+        forget_login();
+        account.set(None);
+      }
+      ServerMessage::LoginRejected(err) => {
+        if err == InvalidLogin::UnknownToken {
+          forget_login();
+          account.set(None);
+        }
+        error.set(Some(err.to_string()));
+      }
+      ServerMessage::Tournament {
+        code: tournament_code,
+        tournament: updated,
+      } => {
+        error.set(None);
+        tournament.set(Some((tournament_code, updated)));
+      }
+      ServerMessage::TournamentRejected(err) => error.set(Some(err.to_string())),
+    }
+  });
+
+  // Logged in players play under their username
+  let player_name = account
+    .read()
+    .as_ref()
+    .map(|account| account.username.clone())
+    .unwrap_or_else(|| name.get().trim().to_string());
+
+  let screen = match (code.read().clone(), &game.read().state, &*tournament.read()) {
+    (None, _, Some((tournament_code, current))) => rsx!(TournamentView {
+      code: tournament_code.clone(),
+      tournament: current,
+      name: player_name.clone(),
+    }),
+    (None, _, None) => rsx!(Setup {
+      account: &account,
+      name: name,
+    }),
+    (Some(code), State::Start, _) => rsx!(WaitingRoom {
+      game: &game,
+      me: *me.read(),
+      code: code,
+    }),
+    (Some(_), _, _) => rsx!(OnlineTable {
+      game: &game,
+      me: *me.read(),
+      name: player_name,
+      chat: &chat,
+      language: language,
+    }),
+  };
+
+  let error = error.read().clone().map(|error| {
+    rsx!(div {
+      class: "container error",
+      "{error}"
+    })
+  });
+
+  let chat_box = code.read().as_ref().map(|_| rsx!(ChatBox { chat: &chat }));
+
+  // Stays in the room until another one is entered, so the game can be watched again
+  let back = match (code.read().as_ref(), tournament.read().as_ref()) {
+    (Some(_), Some(_)) => Some(rsx!(div {
+      class: "container",
+      button {
+        onclick: move |_| code.set(None),
+        prevent_default: "onclick",
+        "Back to the tournament"
+      }
+    })),
+    _ => None,
+  };
+
+  rsx!(cx,
+    div {
+      screen
+      error
+      chat_box
+      back
+    }
+  )
+}
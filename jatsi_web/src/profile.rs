@@ -0,0 +1,146 @@
+use dioxus::prelude::*;
+use gloo_net::http::Request;
+use jatsi_shared::stats::{PlayerStats, RulesetStats};
+
+use crate::account::stored_login;
+use crate::online::api_url;
+
+fn percent(rate: f64) -> String {
+  format!("{:.0} %", rate * 100.0)
+}
+
+async fn fetch_stats(username: &str) -> Result<PlayerStats, String> {
+  let url = format!("{}/players/{}/stats", api_url(), username);
+  let response = Request::get(&url)
+    .send()
+    .await
+    .map_err(|err| err.to_string())?;
+
+  match response.status() {
+    200 => response.json().await.map_err(|err| err.to_string()),
+    404 => Err(format!("There is no player called {}", username)),
+    status => Err(format!("The server responded with {}", status)),
+  }
+}
+
+#[derive(Props, PartialEq)]
+pub struct RulesetStatsTableProps {
+  pub stats: RulesetStats,
+}
+
+#[allow(non_snake_case)]
+pub fn RulesetStatsTable(cx: Scope<RulesetStatsTableProps>) -> Element {
+  let stats = &cx.props.stats;
+  let average_score = format!("{:.1}", stats.average_score);
+  let bonus = stats
+    .bonus_hit_rate
+    .map(percent)
+    .unwrap_or_else(|| "–".into());
+  let zero_rate = percent(stats.zero_rate);
+
+  let rows = stats.rows.iter().map(|row| {
+    let average = format!("{:.1}", row.average);
+    let zero_rate = percent(row.zero_rate);
+    rsx!(tr {
+      th { [row.name.clone()] }
+      td { "{average}" }
+      td { "{zero_rate}" }
+    })
+  });
+
+  cx.render(rsx!(
+    h3 { [stats.ruleset_name.clone()] }
+    table {
+      tbody {
+        tr { th { "Games" } td { [stats.games.to_string()] } }
+        tr { th { "Average score" } td { "{average_score}" } }
+        tr { th { "Best score" } td { [stats.best_score.to_string()] } }
+        tr { th { "Yahtzees" } td { [stats.yahtzees.to_string()] } }
+        tr { th { "Bonus hit rate" } td { "{bonus}" } }
+        tr { th { "Scratched rows" } td { "{zero_rate}" } }
+      }
+    }
+    table {
+      thead {
+        tr {
+          th {}
+          th { "Average" }
+          th { "Scratched" }
+        }
+      }
+      tbody { rows }
+    }
+  ))
+}
+
+// Stats of a player with an account, over all the games they have finished.
+#[allow(non_snake_case)]
+pub fn Profile(cx: Scope) -> Element {
+  let username = use_state(&cx, || {
+    stored_login()
+      .map(|login| login.account.username)
+      .unwrap_or_default()
+  });
+  let stats = use_ref(&cx, || None::<Result<PlayerStats, String>>);
+
+  let show = {
+    let stats = stats.clone();
+    let username = username.get().trim().to_string();
+    move |_| {
+      let stats = stats.clone();
+      let username = username.clone();
+      cx.push_future(async move {
+        stats.set(Some(fetch_stats(&username).await));
+      });
+    }
+  };
+
+  let content = match &*stats.read() {
+    None => None,
+    Some(Err(err)) => Some(rsx!(p { class: "error", "{err}" })),
+    Some(Ok(player_stats)) if player_stats.games_played == 0 => {
+      let name = &player_stats.username;
+      Some(rsx!(p { "{name} has not finished any games yet." }))
+    }
+    Some(Ok(player_stats)) => {
+      let name = &player_stats.username;
+      let games_played = player_stats.games_played;
+      let unreadable = match player_stats.unreadable_games {
+        0 => None,
+        count => Some(rsx!(p { "{count} of them could not be read for the stats below." })),
+      };
+      let rulesets = player_stats.rulesets.iter().map(|ruleset_stats| {
+        rsx!(RulesetStatsTable {
+          stats: ruleset_stats.clone(),
+        })
+      });
+      Some(rsx!(
+        h2 { "{name}" }
+        p { "{games_played} games played" }
+        unreadable
+        rulesets
+      ))
+    }
+  };
+
+  cx.render(rsx!(
+    div {
+      class: "container profile",
+
+      h2 { "Player profile" }
+      input {
+        placeholder: "Username",
+        value: "{username}",
+        oninput: move |evt| username.set(evt.value.clone()),
+      }
+      button {
+        disabled: "{username.get().trim().is_empty()}",
+        onclick: show,
+        prevent_default: "onclick",
+        "Show stats"
+      }
+
+      content
+    }
+  ))
+}
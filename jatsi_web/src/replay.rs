@@ -0,0 +1,254 @@
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+use jatsi_shared::game::{Game, SavedGame};
+use jatsi_shared::i18n::Language;
+use jatsi_shared::rules::ee_rules;
+
+use crate::chat::ChatLog;
+use crate::ruleset_picker::read_chosen_file;
+use crate::table::Table;
+
+const FILE_INPUT_ID: &str = "replay-file-input";
+
+/// Milliseconds between steps, with their labels.
+const SPEEDS: [(u32, &str); 4] = [(2000, "0.5×"), (1000, "1×"), (500, "2×"), (250, "4×")];
+
+/// A link that downloads the game as a file that can be loaded into the replay viewer.
+pub fn download_link(saved_game: &SavedGame) -> String {
+  let json = serde_json::to_string(saved_game).expect("games should serialize");
+  format!(
+    "data:application/json;charset=utf-8,{}",
+    js_sys::encode_uri_component(&json)
+  )
+}
+
+/// The game as it was after the first `step` messages.
+fn game_at(saved_game: &SavedGame, step: usize) -> Game {
+  Game::replay(
+    saved_game.ruleset.clone(),
+    &saved_game.message_history[..step],
+  )
+  .expect("the whole history was replayed when loading")
+}
+
+#[derive(Props, PartialEq)]
+pub struct ReplayProps {
+  /// The language of row names.
+  pub language: Language,
+}
+
+// Stepping through a finished or saved game.
+#[allow(non_snake_case)]
+pub fn Replay(cx: Scope<ReplayProps>) -> Element {
+  let saved_game = use_ref(&cx, || None::<SavedGame>);
+  let game = use_ref(&cx, || Game::new(ee_rules()));
+  let step = use_ref(&cx, || 0usize);
+  let playing = use_ref(&cx, || false);
+  // Bumped whenever playback starts so that a stale playback loop knows to stop
+  let playback = use_ref(&cx, || 0u64);
+  let delay = use_state(&cx, || SPEEDS[1].0);
+  let file = use_state(&cx, || String::new());
+  let error = use_state(&cx, || None::<String>);
+
+  let go_to = {
+    let saved_game = saved_game.clone();
+    let game = game.clone();
+    let step = step.clone();
+
+    move |to: usize| {
+      if let Some(saved_game) = &*saved_game.read() {
+        let to = to.min(saved_game.message_history.len());
+        game.set(game_at(saved_game, to));
+        step.set(to);
+      }
+    }
+  };
+
+  let total = match &*saved_game.read() {
+    Some(saved_game) => saved_game.message_history.len(),
+    None => {
+      let error = error.get().clone().map(|error| {
+        rsx!(p {
+          class: "error",
+          "{error}"
+        })
+      });
+
+      return cx.render(rsx!(
+        div {
+          class: "container",
+
+          h2 { "Watch a replay" }
+          input {
+            id: "{FILE_INPUT_ID}",
+            r#type: "file",
+            accept: ".json,application/json",
+            onchange: move |_| {
+              let file = file.clone();
+              let error = error.clone();
+              cx.push_future(async move {
+                match read_chosen_file(FILE_INPUT_ID).await {
+                  Some(contents) => {
+                    file.set(contents);
+                    error.set(None);
+                  }
+                  None => error.set(Some("The file could not be read.".to_string())),
+                }
+              });
+            },
+          }
+          textarea {
+            class: "ruleset-file",
+            placeholder: "Open a saved game above or paste its contents here",
+            value: "{file}",
+            oninput: move |evt| file.set(evt.value.clone()),
+          }
+          error
+          button {
+            disabled: "{file.get().trim().is_empty()}",
+            onclick: move |_| {
+              let loaded = serde_json::from_str::<SavedGame>(file.get())
+                .map_err(|err| err.to_string())
+                .and_then(|loaded| {
+                  loaded.ruleset.validate().map(|_| loaded).map_err(|err| err.to_string())
+                })
+                .and_then(|loaded| loaded.load().map(|_| loaded).map_err(|err| err.to_string()));
+              match loaded {
+                Ok(loaded) => {
+                  game.set(game_at(&loaded, 0));
+                  step.set(0);
+                  saved_game.set(Some(loaded));
+                  error.set(None);
+                }
+                Err(err) => error.set(Some(format!("Not a valid saved game: {}", err))),
+              }
+            },
+            prevent_default: "onclick",
+            "Load"
+          }
+        }
+      ));
+    }
+  };
+
+  let current = *step.read();
+
+  let play = {
+    let go_to = go_to.clone();
+    let step = step.clone();
+    let playing = playing.clone();
+    let playback = playback.clone();
+    let delay = delay.clone();
+
+    move |_| {
+      if *playing.read() {
+        playing.set(false);
+        return;
+      }
+
+      // Playing from the end starts over
+      if *step.read() >= total {
+        go_to(0);
+      }
+      playing.set(true);
+      *playback.write() += 1;
+      let this_playback = *playback.read();
+
+      let go_to = go_to.clone();
+      let step = step.clone();
+      let playing = playing.clone();
+      let playback = playback.clone();
+      let delay = delay.clone();
+      cx.push_future(async move {
+        loop {
+          TimeoutFuture::new(*delay.current()).await;
+          if !*playing.read() || *playback.read() != this_playback {
+            break;
+          }
+
+          let next = *step.read() + 1;
+          go_to(next);
+          if next >= total {
+            playing.set(false);
+            break;
+          }
+        }
+      });
+    }
+  };
+
+  let speeds = SPEEDS.iter().map(|&(ms, label)| {
+    rsx!(option {
+      value: "{ms}",
+      [label]
+    })
+  });
+
+  let play_label = if *playing.read() { "Pause" } else { "Play" };
+  let chat = saved_game
+    .read()
+    .as_ref()
+    .map(|saved_game| saved_game.chat.clone())
+    .unwrap_or_default();
+  let chat = if chat.is_empty() {
+    None
+  } else {
+    Some(rsx!(div {
+      class: "container chat",
+
+      h2 { "Chat" }
+      ChatLog { messages: &chat }
+    }))
+  };
+  let (back, forward) = (go_to.clone(), go_to.clone());
+
+  cx.render(rsx!(
+    div {
+      Table {
+        game: game,
+        me: None,
+        language: cx.props.language,
+        onaction: move |_| {},
+      }
+
+      div {
+        class: "container replay-controls",
+
+        p { "Step {current} of {total}" }
+        button {
+          disabled: "{current == 0}",
+          onclick: move |_| back(current.saturating_sub(1)),
+          prevent_default: "onclick",
+          "Back"
+        }
+        button {
+          onclick: play,
+          prevent_default: "onclick",
+          "{play_label}"
+        }
+        button {
+          disabled: "{current >= total}",
+          onclick: move |_| forward(current + 1),
+          prevent_default: "onclick",
+          "Forward"
+        }
+        select {
+          value: "{delay}",
+          onchange: move |evt| if let Ok(ms) = evt.value.parse() { delay.set(ms) },
+
+          speeds
+        }
+        button {
+          onclick: move |_| {
+            playing.set(false);
+            saved_game.set(None);
+          },
+          prevent_default: "onclick",
+          "Load another game"
+        }
+      }
+
+      chat
+    }
+  ))
+}
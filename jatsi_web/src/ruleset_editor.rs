@@ -0,0 +1,549 @@
+use dioxus::prelude::*;
+use jatsi_shared::dice::Die;
+use jatsi_shared::expression::Expression;
+use jatsi_shared::i18n::{Language, Localize};
+use jatsi_shared::rules::{builtin_rulesets, ee_rules, PlacementOrder, Ruleset, Scoring};
+
+/// The kinds of scoring rows with their labels, in the order they are offered.
+const KINDS: [(&str, &str); 9] = [
+  ("Numbers", "Number"),
+  ("Bonus", "Bonus"),
+  ("SetOf", "Set"),
+  ("Straight", "Straight"),
+  ("TwoPairs", "Two pairs"),
+  ("FullHouse", "Full house"),
+  ("Yahtzee", "Yahtzee"),
+  ("Chance", "Chance"),
+  ("Custom", "Expression"),
+];
+
+const ORDERS: [(PlacementOrder, &str); 4] = [
+  (PlacementOrder::Free, "Any row"),
+  (PlacementOrder::TopDown, "Top down"),
+  (PlacementOrder::BottomUp, "Bottom up"),
+  (PlacementOrder::Announced, "Announced"),
+];
+
+fn kind(scoring: &Scoring) -> &'static str {
+  match scoring {
+    Scoring::Numbers { .. } => "Numbers",
+    Scoring::Bonus { .. } => "Bonus",
+    Scoring::SetOf { .. } => "SetOf",
+    Scoring::Straight { .. } => "Straight",
+    Scoring::TwoPairs {} => "TwoPairs",
+    Scoring::FullHouse { .. } => "FullHouse",
+    Scoring::Yahtzee { .. } => "Yahtzee",
+    Scoring::Chance => "Chance",
+    Scoring::Custom { .. } => "Custom",
+  }
+}
+
+/// A new row of a kind, with the parameters of the classic rules.
+fn new_scoring(kind: &str) -> Scoring {
+  match kind {
+    "Numbers" => Scoring::Numbers { num: 1 },
+    "Bonus" => Scoring::Bonus {
+      min_points: 63,
+      value: 50,
+    },
+    "SetOf" => Scoring::SetOf { num: 3 },
+    "Straight" => Scoring::Straight {
+      min_length: 4,
+      value: 30,
+    },
+    "TwoPairs" => Scoring::TwoPairs {},
+    "FullHouse" => Scoring::FullHouse { value: 25 },
+    "Yahtzee" => Scoring::Yahtzee { value: 50 },
+    "Custom" => Scoring::Custom {
+      name: "Custom".into(),
+      expression: Expression::parse("sum").expect("the default expression is valid"),
+    },
+    _ => Scoring::Chance,
+  }
+}
+
+/// How a die is written in the editor: its number of sides, or its faces separated by commas.
+fn die_text(die: &Die) -> String {
+  match die {
+    Die::Sides(sides) => sides.to_string(),
+    Die::Faces(faces) => faces
+      .iter()
+      .map(|face| face.to_string())
+      .collect::<Vec<String>>()
+      .join(", "),
+  }
+}
+
+fn parse_die(text: &str) -> Option<Die> {
+  let values = text
+    .split(',')
+    .map(|value| value.trim().parse::<u64>().ok())
+    .collect::<Option<Vec<u64>>>()?;
+  if text.contains(',') {
+    Some(Die::Faces(values))
+  } else {
+    values.first().map(|&sides| Die::Sides(sides))
+  }
+}
+
+/// A sample roll written as values separated by spaces, if it has a value for every die.
+fn parse_roll(text: &str, ruleset: &Ruleset) -> Option<Vec<u64>> {
+  let roll: Vec<u64> = text
+    .split_whitespace()
+    .map(|value| value.parse().ok())
+    .collect::<Option<Vec<u64>>>()?;
+  (roll.len() == ruleset.dice.len()).then_some(roll)
+}
+
+/// A link that downloads the ruleset as a ruleset file.
+fn ruleset_link(ruleset: &Ruleset) -> String {
+  format!(
+    "data:application/json;charset=utf-8,{}",
+    js_sys::encode_uri_component(&ruleset.to_json())
+  )
+}
+
+#[derive(Props)]
+pub struct NumberFieldProps<'a> {
+  pub label: &'a str,
+  pub value: u64,
+  pub onchange: EventHandler<'a, u64>,
+}
+
+// A labeled field for a whole number that is not negative.
+#[allow(non_snake_case)]
+pub fn NumberField<'a>(cx: Scope<'a, NumberFieldProps<'a>>) -> Element {
+  let NumberFieldProps {
+    label,
+    value,
+    onchange,
+  } = cx.props;
+
+  cx.render(rsx!(
+    label {
+      "{label} "
+      input {
+        r#type: "number",
+        min: "0",
+        value: "{value}",
+        oninput: move |evt| if let Ok(value) = evt.value.parse() { onchange.call(value) },
+      }
+    }
+  ))
+}
+
+#[derive(Props, PartialEq)]
+pub struct RulesetEditorProps {
+  /// The language of the built-in row names and explanations.
+  pub language: Language,
+}
+
+// Composing a ruleset row by row and exporting it as a ruleset file.
+#[allow(non_snake_case)]
+pub fn RulesetEditor(cx: Scope<RulesetEditorProps>) -> Element {
+  let ruleset = use_ref(&cx, || with_row_names(ee_rules()));
+  // The expressions of the rows as typed, parallel to the scorings, so that an expression can be invalid while it is
+  // being written. Only valid expressions make it to the ruleset.
+  let expressions = use_ref(&cx, || expression_sources(&ee_rules()));
+  let sample = use_state(&cx, || "3 4 5 6 6".to_string());
+  let import = use_state(&cx, String::new);
+  let import_error = use_state(&cx, || None::<String>);
+  let language = cx.props.language;
+
+  let load = move |loaded: Ruleset| {
+    expressions.set(expression_sources(&loaded));
+    ruleset.set(with_row_names(loaded));
+  };
+
+  let starting_points = builtin_rulesets()
+    .into_iter()
+    .enumerate()
+    .map(|(index, (label, _))| {
+      rsx!(option {
+        value: "{index}",
+        [label]
+      })
+    });
+
+  let dice = ruleset.read().dice.iter().enumerate().map(|(i, die)| {
+    let text = die_text(die);
+    rsx!(li {
+      input {
+        value: "{text}",
+        oninput: move |evt| if let Some(die) = parse_die(&evt.value) {
+          ruleset.write().dice[i] = die;
+        },
+      }
+      " "
+      a {
+        href: "#",
+        onclick: move |_| { ruleset.write().dice.remove(i); },
+        prevent_default: "onclick",
+        "remove"
+      }
+    })
+  });
+
+  let orders = ORDERS.iter().map(|(_, label)| {
+    rsx!(option {
+      value: "{label}",
+      [*label]
+    })
+  });
+  let order = ORDERS
+    .iter()
+    .find(|(order, _)| *order == ruleset.read().order)
+    .map_or("", |(_, label)| label);
+
+  let roll = parse_roll(sample.get(), &ruleset.read());
+  let rows = ruleset.read().scorings.clone();
+  let row_count = rows.len();
+  let scorings = rows.into_iter().enumerate().map(|(i, scoring)| {
+    let set = move |scoring: Scoring| ruleset.write().scorings[i] = scoring;
+    let kinds = KINDS.iter().map(|(value, label)| {
+      rsx!(option {
+        value: "{value}",
+        [*label]
+      })
+    });
+
+    let parameters = match scoring.clone() {
+      Scoring::Numbers { num } => rsx!(NumberField {
+        label: "Value",
+        value: num,
+        onchange: move |num| set(Scoring::Numbers { num }),
+      }),
+      Scoring::Bonus { min_points, value } => rsx!(
+        NumberField {
+          label: "Threshold",
+          value: min_points,
+          onchange: move |min_points| set(Scoring::Bonus { min_points, value }),
+        }
+        NumberField {
+          label: "Points",
+          value: value,
+          onchange: move |value| set(Scoring::Bonus { min_points, value }),
+        }
+      ),
+      Scoring::SetOf { num } => rsx!(NumberField {
+        label: "Dice",
+        value: num,
+        onchange: move |num| set(Scoring::SetOf { num }),
+      }),
+      Scoring::Straight { min_length, value } => rsx!(
+        NumberField {
+          label: "Length",
+          value: min_length,
+          onchange: move |min_length| set(Scoring::Straight { min_length, value }),
+        }
+        NumberField {
+          label: "Points",
+          value: value,
+          onchange: move |value| set(Scoring::Straight { min_length, value }),
+        }
+      ),
+      Scoring::FullHouse { value } => rsx!(NumberField {
+        label: "Points",
+        value: value,
+        onchange: move |value| set(Scoring::FullHouse { value }),
+      }),
+      Scoring::Yahtzee { value } => rsx!(NumberField {
+        label: "Points",
+        value: value,
+        onchange: move |value| set(Scoring::Yahtzee { value }),
+      }),
+      Scoring::Custom { name, expression } => {
+        let source = expressions.read().get(i).cloned().unwrap_or_default();
+        let error = Expression::parse(&source).err().map(|err| {
+          rsx!(span {
+            class: "error",
+            " {err}"
+          })
+        });
+        let renamed = expression.clone();
+        let typed_name = name.clone();
+        rsx!(
+          label {
+            "Name "
+            input {
+              value: "{name}",
+              oninput: move |evt| set(Scoring::Custom {
+                name: evt.value.clone(),
+                expression: renamed.clone(),
+              }),
+            }
+          }
+          label {
+            " Expression "
+            input {
+              class: "expression",
+              value: "{source}",
+              oninput: move |evt| {
+                expressions.write()[i] = evt.value.clone();
+                if let Ok(expression) = Expression::parse(&evt.value) {
+                  set(Scoring::Custom { name: typed_name.clone(), expression });
+                }
+              },
+            }
+          }
+          error
+        )
+      }
+      Scoring::TwoPairs {} | Scoring::Chance => rsx!(""),
+    };
+
+    // What the sample roll would score here
+    let preview = roll
+      .as_ref()
+      .and_then(|roll| ruleset.read().explain(i, roll, language))
+      .map(|explanation| format!("{} ({})", explanation.points, explanation.reason))
+      .unwrap_or_default();
+    let name = ruleset.read().row_name(i, language);
+    let shown_as = ruleset
+      .read()
+      .row_names
+      .get(i)
+      .cloned()
+      .flatten()
+      .unwrap_or_default();
+    let built_in_name = scoring.localize(language);
+    let current_kind = kind(&scoring);
+
+    rsx!(tr {
+      th { "{name}" }
+      td {
+        input {
+          placeholder: "{built_in_name}",
+          value: "{shown_as}",
+          oninput: move |evt| {
+            let shown_as = evt.value.trim();
+            ruleset.write().row_names[i] = (!shown_as.is_empty()).then(|| shown_as.to_string());
+          },
+        }
+      }
+      td {
+        select {
+          value: "{current_kind}",
+          onchange: move |evt| {
+            let scoring = new_scoring(&evt.value);
+            if let Scoring::Custom { expression, .. } = &scoring {
+              expressions.write()[i] = expression.to_string();
+            }
+            set(scoring);
+          },
+          kinds
+        }
+      }
+      td { parameters }
+      td { class: "preview", "{preview}" }
+      td {
+        button {
+          disabled: "{i == 0}",
+          onclick: move |_| if i > 0 {
+            let mut ruleset = ruleset.write();
+            ruleset.scorings.swap(i - 1, i);
+            ruleset.row_names.swap(i - 1, i);
+            expressions.write().swap(i - 1, i);
+          },
+          prevent_default: "onclick",
+          title: "Move up",
+          "↑"
+        }
+        button {
+          disabled: "{i + 1 == row_count}",
+          onclick: move |_| if i + 1 < row_count {
+            let mut ruleset = ruleset.write();
+            ruleset.scorings.swap(i, i + 1);
+            ruleset.row_names.swap(i, i + 1);
+            expressions.write().swap(i, i + 1);
+          },
+          prevent_default: "onclick",
+          title: "Move down",
+          "↓"
+        }
+        button {
+          onclick: move |_| {
+            let mut ruleset = ruleset.write();
+            ruleset.scorings.remove(i);
+            ruleset.row_names.remove(i);
+            expressions.write().remove(i);
+          },
+          prevent_default: "onclick",
+          title: "Remove",
+          "✕"
+        }
+      }
+    })
+  });
+
+  let status = match ruleset.read().validate() {
+    Ok(()) => "The ruleset is valid.".to_string(),
+    Err(err) => format!("Invalid ruleset: {}", err),
+  };
+  let sample_status = match roll {
+    Some(..) => String::new(),
+    None => format!(
+      "Give a value for each of the {} dice.",
+      ruleset.read().dice.len()
+    ),
+  };
+  let metadata = ruleset.read().metadata.clone();
+  let json = ruleset.read().to_json();
+  let link = ruleset_link(&ruleset.read());
+  let import_message = import_error.get().clone().map(|error| {
+    rsx!(p {
+      class: "error",
+      "{error}"
+    })
+  });
+
+  cx.render(rsx!(
+    div {
+      class: "container ruleset-editor",
+
+      h2 { "Make a ruleset" }
+      label {
+        "Start from "
+        select {
+          onchange: move |evt| if let Ok(index) = evt.value.parse::<usize>() {
+            load(builtin_rulesets()[index].1.clone());
+          },
+          starting_points
+        }
+      }
+
+      h3 { "About" }
+      label {
+        "Name "
+        input {
+          value: "{metadata.name}",
+          oninput: move |evt| ruleset.write().metadata.name = evt.value.clone(),
+        }
+      }
+      label {
+        " Author "
+        input {
+          value: "{metadata.author}",
+          oninput: move |evt| ruleset.write().metadata.author = evt.value.clone(),
+        }
+      }
+      textarea {
+        class: "description",
+        placeholder: "What is special about these rules",
+        value: "{metadata.description}",
+        oninput: move |evt| ruleset.write().metadata.description = evt.value.clone(),
+      }
+
+      h3 { "Dice" }
+      p { "Give a die as its number of sides, or as its faces separated by commas." }
+      ol { dice }
+      button {
+        onclick: move |_| ruleset.write().dice.push(Die::Sides(6)),
+        prevent_default: "onclick",
+        "Add a die"
+      }
+      NumberField {
+        label: "Rolls per turn",
+        value: ruleset.read().rolls,
+        onchange: move |rolls| ruleset.write().rolls = rolls,
+      }
+      label {
+        " Rows are filled "
+        select {
+          value: "{order}",
+          onchange: move |evt| if let Some((order, _)) = ORDERS.iter().find(|(_, label)| *label == evt.value) {
+            ruleset.write().order = *order;
+          },
+          orders
+        }
+      }
+
+      h3 { "Rows" }
+      label {
+        "Sample roll "
+        input {
+          value: "{sample}",
+          oninput: move |evt| sample.set(evt.value.clone()),
+        }
+        " {sample_status}"
+      }
+      table {
+        thead {
+          tr {
+            th { "Row" }
+            th { "Shown as" }
+            th { "Kind" }
+            th { "Parameters" }
+            th { "Sample roll scores" }
+            th {}
+          }
+        }
+        tbody { scorings }
+      }
+      button {
+        onclick: move |_| {
+          let mut ruleset = ruleset.write();
+          ruleset.scorings.push(Scoring::Chance);
+          ruleset.row_names.push(None);
+          expressions.write().push(String::new());
+        },
+        prevent_default: "onclick",
+        "Add a row"
+      }
+
+      h3 { "Ruleset file" }
+      p { "{status}" }
+      p { "Paste this into \"Ruleset file…\" when starting a game, or save it as a file." }
+      textarea {
+        class: "ruleset-file",
+        readonly: "true",
+        value: "{json}",
+      }
+      a {
+        href: "{link}",
+        download: "jatsi-ruleset.json",
+        "Save the ruleset file"
+      }
+
+      h3 { "Import" }
+      textarea {
+        class: "ruleset-file",
+        placeholder: "Paste the contents of a ruleset file here to edit it",
+        value: "{import}",
+        oninput: move |evt| import.set(evt.value.clone()),
+      }
+      import_message
+      button {
+        disabled: "{import.get().trim().is_empty()}",
+        onclick: move |_| match serde_json::from_str::<Ruleset>(import.get()) {
+          Ok(imported) => {
+            load(imported);
+            import.set(String::new());
+            import_error.set(None);
+          }
+          Err(err) => import_error.set(Some(format!("Cannot read the ruleset: {}", err))),
+        },
+        prevent_default: "onclick",
+        "Import"
+      }
+    }
+  ))
+}
+
+/// `ruleset` with a display name, or `None`, for each row, so that the names stay with their rows when editing.
+fn with_row_names(mut ruleset: Ruleset) -> Ruleset {
+  ruleset.row_names.resize(ruleset.scorings.len(), None);
+  ruleset
+}
+
+/// The sources of the expressions of the rows of `ruleset`, empty for rows of other kinds.
+fn expression_sources(ruleset: &Ruleset) -> Vec<String> {
+  ruleset
+    .scorings
+    .iter()
+    .map(|scoring| match scoring {
+      Scoring::Custom { expression, .. } => expression.to_string(),
+      _ => String::new(),
+    })
+    .collect()
+}
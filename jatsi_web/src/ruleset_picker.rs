@@ -0,0 +1,123 @@
+use dioxus::prelude::*;
+use jatsi_shared::errors::InvalidRuleset;
+use jatsi_shared::rules::{builtin_rulesets, Ruleset};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::HtmlInputElement;
+
+const CUSTOM_RULESET: &str = "custom";
+const FILE_INPUT_ID: &str = "ruleset-file-input";
+
+/// The text of the file chosen with the file input of `id`, if a file was chosen and could be read.
+pub(crate) async fn read_chosen_file(id: &str) -> Option<String> {
+  let input: HtmlInputElement = web_sys::window()?
+    .document()?
+    .get_element_by_id(id)?
+    .dyn_into()
+    .ok()?;
+  let file = input.files()?.get(0)?;
+  JsFuture::from(file.text()).await.ok()?.as_string()
+}
+
+/// The ruleset picked with a `RulesetPicker`, given the same states.
+pub fn picked_ruleset(choice: &str, ruleset_file: &str) -> Result<Ruleset, InvalidRuleset> {
+  match choice.parse::<usize>() {
+    Ok(index) => Ok(builtin_rulesets()[index].1.clone()),
+    Err(_) => Ruleset::from_json(ruleset_file),
+  }
+}
+
+#[derive(Props)]
+pub struct RulesetPickerProps<'a> {
+  /// Index of the built-in ruleset, or "custom" for a ruleset file.
+  pub choice: &'a UseState<String>,
+  /// Contents of the ruleset file.
+  pub ruleset_file: &'a UseState<String>,
+}
+
+// Choosing one of the built-in rulesets, or a ruleset file by opening or pasting it.
+#[allow(non_snake_case)]
+pub fn RulesetPicker<'a>(cx: Scope<'a, RulesetPickerProps<'a>>) -> Element {
+  let RulesetPickerProps {
+    choice,
+    ruleset_file,
+  } = cx.props;
+  let unreadable = use_state(&cx, || false);
+
+  let options = builtin_rulesets()
+    .into_iter()
+    .enumerate()
+    .map(|(index, (label, _))| {
+      rsx!(option {
+        value: "{index}",
+        [label]
+      })
+    });
+
+  let custom = if choice.get() == CUSTOM_RULESET {
+    let picked = picked_ruleset(choice.get(), ruleset_file.get());
+    let status = match &picked {
+      Ok(ruleset) => match (ruleset.metadata.name.trim(), ruleset.metadata.author.trim()) {
+        ("", _) => "The ruleset is valid.".to_string(),
+        (name, "") => format!("{} is a valid ruleset.", name),
+        (name, author) => format!("{} by {} is a valid ruleset.", name, author),
+      },
+      Err(err) => format!("Invalid ruleset: {}", err),
+    };
+    let description = picked
+      .ok()
+      .map(|ruleset| ruleset.metadata.description)
+      .filter(|description| !description.trim().is_empty())
+      .map(|description| rsx!(p { class: "description", "{description}" }));
+
+    let unreadable_error = unreadable
+      .get()
+      .then(|| rsx!(p { class: "error", "The file could not be read." }));
+
+    Some(rsx!(
+      input {
+        id: "{FILE_INPUT_ID}",
+        r#type: "file",
+        accept: ".json,application/json",
+        onchange: move |_| {
+          let ruleset_file = ruleset_file.clone();
+          let unreadable = unreadable.clone();
+          cx.push_future(async move {
+            match read_chosen_file(FILE_INPUT_ID).await {
+              Some(contents) => {
+                ruleset_file.set(contents);
+                unreadable.set(false);
+              }
+              None => unreadable.set(true),
+            }
+          });
+        },
+      }
+      unreadable_error
+      textarea {
+        class: "ruleset-file",
+        placeholder: "Open a ruleset file above or paste its contents here",
+        value: "{ruleset_file}",
+        oninput: move |evt| ruleset_file.set(evt.value.clone()),
+      }
+      p { "{status}" }
+      description
+    ))
+  } else {
+    None
+  };
+
+  cx.render(rsx!(
+    select {
+      value: "{choice}",
+      onchange: move |evt| choice.set(evt.value.clone()),
+
+      options
+      option {
+        value: "{CUSTOM_RULESET}",
+        "Ruleset file…"
+      }
+    }
+    custom
+  ))
+}
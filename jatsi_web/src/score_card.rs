@@ -1,53 +1,168 @@
 use jatsi_shared::game::Game;
+use jatsi_shared::i18n::Language;
+use jatsi_shared::rules::Scoring;
 
 use dioxus::prelude::*;
+
 #[derive(Props)]
 pub struct ScoreCardProps<'a> {
   pub game: &'a UseRef<Game>,
+  /// The language of row names and explanations.
+  pub language: Language,
+  /// Called with the column and row the player in turn chose to place the roll on.
+  pub onplace: EventHandler<'a, (usize, usize)>,
+  /// Called with the dice that count on the row the pointer is on, and with none once it leaves.
+  pub onhighlight: EventHandler<'a, Vec<usize>>,
 }
 
 #[allow(non_snake_case)]
 pub fn ScoreCard<'a>(cx: Scope<'a, ScoreCardProps<'a>>) -> Element {
   let game = cx.props.game.read();
+  let onplace = &cx.props.onplace;
+  let onhighlight = &cx.props.onhighlight;
+  let in_turn = game.player_in_turn;
+  let columns = &game.ruleset.columns;
+  let language = cx.props.language;
 
-  let player_headers = game
-    .players
-    .iter()
-    .map(|player| rsx! ( th { [player.name.clone()] }));
+  // A column for each column of each player's score sheet, marked with its multiplier if there are several.
+  // The members of teams are shown on hover.
+  let player_headers = game.players.iter().flat_map(|player| {
+    let members = player.members.join(", ");
+    columns.iter().map(move |multiplier| {
+      let title = match columns.len() {
+        1 => player.name.clone(),
+        _ => format!("{} ×{}", player.name, multiplier),
+      };
+      rsx!(th {
+        title: "{members}",
+        "{title}"
+      })
+    })
+  });
 
-  let scoring_rows = game
+  let bonus_index = game
     .ruleset
     .scorings
     .iter()
-    .enumerate()
-    .map(|(i, scoring)| {
-      let player_scorings = game.players.iter().map(move |player| {
-        if let Some(points) = player.score_sheet[i] {
-          rsx!(td { [points.to_string()] })
-        } else {
-          rsx!(td {})
-        }
+    .position(|scoring| matches!(scoring, Scoring::Bonus { .. }));
+
+  let scoring_rows = game.ruleset.scorings.iter().enumerate().map(|(i, _)| {
+    let game = &game;
+    let player_scorings = game
+      .players
+      .iter()
+      .enumerate()
+      .flat_map(move |(p, player)| {
+        player
+          .score_sheet
+          .iter()
+          .enumerate()
+          .map(move |(column, sheet_column)| {
+            // What the player in turn would get by placing the current roll here, if they can
+            let preview = if p == in_turn {
+              game.preview_place(column, i).ok()
+            } else {
+              None
+            };
+            let hint = preview
+              .as_ref()
+              .and_then(|score_sheet| score_sheet[column][i]);
+            let triggers_bonus = match (bonus_index, &preview) {
+              (Some(bonus_index), Some(score_sheet)) => {
+                sheet_column[bonus_index].is_none()
+                  && score_sheet[column][bonus_index].unwrap_or(0) > 0
+              }
+              _ => false,
+            };
+
+            let can_place = preview.is_some();
+            let selectable = if can_place { "selectable" } else { "" };
+            let bonus = if triggers_bonus { "triggers-bonus" } else { "" };
+            let wilds = match can_place {
+              true => game.ruleset.assigned_wilds(i, &game.roll),
+              false => Vec::new(),
+            };
+            let explanation = match can_place {
+              true => game.ruleset.explain(i, &game.roll, language),
+              false => None,
+            };
+            let mut titles = Vec::new();
+            if let Some(explanation) = &explanation {
+              let row_name = game.ruleset.row_name(i, language);
+              titles.push(format!("{}: {}", row_name, explanation.reason));
+            }
+            if !wilds.is_empty() {
+              let wilds: Vec<String> = wilds.iter().map(|value| value.to_string()).collect();
+              titles.push(format!("Wilds count as {}", wilds.join(", ")));
+            }
+            if triggers_bonus {
+              titles.push("Placing here also scores the bonus".to_string());
+            }
+            let title = titles.join(". ");
+
+            match (hint, sheet_column[i]) {
+              (Some(points), _) => {
+                let zero = if points == 0 { "zero" } else { "" };
+                let counting = explanation
+                  .map(|explanation| explanation.dice)
+                  .unwrap_or_default();
+                rsx!(td {
+                  class: "hint {zero} {selectable} {bonus}",
+                  title: "{title}",
+                  onclick: move |_| if can_place { onplace.call((column, i)) },
+                  onmouseover: move |_| onhighlight.call(counting.clone()),
+                  onmouseout: move |_| onhighlight.call(Vec::new()),
+                  [points.to_string()]
+                })
+              }
+              (_, Some(points)) => rsx!(td { [points.to_string()] }),
+              _ => rsx!(td {}),
+            }
+          })
       });
 
-      rsx! (
-        tr {
-          th { [scoring.name()] }
-          player_scorings
-        }
-      )
+    rsx! (
+      tr {
+        th { [game.ruleset.row_name(i, language)] }
+        player_scorings
+      }
+    )
+  });
+
+  // Handicaps are shown above the totals they count in, like the totals spanning all columns of a player
+  let handicap_row = game
+    .players
+    .iter()
+    .any(|player| !player.handicap.is_none())
+    .then(|| {
+      let handicaps = game.players.iter().map(|player| {
+        let description = match player.handicap.is_none() {
+          true => String::new(),
+          false => player.handicap.to_string(),
+        };
+        rsx!(td {
+          class: "handicap",
+          colspan: "{columns.len()}",
+          "{description}"
+        })
+      });
+      rsx!(tr {
+        th { "Handicap" }
+        handicaps
+      })
     });
 
+  // The total of a player spans all of their columns
   let total_footers = game
     .players
     .iter()
-    .map(|player| {
-      player
-        .score_sheet
-        .iter()
-        .map(|maybe_points| maybe_points.unwrap_or(0))
-        .sum::<u64>()
-    })
-    .map(|points| rsx!(th { [points.to_string()] }));
+    .map(|player| player.total(&game.ruleset))
+    .map(|points| {
+      rsx!(th {
+        colspan: "{columns.len()}",
+        [points.to_string()]
+      })
+    });
 
   cx.render(rsx! (
     table {
@@ -63,6 +178,7 @@ pub fn ScoreCard<'a>(cx: Scope<'a, ScoreCardProps<'a>>) -> Element {
       }
 
       tfoot {
+        handicap_row
         tr {
           th { "Total" }
           total_footers
@@ -0,0 +1,24 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+fn local_storage() -> Option<web_sys::Storage> {
+  web_sys::window()?.local_storage().ok()?
+}
+
+/// Reads a value stored as JSON in the local storage of the browser.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+  let json = local_storage()?.get_item(key).ok()??;
+  serde_json::from_str(&json).ok()
+}
+
+pub fn store<T: Serialize>(key: &str, value: &T) {
+  if let Some(storage) = local_storage() {
+    let json = serde_json::to_string(value).expect("stored values should serialize");
+    let _ = storage.set_item(key, &json);
+  }
+}
+
+pub fn remove(key: &str) {
+  if let Some(storage) = local_storage() {
+    let _ = storage.remove_item(key);
+  }
+}
@@ -0,0 +1,184 @@
+use crate::analysis::Analysis;
+use crate::die::Die;
+use crate::event_log::EventLog;
+use crate::replay::download_link;
+use crate::score_card::ScoreCard;
+use dioxus::events::MouseEvent;
+use dioxus::prelude::*;
+use jatsi_shared::chat::ChatMessage;
+use jatsi_shared::game::{Game, PlayerMessage, State};
+use jatsi_shared::i18n::Language;
+use jatsi_shared::rules::{PlacementOrder, Scoring};
+
+#[derive(Props)]
+pub struct TableProps<'a> {
+  pub game: &'a UseRef<Game>,
+  /// Index of the player using this client, once joined.
+  pub me: Option<usize>,
+  /// The name of the player using this client, telling apart the members of a team.
+  #[props(default)]
+  pub name: Option<String>,
+  /// The language of row names and explanations.
+  pub language: Language,
+  /// Called with the actions of the player. The game is expected to change only once the leader commits them.
+  pub onaction: EventHandler<'a, PlayerMessage>,
+  /// Chat of the room, saved along with the game.
+  #[props(default)]
+  pub chat: Option<&'a UseRef<Vec<ChatMessage>>>,
+}
+
+// The game as seen by one player.
+#[allow(non_snake_case)]
+pub fn Table<'a>(cx: Scope<'a, TableProps<'a>>) -> Element {
+  let game = cx.props.game;
+  let me = cx.props.me;
+  let language = cx.props.language;
+
+  let send = move |msg: PlayerMessage| cx.props.onaction.call(msg);
+  let my_turn = me == Some(game.read().player_in_turn)
+    && match game.read().member_in_turn() {
+      Some(member) => cx.props.name.as_deref() == Some(member),
+      None => true,
+    };
+
+  // The dice that count on the row of the score card the pointer is on
+  let highlighted = use_state(&cx, Vec::<usize>::new);
+
+  let dice = game
+    .read()
+    .roll
+    .iter()
+    .zip(game.read().keep.iter())
+    .zip(game.read().ruleset.dice.iter())
+    .enumerate()
+    .map(|(ind, ((&value, &kept), die))| {
+      rsx!(Die {
+        die: die.clone(),
+        value: value,
+        wild: game.read().ruleset.is_wild(value),
+        keep: kept,
+        highlight: highlighted.get().contains(&ind),
+        onclick: move |_: MouseEvent| if my_turn {
+          send(PlayerMessage::ToggleHold(ind))
+        },
+      })
+    })
+    .collect::<Vec<LazyNodes>>();
+
+  let roll = if my_turn && matches!(game.read().state, State::FirstRoll | State::Reroll) {
+    Some(rsx!(div {
+      class: "container",
+
+      button {
+        onclick: move |_| send(PlayerMessage::Roll),
+        prevent_default: "onclick",
+        "Toss a die for your witcher!"
+      }
+    }))
+  } else {
+    None
+  };
+
+  // Announcing happens between the first roll and the reroll in rulesets that require it
+  let announced_row = use_state(&cx, || None::<usize>);
+  let announce = {
+    let game = game.read();
+    let can_announce = game.ruleset.order == PlacementOrder::Announced
+      && game.state == State::Reroll
+      && game.times_rolled == 1
+      && game.announced.is_none();
+    let free_rows = game
+      .players
+      .get(game.player_in_turn)
+      .map(|player| {
+        game
+          .ruleset
+          .scorings
+          .iter()
+          .enumerate()
+          .filter(|(row, scoring)| {
+            !matches!(scoring, Scoring::Bonus { .. })
+              && player
+                .score_sheet
+                .iter()
+                .any(|column| column[*row].is_none())
+          })
+          .map(|(row, _)| (row, game.ruleset.row_name(row, language)))
+          .collect::<Vec<(usize, String)>>()
+      })
+      .unwrap_or_default();
+    let chosen = (*announced_row.get())
+      .filter(|row| free_rows.iter().any(|(free, _)| free == row))
+      .or_else(|| free_rows.first().map(|&(row, _)| row));
+
+    match chosen {
+      Some(chosen) if my_turn && can_announce => {
+        let options = free_rows.into_iter().map(|(row, name)| {
+          rsx!(option {
+            value: "{row}",
+            "{name}"
+          })
+        });
+        Some(rsx!(div {
+          class: "container announce",
+
+          select {
+            value: "{chosen}",
+            onchange: move |evt| announced_row.set(evt.value.parse().ok()),
+            options
+          }
+          button {
+            onclick: move |_| send(PlayerMessage::Announce(chosen)),
+            prevent_default: "onclick",
+            "Announce"
+          }
+        }))
+      }
+      _ => None,
+    }
+  };
+
+  let analysis = if game.read().state == State::End {
+    let mut saved_game = game.read().save();
+    if let Some(chat) = cx.props.chat {
+      saved_game.chat = chat.read().clone();
+    }
+    let link = download_link(&saved_game);
+    Some(rsx!(
+      Analysis { game: game, language: language }
+      div {
+        class: "container",
+
+        a {
+          href: "{link}",
+          download: "jatsi-game.json",
+          "Save this game to watch it again later"
+        }
+      }
+    ))
+  } else {
+    None
+  };
+
+  cx.render(rsx!(
+    div {
+      ScoreCard {
+        game: game,
+        language: language,
+        onplace: move |(column, row)| if my_turn { send(PlayerMessage::PlaceInColumn(column, row)) },
+        onhighlight: move |dice| highlighted.set(dice),
+      }
+
+      div {
+        class: "dice",
+
+        dice
+      }
+
+      announce
+      roll
+      EventLog { game: game, language: language }
+      analysis
+    }
+  ))
+}
@@ -0,0 +1,235 @@
+use dioxus::prelude::*;
+use dioxus_websocket_hooks::use_ws_context;
+use jatsi_shared::protocol::ClientMessage;
+use jatsi_shared::tournament::{Format, TieBreak, Tournament, TournamentSettings};
+
+pub fn default_settings() -> TournamentSettings {
+  TournamentSettings {
+    table_size: 4,
+    format: Format::Bracket { advancing: 2 },
+    tie_breaks: vec![TieBreak::TotalPoints],
+  }
+}
+
+#[derive(Props)]
+pub struct SettingsFormProps<'a> {
+  pub settings: &'a UseRef<TournamentSettings>,
+}
+
+// Choosing the format, table size and tie-breaks of a new tournament.
+#[allow(non_snake_case)]
+pub fn SettingsForm<'a>(cx: Scope<'a, SettingsFormProps<'a>>) -> Element {
+  let settings = cx.props.settings;
+  let current = settings.read().clone();
+
+  let (format, count_label, count) = match current.format {
+    Format::Bracket { advancing } => ("bracket", "Players advancing from each table", advancing),
+    Format::Swiss { rounds } => ("swiss", "Rounds", rounds),
+  };
+  let error = current
+    .validate()
+    .err()
+    .map(|err| rsx!(p { class: "error", "{err}" }));
+
+  let tie_breaks = TieBreak::all().into_iter().map(|tie_break| {
+    let checked = current.tie_breaks.contains(&tie_break);
+    let name = tie_break.name();
+    rsx!(label {
+      input {
+        r#type: "checkbox",
+        checked: "{checked}",
+        onchange: move |_| {
+          let mut settings = settings.write();
+          if checked {
+            settings.tie_breaks.retain(|other| *other != tie_break);
+          } else {
+            settings.tie_breaks.push(tie_break);
+          }
+        },
+      }
+      " {name}"
+    })
+  });
+
+  cx.render(rsx!(
+    label { "Players per table" }
+    input {
+      r#type: "number",
+      min: "2",
+      value: "{current.table_size}",
+      oninput: move |evt| settings.write().table_size = evt.value.parse().unwrap_or(0),
+    }
+    select {
+      value: "{format}",
+      onchange: move |evt| {
+        settings.write().format = match evt.value.as_str() {
+          "swiss" => Format::Swiss { rounds: 3 },
+          _ => Format::Bracket { advancing: 1 },
+        }
+      },
+      option { value: "bracket", "Bracket: the best of each table advance" }
+      option { value: "swiss", "Swiss: everyone plays every round" }
+    }
+    label { "{count_label}" }
+    input {
+      r#type: "number",
+      min: "1",
+      value: "{count}",
+      oninput: move |evt| {
+        let count = evt.value.parse().unwrap_or(0);
+        let mut settings = settings.write();
+        settings.format = match settings.format {
+          Format::Bracket { .. } => Format::Bracket { advancing: count },
+          Format::Swiss { .. } => Format::Swiss { rounds: count },
+        };
+      },
+    }
+    p { "Ties are broken by, in the order checked:" }
+    tie_breaks
+    error
+  ))
+}
+
+#[derive(Props)]
+pub struct TournamentViewProps<'a> {
+  pub code: String,
+  pub tournament: &'a Tournament,
+  /// The name the player registered with.
+  pub name: String,
+}
+
+// Standings and the tables of the current round, with links to play at them.
+#[allow(non_snake_case)]
+pub fn TournamentView<'a>(cx: Scope<'a, TournamentViewProps<'a>>) -> Element {
+  let ws = use_ws_context(&cx);
+  let tournament = cx.props.tournament;
+  let code = &cx.props.code;
+  let me = tournament.player_index(&cx.props.name);
+
+  let standings = tournament
+    .standings()
+    .into_iter()
+    .enumerate()
+    .map(|(rank, standing)| {
+      let rank = rank + 1;
+      let class = if standing.eliminated || standing.forfeited {
+        "eliminated"
+      } else {
+        ""
+      };
+      let forfeited = if standing.forfeited {
+        " (forfeited)"
+      } else {
+        ""
+      };
+      rsx!(tr {
+        class: "{class}",
+        td { "{rank}" }
+        th { "{standing.name}{forfeited}" }
+        td { "{standing.games}" }
+        td { "{standing.wins}" }
+        td { "{standing.total_points}" }
+        td { "{standing.best_game}" }
+      })
+    });
+
+  let status = if tournament.is_finished() {
+    let winner = tournament
+      .standings()
+      .first()
+      .map(|standing| standing.name.clone());
+    rsx!(p { "The tournament is over. Congratulations, " [winner] "!" })
+  } else if !tournament.has_started() && me == Some(0) {
+    rsx!(button {
+      disabled: "{tournament.players.len() < 2}",
+      onclick: move |_| ws.send_json(&ClientMessage::StartTournament),
+      prevent_default: "onclick",
+      "Start the tournament"
+    })
+  } else if !tournament.has_started() {
+    rsx!(p { "Waiting for the organizer to start the tournament…" })
+  } else {
+    let round = tournament.rounds.len();
+    rsx!(p { "Round {round} is being played." })
+  };
+
+  let tables = tournament
+    .rounds
+    .last()
+    .into_iter()
+    .flatten()
+    .enumerate()
+    .map(|(index, table)| {
+      let number = index + 1;
+      let players = table.players.iter().enumerate().map(|(seat, &player)| {
+        let points = table
+          .points
+          .as_ref()
+          .map(|points| format!(" {}", points[seat]))
+          .unwrap_or_default();
+        let name = tournament.players[player].clone();
+        // Players can give up their own game, and the organizer anyone's
+        let may_forfeit = table.points.is_none() && (me == Some(player) || me == Some(0));
+        let forfeit = may_forfeit.then(|| {
+          let name = name.clone();
+          rsx!(button {
+            onclick: move |_| ws.send_json(&ClientMessage::Forfeit(name.clone())),
+            prevent_default: "onclick",
+            "Forfeit"
+          })
+        });
+        rsx!(li { "{name}{points} " forfeit })
+      });
+      let seated = me.map(|me| table.players.contains(&me)).unwrap_or(false);
+      let action = if seated && table.points.is_none() {
+        "Play"
+      } else {
+        "Watch"
+      };
+      let enter = table.room.clone().map(|room| {
+        let name = cx.props.name.clone();
+        rsx!(button {
+          onclick: move |_| ws.send_json(&ClientMessage::JoinRoom {
+            name: name.clone(),
+            code: room.clone(),
+            team: None,
+          }),
+          prevent_default: "onclick",
+          "{action}"
+        })
+      });
+      rsx!(li {
+        "Table {number}"
+        ol { players }
+        enter
+      })
+    });
+
+  cx.render(rsx!(
+    div {
+      class: "container tournament",
+
+      h2 { "Tournament {code}" }
+      p { "Others can join with the code {code} until the tournament starts." }
+      status
+
+      h2 { "Standings" }
+      table {
+        thead {
+          tr {
+            th { "#" }
+            th { "Player" }
+            th { "Games" }
+            th { "Wins" }
+            th { "Points" }
+            th { "Best" }
+          }
+        }
+        tbody { standings }
+      }
+
+      h2 { "Tables" }
+      ol { tables }
+    }
+  ))
+}